@@ -8,6 +8,13 @@ template Withdraw(k){
 	// public input
 	signal input root;
 	signal input nullifierHash;
+	// bound to the proof so a copied proof can not be redirected, see README
+	signal input recipient;
+	signal input relayer;
+	signal input fee;
+	signal input refund;
+	// 0, or the owner whose OTP the note is withdrawn with, committed to by the leaf
+	signal input guard;
 
 	// private input
 	signal input secret;
@@ -18,7 +25,7 @@ template Withdraw(k){
 	// root constrain
 	component leaf = MiMC7(91);
 	leaf.x_in <== secret;
-	leaf.k <== 0;
+	leaf.k <== guard;
 
     component computed_root = GetMerkleRoot(k);
     computed_root.leaf <== leaf.out;
@@ -42,6 +49,15 @@ template Withdraw(k){
 
 	nullifierHash === nullifier.out;
 	
+	// dummy squares, otherwise the optimizer drops the unconstrained inputs
+	signal recipientSquare;
+	signal relayerSquare;
+	signal feeSquare;
+	signal refundSquare;
+	recipientSquare <== recipient * recipient;
+	relayerSquare <== relayer * relayer;
+	feeSquare <== fee * fee;
+	refundSquare <== refund * refund;
 }
 
-component main {public [root,nullifierHash]} = Withdraw(8);
\ No newline at end of file
+component main {public [root,nullifierHash,recipient,relayer,fee,refund,guard]} = Withdraw(8);
\ No newline at end of file
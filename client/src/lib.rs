@@ -0,0 +1,20 @@
+//! Wallet side of the mixer pallet, for the MiMC7 `circom/mixer` circuit.
+//!
+//! A [`Note`] holds the secret of one deposit. Its [`Note::commitment`] is what `deposit` is called
+//! with, and the note is kept as the string of [`Note::to_string`] until it is withdrawn. A note
+//! made [`Note::guarded`] by an account is only withdrawn with that account's OTP. A
+//! [`WithdrawInput`] is built from the note and the path to its leaf, either from a local
+//! [`MerkleTree`] or from the `mixer_getMerklePath` RPC. It serializes to the `input.json` snarkjs
+//! and ark-circom read.
+//!
+//! Hashing is `pallet_mixer::mimc` and `pallet_mixer::merkle_tree`, the code the pallet runs.
+
+pub mod note;
+pub mod withdraw;
+
+pub use note::{Note, ParseNoteError};
+pub use pallet_mixer::{
+	merkle_tree::{MerklePath, MerkleTree, TREE_DEPTH},
+	PoolId,
+};
+pub use withdraw::{account_to_field, WithdrawInput, WithdrawInputError};
@@ -0,0 +1,237 @@
+//! Deposit notes and their string form.
+
+use core::{fmt, str::FromStr};
+
+use pallet_mixer::{
+	merkle_tree::{hash_leaves, Hasher},
+	mimc::{Mimc7, N_ROUNDS},
+	PoolId,
+};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use sp_core::{crypto::AccountId32, U256};
+
+use crate::withdraw::account_to_field;
+
+/// Start of every note string, followed by the version of its format.
+pub const NOTE_PREFIX: &str = "sandglass-note";
+
+/// Version of the note string written by [`Note::to_string`].
+pub const NOTE_VERSION: &str = "v1";
+
+/// The secret of one deposit into `pool_id`. Whoever knows it can withdraw the deposit, unless it
+/// is guarded: a non-zero `guard` is the field element of the account whose OTP has to approve
+/// the withdraw.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Note {
+	pub pool_id: PoolId,
+	pub secret: U256,
+	pub guard: U256,
+}
+
+/// Why a note string could not be read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseNoteError {
+	/// The string does not start with [`NOTE_PREFIX`].
+	MissingPrefix,
+	/// The note was written by a format this version can not read.
+	UnsupportedVersion(String),
+	/// The pool id is not a number.
+	InvalidPoolId,
+	/// The secret is not 32 bytes of `0x` prefixed hex.
+	InvalidSecret,
+	/// The guard is not 32 bytes of `0x` prefixed hex.
+	InvalidGuard,
+}
+
+impl fmt::Display for ParseNoteError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ParseNoteError::MissingPrefix => write!(f, "not a note, missing `{}`", NOTE_PREFIX),
+			ParseNoteError::UnsupportedVersion(version) =>
+				write!(f, "unsupported note version `{}`", version),
+			ParseNoteError::InvalidPoolId => write!(f, "invalid pool id"),
+			ParseNoteError::InvalidSecret => write!(f, "invalid secret"),
+			ParseNoteError::InvalidGuard => write!(f, "invalid guard"),
+		}
+	}
+}
+
+impl std::error::Error for ParseNoteError {}
+
+impl Note {
+	pub fn new(pool_id: PoolId, secret: U256) -> Self {
+		Note { pool_id, secret, guard: U256::zero() }
+	}
+
+	/// This note, withdrawn only by `owner` with its OTP through `withdraw_with_otp` or
+	/// `swap_with_otp`.
+	pub fn guarded(self, owner: &AccountId32) -> Self {
+		Note { guard: account_to_field(owner), ..self }
+	}
+
+	/// A note with a fresh secret from the operating system's generator.
+	pub fn generate(pool_id: PoolId) -> Self {
+		Note::generate_with(pool_id, &mut OsRng)
+	}
+
+	/// A note with a fresh secret from `rng`. The secret is 31 bytes, so it is always below the
+	/// field modulus and the circuit sees the same value.
+	pub fn generate_with<R: RngCore + CryptoRng>(pool_id: PoolId, rng: &mut R) -> Self {
+		let mut bytes = [0u8; 32];
+		rng.fill_bytes(&mut bytes[1..]);
+		Note::new(pool_id, U256::from_big_endian(&bytes))
+	}
+
+	/// The leaf the circuit computes for this note, `MiMC7(secret, guard)`.
+	pub fn commitment(&self) -> U256 {
+		hash_leaves(&Mimc7::new(N_ROUNDS), &self.secret, &self.guard)
+	}
+
+	/// [`Note::commitment`] as the big-endian bytes `deposit` takes.
+	pub fn commitment_bytes(&self) -> Vec<u8> {
+		to_bytes(self.commitment())
+	}
+
+	/// Nullifier hash published when withdrawing the leaf at `leaf_index` of a tree of `depth`.
+	///
+	/// The circuit hashes `Bits2Num(paths2_root_pos)` with the secret, and `mixer` sets the
+	/// position bits where the path node is a left child. That is `leaf_index` with its `depth`
+	/// bits flipped.
+	pub fn nullifier_hash(&self, leaf_index: u32, depth: usize) -> U256 {
+		let positions = (0..depth).fold(0u64, |acc, d| {
+			let bit = (leaf_index >> d) & 1;
+			acc | (u64::from(Hasher::MiMC7.path_position(bit)) << d)
+		});
+		hash_leaves(&Mimc7::new(N_ROUNDS), &U256::from(positions), &self.secret)
+	}
+
+	/// Read a note written by [`Note::to_string`].
+	pub fn parse(note: &str) -> Result<Self, ParseNoteError> {
+		note.parse()
+	}
+}
+
+// `sandglass-note-v1-<pool id>-0x<secret>`, the secret as 32 bytes of big-endian hex, followed by
+// `-0x<guard>` for guarded notes.
+impl fmt::Display for Note {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"{}-{}-{}-0x{}",
+			NOTE_PREFIX,
+			NOTE_VERSION,
+			self.pool_id,
+			hex::encode(to_bytes(self.secret))
+		)?;
+		if !self.guard.is_zero() {
+			write!(f, "-0x{}", hex::encode(to_bytes(self.guard)))?;
+		}
+		Ok(())
+	}
+}
+
+// Keeps the secret out of logs.
+impl fmt::Debug for Note {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("Note").field("pool_id", &self.pool_id).finish_non_exhaustive()
+	}
+}
+
+impl FromStr for Note {
+	type Err = ParseNoteError;
+
+	fn from_str(note: &str) -> Result<Self, Self::Err> {
+		let rest = note
+			.strip_prefix(NOTE_PREFIX)
+			.and_then(|rest| rest.strip_prefix('-'))
+			.ok_or(ParseNoteError::MissingPrefix)?;
+		let mut parts = rest.splitn(4, '-');
+		let version = parts.next().unwrap_or_default();
+		if version != NOTE_VERSION {
+			return Err(ParseNoteError::UnsupportedVersion(version.to_string()))
+		}
+
+		let pool_id = parts
+			.next()
+			.and_then(|pool_id| pool_id.parse().ok())
+			.ok_or(ParseNoteError::InvalidPoolId)?;
+		let secret = parts.next().and_then(parse_hex).ok_or(ParseNoteError::InvalidSecret)?;
+		let guard = match parts.next() {
+			Some(guard) => parse_hex(guard).ok_or(ParseNoteError::InvalidGuard)?,
+			None => U256::zero(),
+		};
+
+		Ok(Note { pool_id, secret, guard })
+	}
+}
+
+/// 32 bytes of `0x` prefixed hex.
+fn parse_hex(value: &str) -> Option<U256> {
+	let bytes = hex::decode(value.strip_prefix("0x")?).ok()?;
+	(bytes.len() == 32).then(|| U256::from_big_endian(&bytes))
+}
+
+fn to_bytes(value: U256) -> Vec<u8> {
+	let mut bytes = [0u8; 32];
+	value.to_big_endian(&mut bytes);
+	bytes.to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_note_string_round_trip() {
+		let note = Note::generate(3);
+		let string = note.to_string();
+		assert!(string.starts_with("sandglass-note-v1-3-0x"));
+		assert_eq!(Note::parse(&string), Ok(note.clone()));
+		assert!(!format!("{:?}", note).contains(&note.secret.to_string()));
+	}
+
+	#[test]
+	fn test_parse_rejects_malformed_notes() {
+		let secret = format!("0x{}", "00".repeat(31) + "2a");
+		assert_eq!(
+			Note::parse(&format!("sandglass-note-v1-0-{}", secret)),
+			Ok(Note::new(0, U256::from(42)))
+		);
+
+		assert_eq!(Note::parse("tornado-eth-0.1-1-0x00"), Err(ParseNoteError::MissingPrefix));
+		assert_eq!(
+			Note::parse(&format!("sandglass-note-v2-0-{}", secret)),
+			Err(ParseNoteError::UnsupportedVersion("v2".into()))
+		);
+		assert_eq!(
+			Note::parse(&format!("sandglass-note-v1-x-{}", secret)),
+			Err(ParseNoteError::InvalidPoolId)
+		);
+		assert_eq!(Note::parse("sandglass-note-v1-0-0x2a"), Err(ParseNoteError::InvalidSecret));
+		assert_eq!(Note::parse("sandglass-note-v1-0"), Err(ParseNoteError::InvalidSecret));
+		assert_eq!(
+			Note::parse(&format!("sandglass-note-v1-0-{}-0x2a", secret)),
+			Err(ParseNoteError::InvalidGuard)
+		);
+	}
+
+	#[test]
+	fn test_guarded_note() {
+		let note = Note::new(1, U256::from(42));
+		let owner = AccountId32::new([1; 32]);
+		let guarded = note.clone().guarded(&owner);
+
+		assert_eq!(guarded.guard, account_to_field(&owner));
+		assert_ne!(guarded.commitment(), note.commitment());
+		assert_eq!(guarded.nullifier_hash(0, 8), note.nullifier_hash(0, 8));
+		assert_eq!(Note::parse(&guarded.to_string()), Ok(guarded.clone()));
+		assert_eq!(Note::parse(&note.to_string()), Ok(note));
+	}
+
+	#[test]
+	fn test_generated_secret_is_a_field_element() {
+		for _ in 0..16 {
+			assert!(Note::generate(0).secret < U256::one() << 248);
+		}
+	}
+}
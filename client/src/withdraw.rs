@@ -0,0 +1,265 @@
+//! Inputs of the `Withdraw(k)` circuit in `circom/mixer`.
+
+use core::fmt;
+
+use pallet_mixer::merkle_tree::{Hasher, MerklePath, MerkleTree};
+use serde::{Serialize, Serializer};
+use sp_core::{crypto::AccountId32, hashing::blake2_256, U256};
+
+use crate::note::Note;
+
+/// Every signal of the withdraw circuit, public ones first. Serializes to the `input.json` of
+/// snarkjs, field elements as decimal strings.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct WithdrawInput {
+	#[serde(serialize_with = "decimal")]
+	pub root: U256,
+	#[serde(rename = "nullifierHash", serialize_with = "decimal")]
+	pub nullifier_hash: U256,
+	#[serde(serialize_with = "decimal")]
+	pub recipient: U256,
+	#[serde(serialize_with = "decimal")]
+	pub relayer: U256,
+	#[serde(serialize_with = "decimal")]
+	pub fee: U256,
+	#[serde(serialize_with = "decimal")]
+	pub refund: U256,
+	#[serde(serialize_with = "decimal")]
+	pub guard: U256,
+	#[serde(serialize_with = "decimal")]
+	pub secret: U256,
+	#[serde(serialize_with = "decimals")]
+	pub paths2_root: Vec<U256>,
+	pub paths2_root_pos: Vec<u8>,
+}
+
+/// Why a note can not be withdrawn with a path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WithdrawInputError {
+	/// The leaf is not the commitment of the note.
+	CommitmentMismatch,
+	/// The index is outside of the tree.
+	LeafIndexOutOfRange,
+}
+
+impl fmt::Display for WithdrawInputError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			WithdrawInputError::CommitmentMismatch =>
+				write!(f, "the leaf is not the commitment of the note"),
+			WithdrawInputError::LeafIndexOutOfRange => write!(f, "leaf index out of range"),
+		}
+	}
+}
+
+impl std::error::Error for WithdrawInputError {}
+
+impl WithdrawInput {
+	/// Withdraw `note`, deposited at `index` of `tree`, against the current root of `tree`. The
+	/// proof is bound to no recipient, relayer, fee or refund until [`WithdrawInput::bind`].
+	pub fn from_tree(
+		tree: &MerkleTree,
+		index: usize,
+		note: &Note,
+	) -> Result<Self, WithdrawInputError> {
+		if index >= 1 << tree.depth() {
+			return Err(WithdrawInputError::LeafIndexOutOfRange)
+		}
+		if tree.get_leaf(0, index) != note.commitment() {
+			return Err(WithdrawInputError::CommitmentMismatch)
+		}
+
+		Ok(WithdrawInput::new(note, tree.get_root(), index as u32, tree.get_proof(index)))
+	}
+
+	/// Withdraw `note` along a path read from the `mixer_getMerklePath` RPC.
+	pub fn from_path(path: &MerklePath, note: &Note) -> Result<Self, WithdrawInputError> {
+		if path.leaf != note.commitment() {
+			return Err(WithdrawInputError::CommitmentMismatch)
+		}
+
+		Ok(WithdrawInput::new(note, path.root, path.leaf_index, path.path_elements.clone()))
+	}
+
+	fn new(note: &Note, root: U256, leaf_index: u32, paths2_root: Vec<U256>) -> Self {
+		let depth = paths2_root.len();
+		WithdrawInput {
+			root,
+			nullifier_hash: note.nullifier_hash(leaf_index, depth),
+			recipient: U256::zero(),
+			relayer: U256::zero(),
+			fee: U256::zero(),
+			refund: U256::zero(),
+			guard: note.guard,
+			secret: note.secret,
+			paths2_root,
+			paths2_root_pos: (0..depth)
+				.map(|d| Hasher::MiMC7.path_position((leaf_index >> d) & 1))
+				.collect(),
+		}
+	}
+
+	/// Bind the proof to the accounts and amounts of the withdraw call it is submitted with.
+	pub fn bind(
+		mut self,
+		recipient: &AccountId32,
+		relayer: &AccountId32,
+		fee: u128,
+		refund: u128,
+	) -> Self {
+		self.recipient = account_to_field(recipient);
+		self.relayer = account_to_field(relayer);
+		self.fee = U256::from(fee);
+		self.refund = U256::from(refund);
+		self
+	}
+
+	/// The public inputs in the order the pallet verifies them.
+	pub fn public_inputs(&self) -> Vec<U256> {
+		vec![
+			self.root,
+			self.nullifier_hash,
+			self.recipient,
+			self.relayer,
+			self.fee,
+			self.refund,
+			self.guard,
+		]
+	}
+
+	/// Every signal by name, the shape ark-circom's `CircomBuilder::push_input` is fed with.
+	pub fn signals(&self) -> Vec<(&'static str, Vec<U256>)> {
+		vec![
+			("root", vec![self.root]),
+			("nullifierHash", vec![self.nullifier_hash]),
+			("recipient", vec![self.recipient]),
+			("relayer", vec![self.relayer]),
+			("fee", vec![self.fee]),
+			("refund", vec![self.refund]),
+			("guard", vec![self.guard]),
+			("secret", vec![self.secret]),
+			("paths2_root", self.paths2_root.clone()),
+			("paths2_root_pos", self.paths2_root_pos.iter().map(|pos| U256::from(*pos)).collect()),
+		]
+	}
+
+	/// The snarkjs `input.json`.
+	pub fn to_json(&self) -> String {
+		serde_json::to_string_pretty(self).expect("inputs are plain strings and numbers")
+	}
+}
+
+/// The field element the pallet's `account_to_field` maps an `AccountId32` to, the first 31
+/// bytes of the blake2 hash of the account.
+pub fn account_to_field(account: &AccountId32) -> U256 {
+	let hash = blake2_256(account.as_ref());
+	U256::from_big_endian(&hash[..31])
+}
+
+fn decimal<S: Serializer>(value: &U256, serializer: S) -> Result<S::Ok, S::Error> {
+	serializer.serialize_str(&value.to_string())
+}
+
+fn decimals<S: Serializer>(values: &[U256], serializer: S) -> Result<S::Ok, S::Error> {
+	serializer.collect_seq(values.iter().map(|value| value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use pallet_mixer::merkle_tree::{merkle_path, zero_hashes, TREE_DEPTH};
+
+	fn dec(value: &str) -> U256 {
+		U256::from_dec_str(value).unwrap()
+	}
+
+	#[test]
+	fn test_withdraw_input_matches_circuit_input() {
+		// the input of `circom/mixer/input.json`, the note with secret `0` as the only leaf
+		let note = Note::new(0, U256::zero());
+		let mut tree = MerkleTree::new(TREE_DEPTH);
+		tree.insert(note.commitment()).unwrap();
+
+		let input = WithdrawInput::from_tree(&tree, 0, &note).unwrap();
+
+		assert_eq!(
+			input.root,
+			dec("29809161057426986176292945311087526926144633513867365185258413607995934707132")
+		);
+		assert_eq!(
+			input.nullifier_hash,
+			dec("47383248954783409320757252323368067485491150229432134318939482346666131919279")
+		);
+		assert_eq!(input.paths2_root_pos, vec![1; TREE_DEPTH]);
+		assert_eq!(
+			input.paths2_root[2],
+			dec("42580357077486170992007689106671000699149712310491990755963808567395604912555")
+		);
+
+		let json: serde_json::Value = serde_json::from_str(&input.to_json()).unwrap();
+		assert_eq!(
+			json["root"],
+			"29809161057426986176292945311087526926144633513867365185258413607995934707132"
+		);
+		assert_eq!(json["recipient"], "0");
+		assert_eq!(json["paths2_root"][0], "0");
+		assert_eq!(json["paths2_root_pos"][7], 1);
+	}
+
+	#[test]
+	fn test_from_path_matches_from_tree() {
+		let notes = (0..3).map(|_| Note::generate(0)).collect::<Vec<_>>();
+		let mut tree = MerkleTree::new(TREE_DEPTH);
+		for note in notes.iter() {
+			tree.insert(note.commitment()).unwrap();
+		}
+		let leaves = notes.iter().map(Note::commitment).collect::<Vec<_>>();
+
+		for (index, note) in notes.iter().enumerate() {
+			let path = MerklePath {
+				root: tree.get_root(),
+				leaf: leaves[index],
+				leaf_index: index as u32,
+				path_elements: merkle_path(
+					Hasher::MiMC7,
+					leaves.clone(),
+					&zero_hashes(Hasher::MiMC7, TREE_DEPTH),
+					index,
+				),
+				path_positions: vec![],
+			};
+			assert_eq!(
+				WithdrawInput::from_path(&path, note),
+				WithdrawInput::from_tree(&tree, index, note)
+			);
+		}
+
+		assert_eq!(
+			WithdrawInput::from_tree(&tree, 1, &notes[0]),
+			Err(WithdrawInputError::CommitmentMismatch)
+		);
+		assert_eq!(
+			WithdrawInput::from_tree(&tree, 1 << TREE_DEPTH, &notes[0]),
+			Err(WithdrawInputError::LeafIndexOutOfRange)
+		);
+	}
+
+	#[test]
+	fn test_bind() {
+		let note = Note::new(0, U256::from(1));
+		let mut tree = MerkleTree::new(TREE_DEPTH);
+		tree.insert(note.commitment()).unwrap();
+		let recipient = AccountId32::new([2; 32]);
+		let relayer = AccountId32::new([3; 32]);
+
+		let input = WithdrawInput::from_tree(&tree, 0, &note)
+			.unwrap()
+			.bind(&recipient, &relayer, 100, 0);
+
+		assert_eq!(input.fee, U256::from(100));
+		assert_eq!(input.recipient, account_to_field(&recipient));
+		assert_ne!(input.recipient, input.relayer);
+		assert_eq!(input.public_inputs()[3], input.relayer);
+		assert_eq!(input.public_inputs()[6], U256::zero());
+	}
+}
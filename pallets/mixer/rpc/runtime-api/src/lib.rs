@@ -0,0 +1,23 @@
+//! Runtime API of the mixer pallet, for wallets building withdraw proofs.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet_mixer::{merkle_tree::MerklePath, PoolId};
+use sp_core::U256;
+
+sp_api::decl_runtime_apis! {
+	pub trait MixerApi {
+		/// Path of the leaf at `leaf_index` of `pool_id` to the current root.
+		fn get_merkle_path(pool_id: PoolId, leaf_index: u32) -> Option<MerklePath>;
+		/// Leaf index `commitment` was deposited at in `pool_id`.
+		fn get_leaf_index(pool_id: PoolId, commitment: U256) -> Option<u32>;
+		/// Whether `root` is still accepted by withdraws from `pool_id`.
+		fn is_known_root(pool_id: PoolId, root: U256) -> bool;
+		/// Whether the note with `nullifier_hash` was withdrawn from `pool_id`.
+		fn is_spent(pool_id: PoolId, nullifier_hash: U256) -> bool;
+		/// Root of the tree of `pool_id`, `None` for an unknown pool.
+		fn current_root(pool_id: PoolId) -> Option<U256>;
+		/// Number of commitments deposited into `pool_id`.
+		fn leaf_count(pool_id: PoolId) -> u32;
+	}
+}
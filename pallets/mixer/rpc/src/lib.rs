@@ -0,0 +1,143 @@
+//! RPC interface of the mixer pallet.
+//!
+//! Exposes the paths, roots and nullifier status a wallet needs to build a withdraw proof, so it
+//! does not have to replay every `Deposited` event.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::{error::ErrorObject, ErrorObjectOwned},
+};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::U256;
+use sp_runtime::traits::Block as BlockT;
+
+pub use pallet_mixer_rpc_runtime_api::{MerklePath, MixerApi as MixerRuntimeApi, PoolId};
+
+#[rpc(client, server)]
+pub trait MixerApi<BlockHash> {
+	#[method(name = "mixer_getMerklePath")]
+	fn get_merkle_path(
+		&self,
+		pool_id: PoolId,
+		leaf_index: u32,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<MerklePath>>;
+
+	#[method(name = "mixer_getLeafIndex")]
+	fn get_leaf_index(
+		&self,
+		pool_id: PoolId,
+		commitment: U256,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<u32>>;
+
+	#[method(name = "mixer_isKnownRoot")]
+	fn is_known_root(&self, pool_id: PoolId, root: U256, at: Option<BlockHash>) -> RpcResult<bool>;
+
+	#[method(name = "mixer_isSpent")]
+	fn is_spent(
+		&self,
+		pool_id: PoolId,
+		nullifier_hash: U256,
+		at: Option<BlockHash>,
+	) -> RpcResult<bool>;
+
+	#[method(name = "mixer_currentRoot")]
+	fn current_root(&self, pool_id: PoolId, at: Option<BlockHash>) -> RpcResult<Option<U256>>;
+
+	#[method(name = "mixer_leafCount")]
+	fn leaf_count(&self, pool_id: PoolId, at: Option<BlockHash>) -> RpcResult<u32>;
+}
+
+/// Provides RPC methods to query the mixer pools.
+pub struct Mixer<C, Block> {
+	client: Arc<C>,
+	_marker: PhantomData<Block>,
+}
+
+impl<C, Block> Mixer<C, Block> {
+	/// Creates a new instance of the Mixer RPC helper.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+/// Error code of a failed runtime call.
+const RUNTIME_ERROR: i32 = 1;
+
+fn runtime_error(e: sp_api::ApiError) -> ErrorObjectOwned {
+	ErrorObject::owned(RUNTIME_ERROR, "Unable to query the mixer.", Some(e.to_string()))
+}
+
+impl<C, Block> MixerApiServer<<Block as BlockT>::Hash> for Mixer<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: MixerRuntimeApi<Block>,
+{
+	fn get_merkle_path(
+		&self,
+		pool_id: PoolId,
+		leaf_index: u32,
+		at: Option<Block::Hash>,
+	) -> RpcResult<Option<MerklePath>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		self.client
+			.runtime_api()
+			.get_merkle_path(at, pool_id, leaf_index)
+			.map_err(runtime_error)
+	}
+
+	fn get_leaf_index(
+		&self,
+		pool_id: PoolId,
+		commitment: U256,
+		at: Option<Block::Hash>,
+	) -> RpcResult<Option<u32>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		self.client
+			.runtime_api()
+			.get_leaf_index(at, pool_id, commitment)
+			.map_err(runtime_error)
+	}
+
+	fn is_known_root(
+		&self,
+		pool_id: PoolId,
+		root: U256,
+		at: Option<Block::Hash>,
+	) -> RpcResult<bool> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		self.client
+			.runtime_api()
+			.is_known_root(at, pool_id, root)
+			.map_err(runtime_error)
+	}
+
+	fn is_spent(
+		&self,
+		pool_id: PoolId,
+		nullifier_hash: U256,
+		at: Option<Block::Hash>,
+	) -> RpcResult<bool> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		self.client
+			.runtime_api()
+			.is_spent(at, pool_id, nullifier_hash)
+			.map_err(runtime_error)
+	}
+
+	fn current_root(&self, pool_id: PoolId, at: Option<Block::Hash>) -> RpcResult<Option<U256>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		self.client.runtime_api().current_root(at, pool_id).map_err(runtime_error)
+	}
+
+	fn leaf_count(&self, pool_id: PoolId, at: Option<Block::Hash>) -> RpcResult<u32> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		self.client.runtime_api().leaf_count(at, pool_id).map_err(runtime_error)
+	}
+}
@@ -1,35 +1,329 @@
-//! Benchmarking setup for pallet-template
+//! Benchmarking setup for pallet-mixer
+//!
+//! Withdraws verify proofs made with the trapdoor of
+//! [`zk_verifier::benchmarking::simulated_verification_key`], so they run the same pairing checks
+//! as real withdraws.
 #![cfg(feature = "runtime-benchmarks")]
 use super::*;
 
-#[allow(unused)]
-use crate::Pallet as Template;
+use crate::{
+	merkle_tree::{Hasher, MAX_TREE_DEPTH},
+	Pallet as Mixer,
+};
 use frame_benchmarking::v2::*;
+use frame_support::traits::{EnsureOrigin, Get};
 use frame_system::RawOrigin;
+use primitives::{CircuitId, VerificationKeyRegistry};
+use sp_runtime::traits::{Saturating, Zero};
+use sp_std::vec;
+use zk_verifier::{
+	benchmarking::{rejecting_verification_key, simulated_proof, simulated_verification_key},
+	EncodedProof,
+};
+
+/// Registry id the benchmark pools verify their withdraws with.
+const CIRCUIT_ID: CircuitId = 0;
+
+/// Pool of the native currency with a tree of `depth`, created the way `create_pool` does.
+fn new_pool<T: Config>(depth: u32) -> PoolId {
+	let pool_id = NextPoolId::<T>::get();
+	let currency_id = T::GetNativeCurrencyId::get();
+	let denomination = T::Currency::minimum_balance(currency_id).saturating_mul(1_000u32.into());
+	Pools::<T>::insert(
+		pool_id,
+		PoolInfo {
+			currency_id,
+			denomination,
+			hasher: Hasher::MiMC7,
+			depth,
+			circuit_id: CIRCUIT_ID,
+		},
+	);
+	NextPoolId::<T>::put(pool_id + 1);
+	pool_id
+}
+
+/// `who` holding `deposits` denominations of the pool currency.
+fn fund<T: Config>(pool_id: PoolId, who: &T::AccountId, deposits: u32) {
+	let pool = Pools::<T>::get(pool_id).expect("pool was created");
+	let amount = pool.denomination.saturating_mul(deposits.saturating_add(1).into());
+	T::Currency::deposit(pool.currency_id, who, amount).expect("deposit into the account");
+}
+
+/// Registers `k` keys for the withdraw circuit of the deepest trees, the simulated key the oldest
+/// of them, so proofs are checked against all `k` before one verifies.
+fn set_verification_keys<T: Config>(k: u32) {
+	let keys = (1..k)
+		.map(|nth| rejecting_verification_key(WITHDRAW_PUBLIC_INPUTS, nth.into()))
+		.chain([simulated_verification_key(WITHDRAW_PUBLIC_INPUTS)])
+		.map(|key| key.to_bytes())
+		.collect();
+	T::VerificationKeys::set_verification_keys(
+		CIRCUIT_ID,
+		WITHDRAW_PUBLIC_INPUTS as u32,
+		Some(T::MaxTreeDepth::get()),
+		keys,
+	);
+}
+
+/// Deposits `n` notes into `pool_id` and returns the root after the last of them.
+fn deposit_notes<T: Config>(pool_id: PoolId, n: u32) -> U256 {
+	fund::<T>(pool_id, &account_id::<T>(), n);
+	let pool = Pools::<T>::get(pool_id).expect("pool was created");
+	let mut root = U256::zero();
+	for i in 0..n {
+		let commitment = U256::from(i + 1);
+		let (index, new_root) =
+			insert_commitment::<T>(pool_id, &pool, commitment).expect("tree is not full");
+		Commitments::<T>::insert(pool_id, commitment, index);
+		root = new_root;
+		push_root::<T>(pool_id, root);
+	}
+	root
+}
+
+/// Proof of withdrawing the note with `nullifier_hash` from under `root`.
+fn withdraw_proof<T: Config>(
+	root: U256,
+	nullifier_hash: U256,
+	receiver: &T::AccountId,
+	relayer: &T::AccountId,
+	fee: BalanceOf<T>,
+) -> EncodedProof {
+	simulated_proof(withdraw_public_inputs::<T>(
+		root,
+		nullifier_hash,
+		receiver,
+		relayer,
+		fee,
+		Zero::zero(),
+		None,
+	))
+}
+
+/// A reason of `MaxBlackListReasonLength` bytes.
+fn longest_reason<T: Config>() -> Vec<u8> {
+	vec![b'x'; T::MaxBlackListReasonLength::get() as usize]
+}
+
+/// Blacklists `n` accounts with the longest reason.
+fn black_list<T: Config>(n: u32) -> Vec<T::AccountId> {
+	(0..n)
+		.map(|i| {
+			let acc: T::AccountId = account("blacklisted", i, 0);
+			let reason = BoundedVec::truncate_from(longest_reason::<T>());
+			BlackList::<T>::insert(&acc, reason);
+			acc
+		})
+		.collect()
+}
+
+fn to_bytes(value: U256) -> Vec<u8> {
+	let mut bytes = [0u8; 32];
+	value.to_big_endian(&mut bytes);
+	bytes.to_vec()
+}
 
 #[benchmarks]
 mod benchmarks {
 	use super::*;
 
+	/// The first deposit into a pool of depth 1, hashing a deeper tree is `merkle_insert`.
 	#[benchmark]
-	fn do_something() {
-		let value = 100u32.into();
+	fn deposit() {
 		let caller: T::AccountId = whitelisted_caller();
+		let pool_id = new_pool::<T>(1);
+		fund::<T>(pool_id, &caller, 1);
+		ZeroHashes::<T>::remove(Hasher::MiMC7);
+		let commitment = U256::from(42);
+
 		#[extrinsic_call]
-		do_something(RawOrigin::Signed(caller), value);
+		deposit(RawOrigin::Signed(caller), pool_id, to_bytes(commitment));
 
-		assert_eq!(Something::<T>::get(), Some(value));
+		assert!(Commitments::<T>::contains_key(pool_id, commitment));
 	}
 
+	/// A withdraw whose proof only verifies against the last of `k` usable keys.
 	#[benchmark]
-	fn cause_error() {
-		Something::<T>::put(100u32);
+	fn withdraw(k: Linear<1, { T::VerificationKeys::max_usable_keys() }>) {
 		let caller: T::AccountId = whitelisted_caller();
+		let receiver: T::AccountId = account("receiver", 0, 0);
+		let pool_id = new_pool::<T>(T::MaxTreeDepth::get());
+		let root = deposit_notes::<T>(pool_id, 1);
+		set_verification_keys::<T>(k);
+		let fee = Pools::<T>::get(pool_id).expect("pool was created").denomination / 2u32.into();
+		let nullifier_hash = U256::from(7);
+		let proof = withdraw_proof::<T>(root, nullifier_hash, &receiver, &caller, fee);
+
+		#[extrinsic_call]
+		withdraw(
+			RawOrigin::Signed(caller.clone()),
+			pool_id,
+			proof,
+			to_bytes(root),
+			to_bytes(nullifier_hash),
+			receiver,
+			caller.clone(),
+			fee,
+			Zero::zero(),
+		);
+
+		assert!(NullifierHashes::<T>::contains_key(pool_id, nullifier_hash));
+	}
+
+	#[benchmark]
+	fn withdraw_unsigned(k: Linear<1, { T::VerificationKeys::max_usable_keys() }>) {
+		let relayer: T::AccountId = account("relayer", 0, 0);
+		let receiver: T::AccountId = account("receiver", 0, 0);
+		let pool_id = new_pool::<T>(T::MaxTreeDepth::get());
+		let root = deposit_notes::<T>(pool_id, 1);
+		set_verification_keys::<T>(k);
+		let fee = Pools::<T>::get(pool_id).expect("pool was created").denomination / 2u32.into();
+		let nullifier_hash = U256::from(7);
+		let proof = withdraw_proof::<T>(root, nullifier_hash, &receiver, &relayer, fee);
+
+		#[extrinsic_call]
+		withdraw_unsigned(
+			RawOrigin::None,
+			pool_id,
+			proof,
+			to_bytes(root),
+			to_bytes(nullifier_hash),
+			receiver,
+			relayer,
+			fee,
+		);
+
+		assert!(NullifierHashes::<T>::contains_key(pool_id, nullifier_hash));
+	}
+
+	#[benchmark]
+	fn batch_withdraw(
+		n: Linear<1, { T::MaxBatchWithdraws::get() }>,
+		k: Linear<1, { T::VerificationKeys::max_usable_keys() }>,
+	) {
+		let caller: T::AccountId = whitelisted_caller();
+		let pool_id = new_pool::<T>(T::MaxTreeDepth::get());
+		let root = deposit_notes::<T>(pool_id, n);
+		set_verification_keys::<T>(k);
+		let fee = Pools::<T>::get(pool_id).expect("pool was created").denomination / 2u32.into();
+		let withdraws: Vec<_> = (0..n)
+			.map(|i| {
+				let receiver: T::AccountId = account("receiver", i, 0);
+				let nullifier_hash = U256::from(i + 1);
+				BatchedWithdraw {
+					proof: withdraw_proof::<T>(root, nullifier_hash, &receiver, &caller, fee),
+					root: to_bytes(root),
+					nullifier_hash: to_bytes(nullifier_hash),
+					receiver,
+					fee,
+				}
+			})
+			.collect();
+
+		#[extrinsic_call]
+		batch_withdraw(RawOrigin::Signed(caller), pool_id, withdraws);
+
+		assert!(NullifierHashes::<T>::contains_key(pool_id, U256::from(n)));
+	}
+
+	#[benchmark]
+	fn add_black_list() -> Result<(), BenchmarkError> {
+		let origin =
+			T::BlackListOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let acc: T::AccountId = account("blacklisted", 0, 0);
+
+		#[extrinsic_call]
+		add_black_list(origin as T::RuntimeOrigin, acc.clone(), longest_reason::<T>());
+
+		assert!(BlackList::<T>::contains_key(acc));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn remove_black_list() -> Result<(), BenchmarkError> {
+		let origin =
+			T::BlackListOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let acc = black_list::<T>(1).remove(0);
+
+		#[extrinsic_call]
+		remove_black_list(origin as T::RuntimeOrigin, acc.clone());
+
+		assert!(!BlackList::<T>::contains_key(acc));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn add_black_list_batch(
+		n: Linear<1, { T::MaxBlackListBatch::get() }>,
+	) -> Result<(), BenchmarkError> {
+		let origin =
+			T::BlackListOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let entries: Vec<_> =
+			(0..n).map(|i| (account("blacklisted", i, 0), longest_reason::<T>())).collect();
+
 		#[extrinsic_call]
-		cause_error(RawOrigin::Signed(caller));
+		add_black_list_batch(origin as T::RuntimeOrigin, entries);
+
+		assert_eq!(BlackList::<T>::iter_keys().count() as u32, n);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn remove_black_list_batch(
+		n: Linear<1, { T::MaxBlackListBatch::get() }>,
+	) -> Result<(), BenchmarkError> {
+		let origin =
+			T::BlackListOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let accs = black_list::<T>(n);
+
+		#[extrinsic_call]
+		remove_black_list_batch(origin as T::RuntimeOrigin, accs);
+
+		assert_eq!(BlackList::<T>::iter_keys().count(), 0);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn create_pool() -> Result<(), BenchmarkError> {
+		let origin =
+			T::CreatePoolOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let currency_id = T::GetNativeCurrencyId::get();
+		let denomination =
+			T::Currency::minimum_balance(currency_id).saturating_mul(1_000u32.into());
+		let pool_id = NextPoolId::<T>::get();
+
+		#[extrinsic_call]
+		create_pool(
+			origin as T::RuntimeOrigin,
+			currency_id,
+			denomination,
+			Hasher::MiMC7,
+			T::MaxTreeDepth::get(),
+			CIRCUIT_ID,
+		);
+
+		assert!(Pools::<T>::contains_key(pool_id));
+		Ok(())
+	}
+
+	/// The first insertion into a pool of depth `d`, computing its zero hashes on the way, one
+	/// hash and one stored node per level each. Deposits add it at `MaxTreeDepth` to `deposit`.
+	#[benchmark]
+	fn merkle_insert(d: Linear<1, { MAX_TREE_DEPTH as u32 }>) {
+		let pool_id = new_pool::<T>(d);
+		let pool = Pools::<T>::get(pool_id).expect("pool was created");
+		ZeroHashes::<T>::remove(Hasher::MiMC7);
+		let index;
+
+		#[block]
+		{
+			index =
+				insert_commitment::<T>(pool_id, &pool, U256::from(42)).expect("tree is empty").0;
+		}
 
-		assert_eq!(Something::<T>::get(), Some(101u32));
+		assert_eq!(index, 0);
 	}
 
-	impl_benchmark_test_suite!(Template, crate::mock::new_test_ext(), crate::mock::Test);
+	impl_benchmark_test_suite!(Mixer, crate::mock::new_test_ext(), crate::mock::Test);
 }
@@ -0,0 +1,219 @@
+//! Transaction pool checks of mixer calls.
+
+use super::*;
+use codec::{Decode, Encode};
+use frame_support::traits::IsSubType;
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{DispatchInfoOf, SignedExtension, Zero},
+	transaction_validity::{
+		InvalidTransaction, TransactionValidity, TransactionValidityError, ValidTransaction,
+	},
+};
+use sp_std::{fmt, marker::PhantomData};
+
+/// `InvalidTransaction::Custom` code of calls signed by, paying or relaying for a blacklisted
+/// account.
+pub const BLACKLISTED: u8 = 1;
+
+/// Rejects mixer calls from or to blacklisted accounts, of spent nullifiers, of unknown roots and
+/// with refunds the signer does not pay when they are validated, so they neither enter the pool nor
+/// a block and nobody pays fees for them. Proofs are left to dispatch.
+///
+/// Signed withdraws and swaps provide no tag. Their proofs are unchecked here, so a tag of the note
+/// would let anyone evict a pending spend with a junk proof and a higher tip. Spends of the same
+/// note may share the pool, the first one dispatched wins and the others fail paying their fees.
+/// Only `withdraw_unsigned`, whose proof `validate_unsigned` verifies, claims its note.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct CheckMixerCall<T: Config + Send + Sync>(PhantomData<T>);
+
+impl<T: Config + Send + Sync> CheckMixerCall<T> {
+	pub fn new() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<T: Config + Send + Sync> Default for CheckMixerCall<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T: Config + Send + Sync> fmt::Debug for CheckMixerCall<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "CheckMixerCall")
+	}
+}
+
+impl<T: Config + Send + Sync> SignedExtension for CheckMixerCall<T>
+where
+	T::RuntimeCall: IsSubType<Call<T>>,
+{
+	const IDENTIFIER: &'static str = "CheckMixerCall";
+	type AccountId = T::AccountId;
+	type Call = T::RuntimeCall;
+	type AdditionalSigned = ();
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		_info: &DispatchInfoOf<Self::Call>,
+		_len: usize,
+	) -> TransactionValidity {
+		match call.is_sub_type() {
+			Some(call) => validate_call::<T>(who, call),
+			None => Ok(ValidTransaction::default()),
+		}
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<(), TransactionValidityError> {
+		self.validate(who, call, info, len).map(|_| ())
+	}
+}
+
+fn validate_call<T: Config>(who: &T::AccountId, call: &Call<T>) -> TransactionValidity {
+	match call {
+		Call::deposit { .. } |
+		Call::deposit_with_naive_otp { .. } |
+		Call::deposit_with_block_time_otp { .. } => {
+			ensure_not_blacklisted::<T>(who)?;
+			Ok(ValidTransaction::default())
+		},
+		Call::withdraw {
+			pool_id, root, nullifier_hash, receiver, relayer, fee, refund, ..
+		} => validate_withdraw::<T>(
+			who,
+			*pool_id,
+			root,
+			nullifier_hash,
+			receiver,
+			relayer,
+			*fee,
+			*refund,
+			None,
+		),
+		// the signer is the guard, its password is checked at dispatch
+		Call::withdraw_with_otp {
+			pool_id,
+			root,
+			nullifier_hash,
+			receiver,
+			relayer,
+			fee,
+			refund,
+			..
+		} => validate_withdraw::<T>(
+			who,
+			*pool_id,
+			root,
+			nullifier_hash,
+			receiver,
+			relayer,
+			*fee,
+			*refund,
+			Some(who),
+		),
+		// the signer relays swaps for free
+		Call::swap { pool_id, root, nullifier_hash, receiver, .. } => validate_withdraw::<T>(
+			who,
+			*pool_id,
+			root,
+			nullifier_hash,
+			receiver,
+			who,
+			Zero::zero(),
+			Zero::zero(),
+			None,
+		),
+		Call::swap_with_otp { pool_id, root, nullifier_hash, receiver, .. } =>
+			validate_withdraw::<T>(
+				who,
+				*pool_id,
+				root,
+				nullifier_hash,
+				receiver,
+				who,
+				Zero::zero(),
+				Zero::zero(),
+				Some(who),
+			),
+		Call::batch_withdraw { pool_id, withdraws } => {
+			ensure_not_blacklisted::<T>(who)?;
+			let pool = get_pool::<T>(*pool_id).map_err(invalid_withdraw::<T>)?;
+			let mut nullifier_hashes = Vec::with_capacity(withdraws.len());
+			for withdraw in withdraws {
+				let (nullifier_hash, _) = check_withdraw_inputs::<T>(
+					*pool_id,
+					&pool,
+					&withdraw.root,
+					&withdraw.nullifier_hash,
+					&withdraw.receiver,
+					who,
+					withdraw.fee,
+					Zero::zero(),
+					None,
+				)
+				.map_err(invalid_withdraw::<T>)?;
+				if nullifier_hashes.contains(&nullifier_hash) {
+					return InvalidTransaction::Stale.into()
+				}
+				nullifier_hashes.push(nullifier_hash);
+			}
+			Ok(ValidTransaction::default())
+		},
+		_ => Ok(ValidTransaction::default()),
+	}
+}
+
+/// The checks of a single withdraw or swap of `pool_id` signed by `who`, all but its proof.
+fn validate_withdraw<T: Config>(
+	who: &T::AccountId,
+	pool_id: PoolId,
+	root: &[u8],
+	nullifier_hash: &[u8],
+	receiver: &T::AccountId,
+	relayer: &T::AccountId,
+	fee: BalanceOf<T>,
+	refund: BalanceOf<T>,
+	owner: Option<&T::AccountId>,
+) -> TransactionValidity {
+	ensure_not_blacklisted::<T>(who)?;
+	// the relayer forwards the refund itself
+	if !refund.is_zero() && who != relayer {
+		return Err(invalid_withdraw::<T>(Error::<T>::InvalidRefund.into()))
+	}
+	let pool = get_pool::<T>(pool_id).map_err(invalid_withdraw::<T>)?;
+	check_withdraw_inputs::<T>(
+		pool_id,
+		&pool,
+		root,
+		nullifier_hash,
+		receiver,
+		relayer,
+		fee,
+		refund,
+		owner,
+	)
+	.map_err(invalid_withdraw::<T>)?;
+	Ok(ValidTransaction::default())
+}
+
+fn ensure_not_blacklisted<T: Config>(who: &T::AccountId) -> Result<(), TransactionValidityError> {
+	if BlackList::<T>::contains_key(who) {
+		return Err(InvalidTransaction::Custom(BLACKLISTED).into())
+	}
+	Ok(())
+}
@@ -61,49 +61,53 @@ mod benchmarking;
 pub mod weights;
 pub use weights::*;
 
-pub mod common;
-pub mod deserialization;
+pub mod extension;
 pub mod merkle_tree;
+pub mod migrations;
 pub mod mimc;
-pub mod verify;
+pub mod poseidon;
 
-use frame_support::{
-	storage::bounded_vec::BoundedVec,
-	traits::{Currency, ExistenceRequirement::AllowDeath, ReservableCurrency},
-};
+pub use extension::CheckMixerCall;
+
+use frame_support::storage::bounded_vec::BoundedVec;
+use orml_traits::MultiCurrency;
 use sp_std::vec::Vec;
 
 type PublicInputsDef<T> = BoundedVec<u8, <T as Config>::MaxPublicInputsLength>;
-type ProofDef<T> = BoundedVec<u8, <T as Config>::MaxProofLength>;
-type VerificationKeyDef<T> = BoundedVec<u8, <T as Config>::MaxVerificationKeyLength>;
 pub type BalanceOf<T> =
-	<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+	<<T as Config>::Currency as MultiCurrency<<T as frame_system::Config>::AccountId>>::Balance;
+pub type CurrencyIdOf<T> =
+	<<T as Config>::Currency as MultiCurrency<<T as frame_system::Config>::AccountId>>::CurrencyId;
+pub type PoolId = u32;
 
 // All pallet logic is defined in its own module and must be annotated by the `pallet` attribute.
 #[frame_support::pallet]
 pub mod pallet {
 	// Import various useful types required by all FRAME pallets.
 	use super::*;
-	use crate::{
-		common::prepare_verification_key,
-		deserialization::{Proof, VKey},
-		merkle_tree::MerkleTree,
-		verify::{
-			prepare_public_inputs, verify, G1UncompressedBytes, G2UncompressedBytes, GProof,
-			VerificationKey, SUPPORTED_CURVE, SUPPORTED_PROTOCOL,
-		},
+	use crate::merkle_tree::{
+		zero_hashes, Hasher, IncrementalMerkleTree, MerklePath, MAX_TREE_DEPTH,
 	};
-	use frame_support::{pallet_prelude::*, PalletId};
+	use frame_support::{pallet_prelude::*, traits::UnixTime, PalletId};
 	use frame_system::pallet_prelude::*;
-	use primitives::{Otp, Swap};
-	use sp_runtime::traits::AccountIdConversion;
+	use primitives::{CircuitId, Otp, Swap, VerificationKeyRegistry};
+	use sp_runtime::{
+		traits::{AccountIdConversion, Zero},
+		SaturatedConversion,
+	};
 	use sp_std::vec;
+	use zk_verifier::{
+		batch_verify_any, decode_proof, verify_any, Curve, CurveProof, CurveVerificationError,
+		CurveVerificationKey, EncodedProof, ParseError,
+	};
 
-	//const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+	/// The in-code storage version.
+	pub const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
 
 	// The `Pallet` struct serves as a placeholder to implement traits, methods and dispatchables
 	// (`Call`s) in this pallet.
 	#[pallet::pallet]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(_);
 
 	/// The pallet's configuration trait.
@@ -125,51 +129,204 @@ pub mod pallet {
 		#[pallet::constant]
 		type MaxProofLength: Get<u32>;
 
-		/// The maximum length of the verification key.
 		#[pallet::constant]
-		type MaxVerificationKeyLength: Get<u32>;
+		type PalletId: Get<PalletId>;
 
+		/// The multi-currency mechanism pool deposits are moved through.
+		type Currency: MultiCurrency<Self::AccountId>;
+
+		/// Currency withdraw refunds are paid in.
 		#[pallet::constant]
-		type PalletId: Get<PalletId>;
+		type GetNativeCurrencyId: Get<CurrencyIdOf<Self>>;
+
+		/// Origin allowed to create new pools.
+		type CreatePoolOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// Priority of unsigned withdraws in the transaction pool.
+		#[pallet::constant]
+		type UnsignedPriority: Get<TransactionPriority>;
 
-		/// The currency mechanism.
-		type Currency: ReservableCurrency<Self::AccountId>;
+		/// Blocks an unsigned withdraw stays valid in the transaction pool for. Its root leaves the
+		/// `RootHistorySize` latest roots after as many deposits, so keep it short.
+		#[pallet::constant]
+		type UnsignedLongevity: Get<TransactionLongevity>;
 
+		/// Depth of the deepest tree a pool may be created with, deposits are weighed at it. At
+		/// most `MAX_TREE_DEPTH`.
 		#[pallet::constant]
-		type MixerBalance: Get<BalanceOf<Self>>;
+		type MaxTreeDepth: Get<u32>;
 
-		type SwapApi: Swap<BalanceOf<Self>, Self::AccountId>;
+		/// How many of the latest roots a withdraw may prove against, older ones are pruned.
+		#[pallet::constant]
+		type RootHistorySize: Get<u32>;
+
+		/// The maximum number of withdraws in one `batch_withdraw`.
+		#[pallet::constant]
+		type MaxBatchWithdraws: Get<u32>;
+
+		/// Origin allowed to add accounts to and remove them from the blacklist.
+		type BlackListOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// The maximum length of the reason an account was blacklisted for.
+		#[pallet::constant]
+		type MaxBlackListReasonLength: Get<u32>;
+
+		/// The maximum number of accounts in one blacklist batch call.
+		#[pallet::constant]
+		type MaxBlackListBatch: Get<u32>;
+
+		/// Registry the withdraw circuits' verification keys are read from.
+		type VerificationKeys: VerificationKeyRegistry;
+
+		type SwapApi: Swap<CurrencyIdOf<Self>, BalanceOf<Self>, Self::AccountId>;
 
 		type OtpApi: Otp<Self::AccountId>;
+
+		/// Clock the time of a deposit is read from.
+		type TimeProvider: UnixTime;
+	}
+
+	/// A mixer pool, every deposit into it locks exactly `denomination` of `currency_id`. Its
+	/// tree of `depth` levels is hashed with `hasher`, withdraws prove against it with the keys
+	/// registered under `circuit_id` for that depth.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct PoolInfo<CurrencyId, Balance> {
+		pub currency_id: CurrencyId,
+		pub denomination: Balance,
+		pub hasher: Hasher,
+		pub depth: u32,
+		pub circuit_id: CircuitId,
+	}
+
+	pub type PoolInfoOf<T> = PoolInfo<CurrencyIdOf<T>, BalanceOf<T>>;
+
+	/// One withdraw of a `batch_withdraw`, relayed by the signer of the batch.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub struct BatchedWithdraw<AccountId, Balance> {
+		pub proof: EncodedProof,
+		pub root: Vec<u8>,
+		pub nullifier_hash: Vec<u8>,
+		pub receiver: AccountId,
+		pub fee: Balance,
 	}
 
+	pub type BatchedWithdrawOf<T> =
+		BatchedWithdraw<<T as frame_system::Config>::AccountId, BalanceOf<T>>;
+
+	/// Why an account was blacklisted, free-form for the list's auditors.
+	pub type BlackListReasonOf<T> = BoundedVec<u8, <T as Config>::MaxBlackListReasonLength>;
+
+	/// Registered pools.
+	#[pallet::storage]
+	#[pallet::getter(fn pools)]
+	pub type Pools<T: Config> = StorageMap<_, Twox64Concat, PoolId, PoolInfoOf<T>>;
+
+	/// Id the next created pool will get.
+	#[pallet::storage]
+	#[pallet::getter(fn next_pool_id)]
+	pub type NextPoolId<T> = StorageValue<_, PoolId, ValueQuery>;
+
+	/// Roots still in `RootHistory`, for constant time membership checks.
 	#[pallet::storage]
 	#[pallet::getter(fn roots)]
-	pub type Roots<T: Config> = StorageMap<_, Blake2_128Concat, U256, bool>;
+	pub type Roots<T: Config> =
+		StorageDoubleMap<_, Twox64Concat, PoolId, Blake2_128Concat, U256, bool>;
+
+	/// Ring buffer of the last `RootHistorySize` roots of every pool.
+	#[pallet::storage]
+	#[pallet::getter(fn root_history)]
+	pub type RootHistory<T: Config> =
+		StorageDoubleMap<_, Twox64Concat, PoolId, Twox64Concat, u32, U256>;
+
+	/// Slot of `RootHistory` holding the current root of a pool.
+	#[pallet::storage]
+	#[pallet::getter(fn current_root_index)]
+	pub type CurrentRootIndex<T: Config> = StorageMap<_, Twox64Concat, PoolId, u32, ValueQuery>;
 
 	#[pallet::storage]
 	#[pallet::getter(fn nullifier_hashes)]
-	pub type NullifierHashes<T: Config> = StorageMap<_, Blake2_128Concat, U256, bool>;
+	pub type NullifierHashes<T: Config> =
+		StorageDoubleMap<_, Twox64Concat, PoolId, Blake2_128Concat, U256, bool>;
 
+	/// Leaf index of every commitment deposited into a pool.
 	#[pallet::storage]
 	#[pallet::getter(fn commitments)]
-	pub type Commitments<T: Config> = StorageMap<_, Blake2_128Concat, U256, bool>;
+	pub type Commitments<T: Config> =
+		StorageDoubleMap<_, Twox64Concat, PoolId, Blake2_128Concat, U256, u32>;
 
+	/// Index the next deposited commitment will be assigned in a pool's tree.
 	#[pallet::storage]
-	#[pallet::getter(fn merkle_vec)]
-	pub type MerkleVec<T> = StorageValue<_, BoundedVec<U256, ConstU32<{ u32::MAX }>>, ValueQuery>;
+	#[pallet::getter(fn next_index)]
+	pub type NextIndex<T> = StorageMap<_, Twox64Concat, PoolId, u32, ValueQuery>;
 
-	/// Storing a public input.
+	/// Right-most left-hand node of every level of a pool's tree, leaves first.
 	#[pallet::storage]
-	pub type PublicInputStorage<T: Config> = StorageValue<_, PublicInputsDef<T>, ValueQuery>;
+	pub type FilledSubtrees<T> = StorageMap<
+		_,
+		Twox64Concat,
+		PoolId,
+		BoundedVec<U256, ConstU32<{ MAX_TREE_DEPTH as u32 }>>,
+		ValueQuery,
+	>;
+
+	/// Roots of empty subtrees for every level, leaves first, as deep as the deepest tree they were
+	/// needed for. Shared by all pools hashing with the same hasher, a tree of depth `d` uses the
+	/// first `d + 1`.
+	#[pallet::storage]
+	pub type ZeroHashes<T> = StorageMap<
+		_,
+		Twox64Concat,
+		Hasher,
+		BoundedVec<U256, ConstU32<{ MAX_TREE_DEPTH as u32 + 1 }>>,
+		ValueQuery,
+	>;
+
+	/// Root of a pool's tree after its latest deposit.
+	#[pallet::storage]
+	#[pallet::getter(fn current_root)]
+	pub type CurrentRoot<T> = StorageMap<_, Twox64Concat, PoolId, U256, ValueQuery>;
+
+	/// Deposited commitments by pool and leaf index. Never read when inserting, the bottom level
+	/// of the paths `get_merkle_path` returns.
+	#[pallet::storage]
+	#[pallet::getter(fn leaves)]
+	pub type Leaves<T> = StorageDoubleMap<_, Twox64Concat, PoolId, Twox64Concat, u32, U256>;
+
+	/// Nodes between the leaves and the root of a pool's tree by level and index, level `1`
+	/// hashing two leaves. Rewritten along the path of every deposit, so they are those of the
+	/// current root. Nodes right of the latest leaf are never written, they are zero hashes.
+	#[pallet::storage]
+	pub type Nodes<T> = StorageDoubleMap<_, Twox64Concat, PoolId, Twox64Concat, (u32, u32), U256>;
 
-	/// Storing a verification key.
+	/// Storing a public input.
 	#[pallet::storage]
-	pub type VerificationKeyStorage<T: Config> = StorageValue<_, VerificationKeyDef<T>, ValueQuery>;
+	pub type PublicInputStorage<T: Config> = StorageValue<_, PublicInputsDef<T>, ValueQuery>;
 
+	/// Accounts that may not deposit, withdraw or relay, with the reason they were added for.
 	#[pallet::storage]
 	#[pallet::getter(fn blacklist)]
-	pub type BlackList<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, bool>;
+	pub type BlackList<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, BlackListReasonOf<T>>;
+
+	#[pallet::genesis_config]
+	#[derive(frame_support::DefaultNoBound)]
+	pub struct GenesisConfig<T: Config> {
+		/// Accounts blacklisted from the first block, with their reasons.
+		pub black_list: Vec<(T::AccountId, Vec<u8>)>,
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+		fn build(&self) {
+			for (who, reason) in &self.black_list {
+				let reason: BlackListReasonOf<T> = reason
+					.clone()
+					.try_into()
+					.expect("genesis blacklist reasons fit MaxBlackListReasonLength");
+				BlackList::<T>::insert(who, reason);
+			}
+		}
+	}
 
 	/// Events that functions in this pallet can emit.
 	///
@@ -184,11 +341,46 @@ pub mod pallet {
 	#[pallet::event]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
 	pub enum Event<T: Config> {
-		VerificationSetupCompleted,
-		Deposited { commitment: Vec<u8>, commit_h256: U256, root: U256 },
-		Withdrawed { receiver: T::AccountId },
-		Swaped { receiver: T::AccountId },
-		BlackListAdded,
+		PoolCreated {
+			pool_id: PoolId,
+			currency_id: CurrencyIdOf<T>,
+			denomination: BalanceOf<T>,
+			hasher: Hasher,
+			depth: u32,
+			circuit_id: CircuitId,
+		},
+		/// `commitment` was assigned `leaf_index` in the tree of `pool_id`, whose root became
+		/// `root`, at `timestamp` milliseconds.
+		Deposited {
+			pool_id: PoolId,
+			commitment: Vec<u8>,
+			commit_h256: U256,
+			leaf_index: u32,
+			root: U256,
+			timestamp: u64,
+		},
+		/// The note of `nullifier_hash` was paid out to `receiver`, `fee` of it to `relayer`.
+		Withdrawed {
+			pool_id: PoolId,
+			nullifier_hash: U256,
+			receiver: T::AccountId,
+			relayer: T::AccountId,
+			fee: BalanceOf<T>,
+		},
+		/// The note of `nullifier_hash` took `order_id` for `receiver`.
+		Swaped {
+			pool_id: PoolId,
+			nullifier_hash: U256,
+			order_id: u32,
+			receiver: T::AccountId,
+		},
+		BlackListAdded {
+			who: T::AccountId,
+			reason: BlackListReasonOf<T>,
+		},
+		BlackListRemoved {
+			who: T::AccountId,
+		},
 		PassOtpCheck,
 	}
 
@@ -210,8 +402,6 @@ pub mod pallet {
 		PublicInputsMismatch,
 		/// Public inputs vector is to long.
 		TooLongPublicInputs,
-		/// The verification key is to long.
-		TooLongVerificationKey,
 		/// The proof is too long.
 		TooLongProof,
 		/// The proof is too short.
@@ -250,6 +440,52 @@ pub mod pallet {
 		BlacklistRejected,
 		/// Amount must be equ
 		SwapAmountMustBeEqu,
+		/// Swap order pays out another currency than the pool holds
+		SwapCurrencyMismatch,
+		/// Relayer fee is larger than the pool denomination
+		FeeExceedsDenomination,
+		/// Refund is only paid by the relayer itself, and not in native pools
+		InvalidRefund,
+		/// No usable verification key was built for the pool's tree depth
+		TreeDepthMismatch,
+		/// No pool with the given id
+		PoolNotFound,
+		/// Pool denomination must not be zero
+		ZeroDenomination,
+		/// Proof is on another curve than the verification key
+		CurveMismatch,
+		/// A batch has no withdraws
+		EmptyBatch,
+		/// A batch has more than `MaxBatchWithdraws` withdraws
+		TooManyWithdraws,
+		/// Blacklist reason is longer than `MaxBlackListReasonLength`
+		TooLongBlackListReason,
+		/// A blacklist batch has more than `MaxBlackListBatch` accounts
+		TooManyBlackListEntries,
+		/// The account is not on the blacklist
+		NotBlackListed,
+		/// Root is longer than 32 bytes
+		MalformedRoot,
+		/// Nullifier hash is longer than 32 bytes
+		MalformedNullifierHash,
+		/// Commitment is longer than 32 bytes
+		MalformedCommitment,
+		/// Pool tree depth is zero or deeper than `MaxTreeDepth`
+		InvalidTreeDepth,
+		/// No usable verification key is on BLS12-381, the only curve pool roots can be proven on
+		UnsupportedCurve,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn integrity_test() {
+			let depth = T::MaxTreeDepth::get() as usize;
+			assert!(
+				depth > 0 && depth <= MAX_TREE_DEPTH,
+				"MaxTreeDepth must be in 1..=MAX_TREE_DEPTH"
+			);
+			assert!(T::RootHistorySize::get() > 0, "RootHistorySize must not be zero");
+		}
 	}
 
 	/// The pallet's dispatchable functions ([`Call`]s).
@@ -266,58 +502,29 @@ pub mod pallet {
 	/// The [`weight`] macro is used to assign a weight to each call.
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
-		#[pallet::call_index(0)]
-		#[pallet::weight(0)]
-		pub fn setup_verification(origin: OriginFor<T>, vec_vk: Vec<u8>) -> DispatchResult {
-			let _who = ensure_signed(origin)?;
-
-			let _vk = store_verification_key::<T>(vec_vk)?;
-			Self::deposit_event(Event::<T>::VerificationSetupCompleted);
-			Ok(())
-		}
-
 		#[pallet::call_index(1)]
-		#[pallet::weight(0)]
-		pub fn deposit(origin: OriginFor<T>, commitment: Vec<u8>) -> DispatchResult {
+		#[pallet::weight(deposit_weight::<T>())]
+		pub fn deposit(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			commitment: Vec<u8>,
+		) -> DispatchResult {
 			// Check that the extrinsic was signed and get the signer.
 			let who = ensure_signed(origin)?;
 
 			ensure!(!BlackList::<T>::contains_key(who.clone()), Error::<T>::BlacklistRejected);
+			let pool = get_pool::<T>(pool_id)?;
 
-			let c = U256::from_big_endian(&commitment);
-
-			ensure!(!Commitments::<T>::contains_key(c), Error::<T>::CommitmentHasBeanSubmitted);
-
-			MerkleVec::<T>::try_mutate(|merkle_vec| -> DispatchResult {
-				let _ = merkle_vec.try_push(c);
-				Ok(())
-			})?;
-
-			let merkle_vec = MerkleVec::<T>::get();
-			let _len = merkle_vec.len();
-
-			Commitments::<T>::insert(c, true);
-			let mut mt = MerkleTree::default();
-			for x in &merkle_vec {
-				let (_leaf, _index) = mt.insert(*x).unwrap();
-			}
-
-			let root = mt.get_root();
-			Roots::<T>::insert(root, true);
-
-			T::Currency::transfer(&who, &account_id::<T>(), T::MixerBalance::get(), AllowDeath)?;
-
-			Self::deposit_event(Event::<T>::Deposited { commitment, commit_h256: c, root });
-
-			Ok(())
+			do_deposit::<T>(who, pool_id, &pool, commitment)
 		}
 
 		#[pallet::call_index(2)]
-		#[pallet::weight(0)]
+		#[pallet::weight(deposit_weight::<T>().saturating_add(T::OtpApi::approval_weight()))]
 		pub fn deposit_with_naive_otp(
 			origin: OriginFor<T>,
+			pool_id: PoolId,
 			commitment: Vec<u8>,
-			otp_proof: Vec<u8>,
+			otp_proof: EncodedProof,
 			otp_root: Vec<u8>,
 			timestamp: u128,
 		) -> DispatchResult {
@@ -325,46 +532,21 @@ pub mod pallet {
 			let who = ensure_signed(origin)?;
 
 			ensure!(!BlackList::<T>::contains_key(who.clone()), Error::<T>::BlacklistRejected);
+			let pool = get_pool::<T>(pool_id)?;
 
 			T::OtpApi::naive_approval(who.clone(), otp_proof, otp_root, timestamp)?;
 			Self::deposit_event(Event::<T>::PassOtpCheck);
 
-			let c = U256::from_big_endian(&commitment);
-
-			ensure!(!Commitments::<T>::contains_key(c), Error::<T>::CommitmentHasBeanSubmitted);
-
-			MerkleVec::<T>::try_mutate(|merkle_vec| -> DispatchResult {
-				let _ = merkle_vec.try_push(c);
-				Ok(())
-			})?;
-
-			let merkle_vec = MerkleVec::<T>::get();
-			let _len = merkle_vec.len();
-
-			for x in &merkle_vec {
-				Commitments::<T>::insert(x, true);
-			}
-
-			Commitments::<T>::insert(c, true);
-			let mut mt = MerkleTree::default();
-			let (_leaf, _index) = mt.insert(U256::from_big_endian(&commitment)).unwrap();
-
-			let root = mt.get_root();
-			Roots::<T>::insert(root, true);
-
-			T::Currency::transfer(&who, &account_id::<T>(), T::MixerBalance::get(), AllowDeath)?;
-
-			Self::deposit_event(Event::<T>::Deposited { commitment, commit_h256: c, root });
-
-			Ok(())
+			do_deposit::<T>(who, pool_id, &pool, commitment)
 		}
 
 		#[pallet::call_index(3)]
-		#[pallet::weight(0)]
+		#[pallet::weight(deposit_weight::<T>().saturating_add(T::OtpApi::approval_weight()))]
 		pub fn deposit_with_block_time_otp(
 			origin: OriginFor<T>,
+			pool_id: PoolId,
 			commitment: Vec<u8>,
-			otp_proof: Vec<u8>,
+			otp_proof: EncodedProof,
 			otp_root: Vec<u8>,
 			timestamp: u128,
 		) -> DispatchResult {
@@ -372,226 +554,961 @@ pub mod pallet {
 			let who = ensure_signed(origin)?;
 
 			ensure!(!BlackList::<T>::contains_key(who.clone()), Error::<T>::BlacklistRejected);
+			let pool = get_pool::<T>(pool_id)?;
 
 			T::OtpApi::block_time_approval(who.clone(), otp_proof, otp_root, timestamp)?;
+			Self::deposit_event(Event::<T>::PassOtpCheck);
 
-			let c = U256::from_big_endian(&commitment);
+			do_deposit::<T>(who, pool_id, &pool, commitment)
+		}
 
-			ensure!(!Commitments::<T>::contains_key(c), Error::<T>::CommitmentHasBeanSubmitted);
+		#[pallet::call_index(4)]
+		#[pallet::weight(withdraw_weight::<T>())]
+		pub fn withdraw(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			proof: EncodedProof,
+			root: Vec<u8>,
+			nullifier_hash: Vec<u8>,
+			receiver: T::AccountId,
+			relayer: T::AccountId,
+			fee: BalanceOf<T>,
+			refund: BalanceOf<T>,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			log::info!("before check in withdraw");
 
-			MerkleVec::<T>::try_mutate(|merkle_vec| -> DispatchResult {
-				let _ = merkle_vec.try_push(c);
-				Ok(())
-			})?;
+			ensure!(!BlackList::<T>::contains_key(sender.clone()), Error::<T>::BlacklistRejected);
+			// the relayer forwards the refund itself
+			ensure!(refund.is_zero() || sender == relayer, Error::<T>::InvalidRefund);
+
+			let (pool, nullifier_hash) = check_withdraw::<T>(
+				pool_id,
+				&proof,
+				&root,
+				&nullifier_hash,
+				&receiver,
+				&relayer,
+				fee,
+				refund,
+				None,
+			)?;
+			do_withdraw::<T>(pool_id, &pool, nullifier_hash, receiver, relayer, fee, refund)
+		}
 
-			let merkle_vec = MerkleVec::<T>::get();
-			let _len = merkle_vec.len();
+		#[pallet::call_index(5)]
+		#[pallet::weight(withdraw_weight::<T>().saturating_add(T::SwapApi::take_order_weight()))]
+		pub fn swap(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			proof: EncodedProof,
+			root: Vec<u8>,
+			nullifier_hash: Vec<u8>,
+			order_id: u32,
+			receiver: T::AccountId,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
 
-			for x in &merkle_vec {
-				Commitments::<T>::insert(x, true);
-			}
+			let nullifier_hash = check_swap::<T>(
+				&sender,
+				pool_id,
+				&proof,
+				&root,
+				&nullifier_hash,
+				order_id,
+				&receiver,
+				None,
+			)?;
+			do_swap::<T>(pool_id, nullifier_hash, order_id, receiver)
+		}
+
+		/// Blacklist `acc` for `reason`, replacing the reason if it is already listed.
+		#[pallet::call_index(6)]
+		#[pallet::weight(T::WeightInfo::add_black_list())]
+		pub fn add_black_list(
+			origin: OriginFor<T>,
+			acc: T::AccountId,
+			reason: Vec<u8>,
+		) -> DispatchResult {
+			T::BlackListOrigin::ensure_origin(origin)?;
 
-			Commitments::<T>::insert(c, true);
-			let mut mt = MerkleTree::default();
-			let (_leaf, _index) = mt.insert(U256::from_big_endian(&commitment)).unwrap();
+			add_to_black_list::<T>(acc, reason)
+		}
 
-			let root = mt.get_root();
-			Roots::<T>::insert(root, true);
+		/// Create a pool of `denomination` notes of `currency_id`, whose tree of `depth` levels
+		/// is hashed with `hasher`. Its withdraws are verified with the keys of `circuit_id`, a
+		/// `Withdraw(depth)` circuit of the same hasher. All of them are fixed for the life of the
+		/// pool.
+		#[pallet::call_index(7)]
+		#[pallet::weight(T::WeightInfo::create_pool())]
+		pub fn create_pool(
+			origin: OriginFor<T>,
+			currency_id: CurrencyIdOf<T>,
+			denomination: BalanceOf<T>,
+			hasher: Hasher,
+			depth: u32,
+			circuit_id: CircuitId,
+		) -> DispatchResult {
+			T::CreatePoolOrigin::ensure_origin(origin)?;
 
-			T::Currency::transfer(&who, &account_id::<T>(), T::MixerBalance::get(), AllowDeath)?;
+			ensure!(!denomination.is_zero(), Error::<T>::ZeroDenomination);
+			ensure!(depth > 0 && depth <= T::MaxTreeDepth::get(), Error::<T>::InvalidTreeDepth);
 
-			Self::deposit_event(Event::<T>::Deposited { commitment, commit_h256: c, root });
+			let pool_id = NextPoolId::<T>::get();
+			let next_pool_id = pool_id.checked_add(1).ok_or(Error::<T>::StorageOverflow)?;
 
+			Pools::<T>::insert(
+				pool_id,
+				PoolInfo { currency_id, denomination, hasher, depth, circuit_id },
+			);
+			NextPoolId::<T>::put(next_pool_id);
+
+			Self::deposit_event(Event::<T>::PoolCreated {
+				pool_id,
+				currency_id,
+				denomination,
+				hasher,
+				depth,
+				circuit_id,
+			});
 			Ok(())
 		}
 
-		#[pallet::call_index(4)]
-		#[pallet::weight(0)]
-		pub fn withdraw(
+		/// `withdraw` without a signer, so a fresh `receiver` needs no funds to pay for it. The
+		/// proof is checked in `validate_unsigned` and `fee` is paid to `relayer` out of the
+		/// withdrawn amount.
+		#[pallet::call_index(8)]
+		#[pallet::weight(
+			T::WeightInfo::withdraw_unsigned(T::VerificationKeys::max_usable_keys())
+				.saturating_add(T::DbWeight::get().reads(UNSIGNED_PRE_DISPATCH_READS))
+		)]
+		pub fn withdraw_unsigned(
 			origin: OriginFor<T>,
-			proof: Vec<u8>,
+			pool_id: PoolId,
+			proof: EncodedProof,
 			root: Vec<u8>,
 			nullifier_hash: Vec<u8>,
 			receiver: T::AccountId,
+			relayer: T::AccountId,
+			fee: BalanceOf<T>,
 		) -> DispatchResult {
-			let sender = ensure_signed(origin)?;
-			log::info!("before check in withdraw");
+			ensure_none(origin)?;
+
+			let (pool, nullifier_hash) = check_withdraw::<T>(
+				pool_id,
+				&proof,
+				&root,
+				&nullifier_hash,
+				&receiver,
+				&relayer,
+				fee,
+				Zero::zero(),
+				None,
+			)?;
+			do_withdraw::<T>(pool_id, &pool, nullifier_hash, receiver, relayer, fee, Zero::zero())
+		}
 
-			ensure!(!BlackList::<T>::contains_key(sender.clone()), Error::<T>::BlacklistRejected);
-			ensure!(!BlackList::<T>::contains_key(receiver.clone()), Error::<T>::BlacklistRejected);
+		/// Several `withdraw`s from `pool_id` with the signer as relayer. Their proofs are verified
+		/// together, in one final exponentiation instead of one per proof, and the batch fails as a
+		/// whole if any of them is invalid.
+		#[pallet::call_index(9)]
+		#[pallet::weight(T::WeightInfo::batch_withdraw(
+			withdraws.len() as u32,
+			T::VerificationKeys::max_usable_keys(),
+		))]
+		pub fn batch_withdraw(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			withdraws: Vec<BatchedWithdrawOf<T>>,
+		) -> DispatchResult {
+			let relayer = ensure_signed(origin)?;
 
-			let nullifier_hash = U256::from_big_endian(&nullifier_hash);
-			log::info!("before check NoteHasBeanSpent");
+			ensure!(!BlackList::<T>::contains_key(relayer.clone()), Error::<T>::BlacklistRejected);
+			ensure!(!withdraws.is_empty(), Error::<T>::EmptyBatch);
 			ensure!(
-				!NullifierHashes::<T>::contains_key(nullifier_hash),
-				Error::<T>::NoteHasBeanSpent
+				withdraws.len() <= T::MaxBatchWithdraws::get() as usize,
+				Error::<T>::TooManyWithdraws
 			);
+			let pool = get_pool::<T>(pool_id)?;
+
+			let mut nullifier_hashes = Vec::with_capacity(withdraws.len());
+			let mut batch = Vec::with_capacity(withdraws.len());
+			for withdraw in &withdraws {
+				let (nullifier_hash, public_inputs) = check_withdraw_inputs::<T>(
+					pool_id,
+					&pool,
+					&withdraw.root,
+					&withdraw.nullifier_hash,
+					&withdraw.receiver,
+					&relayer,
+					withdraw.fee,
+					Zero::zero(),
+					None,
+				)?;
+				// the same note twice in one batch
+				ensure!(!nullifier_hashes.contains(&nullifier_hash), Error::<T>::NoteHasBeanSpent);
+				nullifier_hashes.push(nullifier_hash);
+				batch.push((parse_proof::<T>(&withdraw.proof)?, public_inputs));
+			}
 
-			log::info!("before U256::from_big_endian(&root);");
-			let root = U256::from_big_endian(&root);
-
-			log::info!("before proof");
-			let proof = parse_proof::<T>(proof)?;
-			log::info!("before get_verification_key");
-			let vk = get_verification_key::<T>()?;
-			let public_inputs = vec![root, nullifier_hash];
-			log::info!("before public_inputs");
-			let public_inputs = prepare_public_inputs(public_inputs);
-
-			log::info!("before verify");
-			match verify(vk, proof, public_inputs) {
-				Ok(true) => {
-					//Self::deposit_event(Event::<T>::VerificationSuccess { who: sender });
-					NullifierHashes::<T>::insert(nullifier_hash, true);
-					T::Currency::transfer(
-						&account_id::<T>(),
-						&receiver,
-						T::MixerBalance::get(),
-						AllowDeath,
-					)?;
-
-					Self::deposit_event(Event::<T>::Withdrawed { receiver });
-					log::info!("withdraw ok");
-
-					//Ok(())
-				},
-				Ok(false) => {
-					//Self::deposit_event(Event::<T>::VerificationFailed);
-					log::info!("before verify fail");
-
-					return Err(Error::<T>::ProofVerificationFalse.into())
-				},
-				Err(e) => {
-					log::info!("before verify error {:?}", e);
-					return Err(Error::<T>::ProofVerificationError.into())
-				},
-			};
+			let vks = get_verification_keys::<T>(&pool)?;
+			ensure!(
+				batch_verify_any(&vks, &batch).map_err(verification_error::<T>)?,
+				Error::<T>::ProofVerificationFalse
+			);
 
+			for (withdraw, nullifier_hash) in withdraws.into_iter().zip(nullifier_hashes) {
+				do_withdraw::<T>(
+					pool_id,
+					&pool,
+					nullifier_hash,
+					withdraw.receiver,
+					relayer.clone(),
+					withdraw.fee,
+					Zero::zero(),
+				)?;
+			}
 			Ok(())
 		}
 
-		#[pallet::call_index(5)]
-		#[pallet::weight(0)]
-		pub fn swap(
+		#[pallet::call_index(10)]
+		#[pallet::weight(T::WeightInfo::remove_black_list())]
+		pub fn remove_black_list(origin: OriginFor<T>, acc: T::AccountId) -> DispatchResult {
+			T::BlackListOrigin::ensure_origin(origin)?;
+
+			remove_from_black_list::<T>(acc)
+		}
+
+		/// `add_black_list` for every entry, all of them are added or none is.
+		#[pallet::call_index(11)]
+		#[pallet::weight(T::WeightInfo::add_black_list_batch(entries.len() as u32))]
+		pub fn add_black_list_batch(
+			origin: OriginFor<T>,
+			entries: Vec<(T::AccountId, Vec<u8>)>,
+		) -> DispatchResult {
+			T::BlackListOrigin::ensure_origin(origin)?;
+			ensure!(
+				entries.len() <= T::MaxBlackListBatch::get() as usize,
+				Error::<T>::TooManyBlackListEntries
+			);
+
+			for (acc, reason) in entries {
+				add_to_black_list::<T>(acc, reason)?;
+			}
+			Ok(())
+		}
+
+		/// `remove_black_list` for every account, all of them are removed or none is.
+		#[pallet::call_index(12)]
+		#[pallet::weight(T::WeightInfo::remove_black_list_batch(accs.len() as u32))]
+		pub fn remove_black_list_batch(
 			origin: OriginFor<T>,
-			proof: Vec<u8>,
+			accs: Vec<T::AccountId>,
+		) -> DispatchResult {
+			T::BlackListOrigin::ensure_origin(origin)?;
+			ensure!(
+				accs.len() <= T::MaxBlackListBatch::get() as usize,
+				Error::<T>::TooManyBlackListEntries
+			);
+
+			for acc in accs {
+				remove_from_black_list::<T>(acc)?;
+			}
+			Ok(())
+		}
+
+		/// `withdraw` of a note deposited with the signer as its guard, approved by the signer's
+		/// OTP. The proof is verified with the guard as its last public input, so only notes
+		/// committed to the signer are accepted. The OTP proof only commits to the OTP root and
+		/// timestamp, so the guard has to sign for a password copied from the pool to be useless.
+		#[pallet::call_index(14)]
+		#[pallet::weight(withdraw_weight::<T>().saturating_add(T::OtpApi::approval_weight()))]
+		pub fn withdraw_with_otp(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			proof: EncodedProof,
 			root: Vec<u8>,
 			nullifier_hash: Vec<u8>,
-			order_id: u32,
 			receiver: T::AccountId,
+			relayer: T::AccountId,
+			fee: BalanceOf<T>,
+			refund: BalanceOf<T>,
+			otp_proof: EncodedProof,
+			otp_root: Vec<u8>,
+			timestamp: u128,
 		) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
 
 			ensure!(!BlackList::<T>::contains_key(sender.clone()), Error::<T>::BlacklistRejected);
-			ensure!(!BlackList::<T>::contains_key(receiver.clone()), Error::<T>::BlacklistRejected);
+			// the relayer forwards the refund itself
+			ensure!(refund.is_zero() || sender == relayer, Error::<T>::InvalidRefund);
+
+			let (pool, nullifier_hash) = check_withdraw::<T>(
+				pool_id,
+				&proof,
+				&root,
+				&nullifier_hash,
+				&receiver,
+				&relayer,
+				fee,
+				refund,
+				Some(&sender),
+			)?;
+			approve_guard::<T>(sender, otp_proof, otp_root, timestamp)?;
+			do_withdraw::<T>(pool_id, &pool, nullifier_hash, receiver, relayer, fee, refund)
+		}
 
-			let nullifier_hash = U256::from_big_endian(&nullifier_hash);
-			ensure!(
-				!NullifierHashes::<T>::contains_key(nullifier_hash),
-				Error::<T>::NoteHasBeanSpent
-			);
+		/// `swap` of a note deposited with the signer as its guard, approved by the signer's OTP.
+		/// The note is checked before the OTP, a swap that fails does not use up the password.
+		#[pallet::call_index(15)]
+		#[pallet::weight(
+			withdraw_weight::<T>()
+				.saturating_add(T::SwapApi::take_order_weight())
+				.saturating_add(T::OtpApi::approval_weight())
+		)]
+		pub fn swap_with_otp(
+			origin: OriginFor<T>,
+			pool_id: PoolId,
+			proof: EncodedProof,
+			root: Vec<u8>,
+			nullifier_hash: Vec<u8>,
+			order_id: u32,
+			receiver: T::AccountId,
+			otp_proof: EncodedProof,
+			otp_root: Vec<u8>,
+			timestamp: u128,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
 
-			let root = U256::from_big_endian(&root);
-
-			let proof = parse_proof::<T>(proof)?;
-			let vk = get_verification_key::<T>()?;
-			let public_inputs = vec![root, nullifier_hash];
-			log::info!("before public_inputs");
-			let public_inputs = prepare_public_inputs(public_inputs);
-			match verify(vk, proof, public_inputs) {
-				Ok(true) => {
-					//Self::deposit_event(Event::<T>::VerificationSuccess { who: sender });
-					NullifierHashes::<T>::insert(nullifier_hash, true);
-
-					let amount = T::SwapApi::get_target_amount(order_id);
-
-					ensure!(amount == T::MixerBalance::get(), Error::<T>::SwapAmountMustBeEqu);
-
-					T::SwapApi::inter_take_order(account_id::<T>(), order_id, receiver)?;
-				},
-				Ok(false) => {
-					//Self::deposit_event(Event::<T>::VerificationFailed);
-					return Err(Error::<T>::ProofVerificationFalse.into())
-				},
-				Err(e) => {
-					log::info!("verify error {:?}", e);
-					return Err(Error::<T>::ProofVerificationError.into())
-				},
+			let nullifier_hash = check_swap::<T>(
+				&sender,
+				pool_id,
+				&proof,
+				&root,
+				&nullifier_hash,
+				order_id,
+				&receiver,
+				Some(&sender),
+			)?;
+			approve_guard::<T>(sender, otp_proof, otp_root, timestamp)?;
+			do_swap::<T>(pool_id, nullifier_hash, order_id, receiver)
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			let Call::withdraw_unsigned {
+				pool_id,
+				proof,
+				root,
+				nullifier_hash,
+				receiver,
+				relayer,
+				fee,
+			} = call
+			else {
+				return InvalidTransaction::Call.into()
 			};
 
+			let (_pool, nullifier_hash) = check_withdraw::<T>(
+				*pool_id,
+				proof,
+				root,
+				nullifier_hash,
+				receiver,
+				relayer,
+				*fee,
+				Zero::zero(),
+				None,
+			)
+			.map_err(invalid_withdraw::<T>)?;
+
+			ValidTransaction::with_tag_prefix("MixerWithdraw")
+				.priority(T::UnsignedPriority::get())
+				.and_provides((pool_id, nullifier_hash))
+				.longevity(T::UnsignedLongevity::get())
+				.propagate(true)
+				.build()
+		}
+
+		/// Only the storage checks of `validate_unsigned`, the proof is verified once, at dispatch.
+		fn pre_dispatch(call: &Self::Call) -> Result<(), TransactionValidityError> {
+			let Call::withdraw_unsigned {
+				pool_id,
+				root,
+				nullifier_hash,
+				receiver,
+				relayer,
+				fee,
+				..
+			} = call
+			else {
+				return Err(InvalidTransaction::Call.into())
+			};
+
+			let pool = get_pool::<T>(*pool_id).map_err(invalid_withdraw::<T>)?;
+			check_withdraw_inputs::<T>(
+				*pool_id,
+				&pool,
+				root,
+				nullifier_hash,
+				receiver,
+				relayer,
+				*fee,
+				Zero::zero(),
+				None,
+			)
+			.map_err(invalid_withdraw::<T>)?;
 			Ok(())
 		}
+	}
 
-		#[pallet::call_index(6)]
-		#[pallet::weight(0)]
-		pub fn add_black_list(origin: OriginFor<T>, acc: T::AccountId) -> DispatchResult {
-			let _who = ensure_signed(origin)?;
+	/// Storage reads of the `pre_dispatch` of `withdraw_unsigned`: the pool, the blacklist of
+	/// receiver and relayer, the nullifier and the root.
+	const UNSIGNED_PRE_DISPATCH_READS: u64 = 5;
+
+	/// Weight of a deposit into a tree of `MaxTreeDepth`, whose zero hashes are not cached yet.
+	fn deposit_weight<T: Config>() -> Weight {
+		T::WeightInfo::deposit()
+			.saturating_add(T::WeightInfo::merkle_insert(T::MaxTreeDepth::get()))
+	}
 
-			BlackList::<T>::insert(acc, true);
+	/// Weight of a withdraw whose proof is checked against every usable key of its circuit.
+	fn withdraw_weight<T: Config>() -> Weight {
+		T::WeightInfo::withdraw(T::VerificationKeys::max_usable_keys())
+	}
 
-			Self::deposit_event(Event::<T>::BlackListAdded);
-			Ok(())
+	pub(crate) fn invalid_withdraw<T: Config>(
+		error: sp_runtime::DispatchError,
+	) -> TransactionValidityError {
+		if error == Error::<T>::BlacklistRejected.into() {
+			InvalidTransaction::Custom(crate::extension::BLACKLISTED).into()
+		} else if error == Error::<T>::NoteHasBeanSpent.into() ||
+			error == Error::<T>::CanNotFindMerkelRoot.into()
+		{
+			InvalidTransaction::Stale.into()
+		} else if error == Error::<T>::ProofVerificationFalse.into() {
+			InvalidTransaction::BadProof.into()
+		} else {
+			InvalidTransaction::Call.into()
 		}
 	}
 
-	fn get_verification_key<T: Config>() -> Result<VerificationKey, sp_runtime::DispatchError> {
-		let vk = VerificationKeyStorage::<T>::get();
+	/// Check a withdraw proof against the pool without writing to storage, return the pool and
+	/// the nullifier hash it spends. `owner` is the guard the note was deposited with, if any.
+	pub(crate) fn check_withdraw<T: Config>(
+		pool_id: PoolId,
+		proof: &EncodedProof,
+		root: &[u8],
+		nullifier_hash: &[u8],
+		receiver: &T::AccountId,
+		relayer: &T::AccountId,
+		fee: BalanceOf<T>,
+		refund: BalanceOf<T>,
+		owner: Option<&T::AccountId>,
+	) -> Result<(PoolInfoOf<T>, U256), sp_runtime::DispatchError> {
+		let pool = get_pool::<T>(pool_id)?;
+		let (nullifier_hash, public_inputs) = check_withdraw_inputs::<T>(
+			pool_id,
+			&pool,
+			root,
+			nullifier_hash,
+			receiver,
+			relayer,
+			fee,
+			refund,
+			owner,
+		)?;
+
+		let proof = parse_proof::<T>(proof)?;
+		let vks = get_verification_keys::<T>(&pool)?;
+		ensure!(
+			verify_proof::<T>(&vks, &proof, public_inputs)?,
+			Error::<T>::ProofVerificationFalse
+		);
+
+		Ok((pool, nullifier_hash))
+	}
+
+	/// Everything `check_withdraw` checks but the proof, return the nullifier hash and the public
+	/// inputs the proof has to be verified with.
+	pub(crate) fn check_withdraw_inputs<T: Config>(
+		pool_id: PoolId,
+		pool: &PoolInfoOf<T>,
+		root: &[u8],
+		nullifier_hash: &[u8],
+		receiver: &T::AccountId,
+		relayer: &T::AccountId,
+		fee: BalanceOf<T>,
+		refund: BalanceOf<T>,
+		owner: Option<&T::AccountId>,
+	) -> Result<(U256, Vec<U256>), sp_runtime::DispatchError> {
+		ensure!(!BlackList::<T>::contains_key(receiver), Error::<T>::BlacklistRejected);
+		ensure!(!BlackList::<T>::contains_key(relayer), Error::<T>::BlacklistRejected);
+
+		ensure!(fee <= pool.denomination, Error::<T>::FeeExceedsDenomination);
+		// the refund is paid in native currency, which is pointless for native pools where the
+		// fee already covers it
+		ensure!(
+			refund.is_zero() || pool.currency_id != T::GetNativeCurrencyId::get(),
+			Error::<T>::InvalidRefund
+		);
+
+		let nullifier_hash = parse_u256::<T>(nullifier_hash, Error::<T>::MalformedNullifierHash)?;
+		ensure!(
+			!NullifierHashes::<T>::contains_key(pool_id, nullifier_hash),
+			Error::<T>::NoteHasBeanSpent
+		);
 
-		ensure!(!vk.is_empty(), Error::<T>::VerificationKeyIsNotSet);
-		let deserialized_vk = VKey::from_json_u8_slice(vk.as_slice())
-			.map_err(|_| Error::<T>::MalformedVerificationKey)?;
-		let vk = prepare_verification_key(deserialized_vk)
-			.map_err(|_| Error::<T>::VerificationKeyCreationError)?;
-		Ok(vk)
+		let root = parse_u256::<T>(root, Error::<T>::MalformedRoot)?;
+		ensure!(is_known_root::<T>(pool_id, root), Error::<T>::CanNotFindMerkelRoot);
+
+		let public_inputs = withdraw_public_inputs::<T>(
+			root,
+			nullifier_hash,
+			receiver,
+			relayer,
+			fee,
+			refund,
+			owner,
+		);
+		Ok((nullifier_hash, public_inputs))
+	}
+
+	/// Runs the Groth16 check against every usable key on the curve the proof was given on.
+	fn verify_proof<T: Config>(
+		vks: &[CurveVerificationKey],
+		proof: &CurveProof,
+		public_inputs: Vec<U256>,
+	) -> Result<bool, sp_runtime::DispatchError> {
+		verify_any(vks, proof, public_inputs).map_err(verification_error::<T>)
+	}
+
+	/// Maps a Groth16 verification failure to the pallet error.
+	fn verification_error<T: Config>(e: CurveVerificationError) -> sp_runtime::DispatchError {
+		log::info!("verify error {:?}", e);
+		match e {
+			CurveVerificationError::CurveMismatch => Error::<T>::CurveMismatch,
+			CurveVerificationError::PublicInputsOutOfRange => Error::<T>::MalformedPublicInputs,
+			CurveVerificationError::InvalidVerificationKey => Error::<T>::ProofVerificationError,
+		}
+		.into()
 	}
 
-	fn store_verification_key<T: Config>(
-		vec_vk: Vec<u8>,
-	) -> Result<VKey, sp_runtime::DispatchError> {
-		let vk: VerificationKeyDef<T> = vec_vk.try_into().map_err(|e| {
-			log::info!("@@@ store_verification_key err: {:?}", e);
-			Error::<T>::TooLongVerificationKey
-		})?;
-		let deserialized_vk = VKey::from_json_u8_slice(vk.as_slice())
-			.map_err(|_| Error::<T>::MalformedVerificationKey)?;
-		ensure!(deserialized_vk.curve == SUPPORTED_CURVE.as_bytes(), Error::<T>::NotSupportedCurve);
+	/// Append `commitment` to the tree of `pool_id` and lock the denomination of `who` for it. All
+	/// deposit calls end here, OTP gated ones after their approval, so every note joins the same
+	/// tree.
+	fn do_deposit<T: Config>(
+		who: T::AccountId,
+		pool_id: PoolId,
+		pool: &PoolInfoOf<T>,
+		commitment: Vec<u8>,
+	) -> DispatchResult {
+		let c = parse_u256::<T>(&commitment, Error::<T>::MalformedCommitment)?;
+
 		ensure!(
-			deserialized_vk.protocol == SUPPORTED_PROTOCOL.as_bytes(),
-			Error::<T>::NotSupportedProtocol
+			!Commitments::<T>::contains_key(pool_id, c),
+			Error::<T>::CommitmentHasBeanSubmitted
 		);
 
-		VerificationKeyStorage::<T>::put(vk);
-		Ok(deserialized_vk)
+		let (leaf_index, root) = insert_commitment::<T>(pool_id, pool, c)?;
+		Commitments::<T>::insert(pool_id, c, leaf_index);
+		push_root::<T>(pool_id, root);
+
+		T::Currency::transfer(pool.currency_id, &who, &account_id::<T>(), pool.denomination)?;
+
+		Pallet::<T>::deposit_event(Event::<T>::Deposited {
+			pool_id,
+			commitment,
+			commit_h256: c,
+			leaf_index,
+			root,
+			timestamp: T::TimeProvider::now().as_millis().saturated_into(),
+		});
+		Ok(())
+	}
+
+	/// Check the OTP of `owner`, the guard a note is withdrawn or swapped with.
+	fn approve_guard<T: Config>(
+		owner: T::AccountId,
+		otp_proof: EncodedProof,
+		otp_root: Vec<u8>,
+		timestamp: u128,
+	) -> DispatchResult {
+		T::OtpApi::block_time_approval(owner, otp_proof, otp_root, timestamp)?;
+		Pallet::<T>::deposit_event(Event::<T>::PassOtpCheck);
+		Ok(())
 	}
 
-	fn parse_proof<T: Config>(vec_proof: Vec<u8>) -> Result<GProof, sp_runtime::DispatchError> {
-		let proof: ProofDef<T> = vec_proof.try_into().map_err(|_| Error::<T>::TooLongProof)?;
-		let deserialized_proof =
-			Proof::from_json_u8_slice(proof.as_slice()).map_err(|_| Error::<T>::MalformedProof)?;
+	/// Spend `nullifier_hash` and pay out a withdraw that passed `check_withdraw`.
+	fn do_withdraw<T: Config>(
+		pool_id: PoolId,
+		pool: &PoolInfoOf<T>,
+		nullifier_hash: U256,
+		receiver: T::AccountId,
+		relayer: T::AccountId,
+		fee: BalanceOf<T>,
+		refund: BalanceOf<T>,
+	) -> DispatchResult {
+		NullifierHashes::<T>::insert(pool_id, nullifier_hash, true);
+		T::Currency::transfer(
+			pool.currency_id,
+			&account_id::<T>(),
+			&receiver,
+			pool.denomination - fee,
+		)?;
+		if !fee.is_zero() {
+			T::Currency::transfer(pool.currency_id, &account_id::<T>(), &relayer, fee)?;
+		}
+		if !refund.is_zero() {
+			T::Currency::transfer(T::GetNativeCurrencyId::get(), &relayer, &receiver, refund)?;
+		}
+
+		Pallet::<T>::deposit_event(Event::<T>::Withdrawed {
+			pool_id,
+			nullifier_hash,
+			receiver,
+			relayer,
+			fee,
+		});
+		log::info!("withdraw ok");
+		Ok(())
+	}
+
+	/// Check a swap of a note of `pool_id` on the order `order_id` for `receiver` without writing
+	/// to storage, `sender` relaying for free. Return the nullifier hash it spends. `owner` is the
+	/// guard the note was deposited with, if any.
+	fn check_swap<T: Config>(
+		sender: &T::AccountId,
+		pool_id: PoolId,
+		proof: &EncodedProof,
+		root: &[u8],
+		nullifier_hash: &[u8],
+		order_id: u32,
+		receiver: &T::AccountId,
+		owner: Option<&T::AccountId>,
+	) -> Result<U256, sp_runtime::DispatchError> {
+		ensure!(!BlackList::<T>::contains_key(sender), Error::<T>::BlacklistRejected);
+		ensure!(!BlackList::<T>::contains_key(receiver), Error::<T>::BlacklistRejected);
+		let pool = get_pool::<T>(pool_id)?;
+
+		let nullifier_hash = parse_u256::<T>(nullifier_hash, Error::<T>::MalformedNullifierHash)?;
 		ensure!(
-			deserialized_proof.curve == SUPPORTED_CURVE.as_bytes(),
-			Error::<T>::NotSupportedCurve
+			!NullifierHashes::<T>::contains_key(pool_id, nullifier_hash),
+			Error::<T>::NoteHasBeanSpent
+		);
+
+		let root = parse_u256::<T>(root, Error::<T>::MalformedRoot)?;
+		ensure!(is_known_root::<T>(pool_id, root), Error::<T>::CanNotFindMerkelRoot);
+
+		let proof = parse_proof::<T>(proof)?;
+		let vks = get_verification_keys::<T>(&pool)?;
+		// swaps are paid by the order, the submitter relays for free
+		let public_inputs = withdraw_public_inputs::<T>(
+			root,
+			nullifier_hash,
+			receiver,
+			sender,
+			Zero::zero(),
+			Zero::zero(),
+			owner,
 		);
 		ensure!(
-			deserialized_proof.protocol == SUPPORTED_PROTOCOL.as_bytes(),
-			Error::<T>::NotSupportedProtocol
+			verify_proof::<T>(&vks, &proof, public_inputs)?,
+			Error::<T>::ProofVerificationFalse
 		);
 
-		let proof = GProof::from_uncompressed(
-			&G1UncompressedBytes::new(deserialized_proof.a[0], deserialized_proof.a[1]),
-			&G2UncompressedBytes::new(
-				deserialized_proof.b[0][0],
-				deserialized_proof.b[0][1],
-				deserialized_proof.b[1][0],
-				deserialized_proof.b[1][1],
-			),
-			&G1UncompressedBytes::new(deserialized_proof.c[0], deserialized_proof.c[1]),
-		)
-		.map_err(|_| Error::<T>::ProofCreationError)?;
+		let currency_id = T::SwapApi::get_target_currency_id(order_id);
+		let amount = T::SwapApi::get_target_amount(order_id);
+
+		ensure!(currency_id == Some(pool.currency_id), Error::<T>::SwapCurrencyMismatch);
+		ensure!(amount == pool.denomination, Error::<T>::SwapAmountMustBeEqu);
+
+		Ok(nullifier_hash)
+	}
+
+	/// Spend `nullifier_hash` of `pool_id` on the swap order `order_id` for `receiver`, a swap that
+	/// passed `check_swap`.
+	fn do_swap<T: Config>(
+		pool_id: PoolId,
+		nullifier_hash: U256,
+		order_id: u32,
+		receiver: T::AccountId,
+	) -> DispatchResult {
+		NullifierHashes::<T>::insert(pool_id, nullifier_hash, true);
+
+		T::SwapApi::inter_take_order(account_id::<T>(), order_id, receiver.clone())?;
+
+		Pallet::<T>::deposit_event(Event::<T>::Swaped {
+			pool_id,
+			nullifier_hash,
+			order_id,
+			receiver,
+		});
+
+		Ok(())
+	}
+
+	fn add_to_black_list<T: Config>(who: T::AccountId, reason: Vec<u8>) -> DispatchResult {
+		let reason: BlackListReasonOf<T> =
+			reason.try_into().map_err(|_| Error::<T>::TooLongBlackListReason)?;
+
+		BlackList::<T>::insert(&who, &reason);
+
+		Pallet::<T>::deposit_event(Event::<T>::BlackListAdded { who, reason });
+		Ok(())
+	}
+
+	fn remove_from_black_list<T: Config>(who: T::AccountId) -> DispatchResult {
+		ensure!(BlackList::<T>::contains_key(&who), Error::<T>::NotBlackListed);
+
+		BlackList::<T>::remove(&who);
+
+		Pallet::<T>::deposit_event(Event::<T>::BlackListRemoved { who });
+		Ok(())
+	}
+
+	/// Number of public inputs of the withdraw circuit.
+	pub const WITHDRAW_PUBLIC_INPUTS: usize = 7;
+
+	/// Public inputs of the withdraw circuit, in the order of its `main` component. The leaf of a
+	/// note is the hash of its secret and a guard, `0` for plain notes and the field element of
+	/// `owner` for notes that need the OTP of `owner`.
+	pub fn withdraw_public_inputs<T: Config>(
+		root: U256,
+		nullifier_hash: U256,
+		recipient: &T::AccountId,
+		relayer: &T::AccountId,
+		fee: BalanceOf<T>,
+		refund: BalanceOf<T>,
+		owner: Option<&T::AccountId>,
+	) -> Vec<U256> {
+		vec![
+			root,
+			nullifier_hash,
+			account_to_field::<T>(recipient),
+			account_to_field::<T>(relayer),
+			U256::from(fee.saturated_into::<u128>()),
+			U256::from(refund.saturated_into::<u128>()),
+			owner.map_or_else(U256::zero, account_to_field::<T>),
+		]
+	}
+
+	/// The big-endian number `bytes` encode, `malformed` if they do not fit 32 bytes.
+	fn parse_u256<T: Config>(bytes: &[u8], malformed: Error<T>) -> Result<U256, Error<T>> {
+		ensure!(bytes.len() <= 32, malformed);
+		Ok(U256::from_big_endian(bytes))
+	}
+
+	/// Map an account to a field element, the first 31 bytes of the blake2 hash of its encoding.
+	pub fn account_to_field<T: Config>(who: &T::AccountId) -> U256 {
+		let hash = sp_io::hashing::blake2_256(&who.encode());
+		U256::from_big_endian(&hash[..31])
+	}
+
+	pub fn get_pool<T: Config>(
+		pool_id: PoolId,
+	) -> Result<PoolInfoOf<T>, sp_runtime::DispatchError> {
+		Pools::<T>::get(pool_id).ok_or_else(|| Error::<T>::PoolNotFound.into())
+	}
+
+	/// Append `commitment` to the tree of `pool_id`, return its leaf index and the new root.
+	pub(crate) fn insert_commitment<T: Config>(
+		pool_id: PoolId,
+		pool: &PoolInfoOf<T>,
+		commitment: U256,
+	) -> Result<(u32, U256), sp_runtime::DispatchError> {
+		let mut tree = load_merkle_tree::<T>(pool_id, pool);
+		let (index, nodes) =
+			tree.insert_with_nodes(commitment).map_err(|_| Error::<T>::MaxMerkleLen)?;
+		let index = index as u32;
+
+		store_merkle_tree::<T>(pool_id, &tree);
+		Leaves::<T>::insert(pool_id, index, commitment);
+		for (level, node) in nodes.into_iter().enumerate().skip(1) {
+			Nodes::<T>::insert(pool_id, (level as u32, index >> level), node);
+		}
+
+		Ok((index, tree.root))
+	}
+
+	/// Record `root` in the next slot of the history of `pool_id`, pruning the root it overwrites.
+	pub(crate) fn push_root<T: Config>(pool_id: PoolId, root: U256) {
+		let index = (CurrentRootIndex::<T>::get(pool_id) + 1) % T::RootHistorySize::get();
+
+		if let Some(stale) = RootHistory::<T>::get(pool_id, index) {
+			Roots::<T>::remove(pool_id, stale);
+		}
+		RootHistory::<T>::insert(pool_id, index, root);
+		Roots::<T>::insert(pool_id, root, true);
+		CurrentRootIndex::<T>::insert(pool_id, index);
+	}
+
+	/// Whether `root` is one of the last `RootHistorySize` roots of `pool_id`.
+	pub fn is_known_root<T: Config>(pool_id: PoolId, root: U256) -> bool {
+		!root.is_zero() && Roots::<T>::contains_key(pool_id, root)
+	}
+
+	/// Whether the note with `nullifier_hash` was withdrawn from `pool_id`.
+	pub fn is_spent<T: Config>(pool_id: PoolId, nullifier_hash: U256) -> bool {
+		NullifierHashes::<T>::contains_key(pool_id, nullifier_hash)
+	}
+
+	/// Root of the tree of `pool_id`, the empty tree's before its first deposit.
+	pub fn merkle_root<T: Config>(pool_id: PoolId) -> Option<U256> {
+		let pool = Pools::<T>::get(pool_id)?;
+		if NextIndex::<T>::get(pool_id) == 0 {
+			return tree_zeros::<T>(pool.hasher, pool.depth).last().copied()
+		}
+		Some(CurrentRoot::<T>::get(pool_id))
+	}
+
+	/// Leaf index `commitment` was deposited at in `pool_id`.
+	pub fn leaf_index<T: Config>(pool_id: PoolId, commitment: U256) -> Option<u32> {
+		Commitments::<T>::get(pool_id, commitment)
+	}
+
+	/// Path of the leaf at `leaf_index` of `pool_id` to the current root, one stored sibling per
+	/// level.
+	pub fn get_merkle_path<T: Config>(pool_id: PoolId, leaf_index: u32) -> Option<MerklePath> {
+		let pool = Pools::<T>::get(pool_id)?;
+		let leaf_count = NextIndex::<T>::get(pool_id);
+		if leaf_index >= leaf_count {
+			return None
+		}
+		let last = leaf_count - 1;
+		let zeros = tree_zeros::<T>(pool.hasher, pool.depth);
+		let path_elements = (0..pool.depth)
+			.map(|level| {
+				let sibling = (leaf_index >> level) ^ 1;
+				if sibling > last >> level {
+					Some(zeros[level as usize])
+				} else if level == 0 {
+					Leaves::<T>::get(pool_id, sibling)
+				} else {
+					Nodes::<T>::get(pool_id, (level, sibling))
+				}
+			})
+			.collect::<Option<Vec<_>>>()?;
+		let path_positions = (0..pool.depth)
+			.map(|level| pool.hasher.path_position((leaf_index >> level) & 1))
+			.collect();
+
+		Some(MerklePath {
+			root: CurrentRoot::<T>::get(pool_id),
+			leaf: Leaves::<T>::get(pool_id, leaf_index)?,
+			leaf_index,
+			path_elements,
+			path_positions,
+		})
+	}
+
+	/// Zero hashes of a tree of `depth`, without storing them on first use.
+	fn tree_zeros<T: Config>(hasher: Hasher, depth: u32) -> Vec<U256> {
+		let depth = depth as usize;
+		let mut zeros = ZeroHashes::<T>::get(hasher).into_inner();
+		if zeros.len() <= depth {
+			return zero_hashes(hasher, depth)
+		}
+		zeros.truncate(depth + 1);
+		zeros
+	}
+
+	/// Read the tree frontier of `pool_id`, starting an empty tree on its first deposit.
+	pub(crate) fn load_merkle_tree<T: Config>(
+		pool_id: PoolId,
+		pool: &PoolInfoOf<T>,
+	) -> IncrementalMerkleTree {
+		let depth = pool.depth as usize;
+		let mut zeros = ZeroHashes::<T>::get(pool.hasher).into_inner();
+		// missing, or only cached for shallower trees
+		if zeros.len() <= depth {
+			zeros = zero_hashes(pool.hasher, depth);
+			ZeroHashes::<T>::insert(pool.hasher, BoundedVec::truncate_from(zeros.clone()));
+		}
+		zeros.truncate(depth + 1);
+
+		let filled_subtrees = FilledSubtrees::<T>::get(pool_id);
+		if filled_subtrees.is_empty() {
+			return IncrementalMerkleTree::with_zeros(pool.hasher, zeros)
+		}
+
+		IncrementalMerkleTree {
+			hasher: pool.hasher,
+			next_index: NextIndex::<T>::get(pool_id),
+			filled_subtrees: filled_subtrees.into_inner(),
+			zeros,
+			root: CurrentRoot::<T>::get(pool_id),
+		}
+	}
+
+	pub(crate) fn store_merkle_tree<T: Config>(pool_id: PoolId, tree: &IncrementalMerkleTree) {
+		NextIndex::<T>::insert(pool_id, tree.next_index);
+		FilledSubtrees::<T>::insert(
+			pool_id,
+			BoundedVec::truncate_from(tree.filled_subtrees.clone()),
+		);
+		CurrentRoot::<T>::insert(pool_id, tree.root);
+	}
+
+	/// Usable keys of the withdraw circuit of `pool`, the latest registered version first.
+	fn get_verification_keys<T: Config>(
+		pool: &PoolInfoOf<T>,
+	) -> Result<Vec<CurveVerificationKey>, sp_runtime::DispatchError> {
+		let keys = T::VerificationKeys::verification_keys(pool.circuit_id);
+		ensure!(!keys.is_empty(), Error::<T>::VerificationKeyIsNotSet);
+
+		// keys registered for another number of public inputs can never accept a withdraw
+		let keys: Vec<_> = keys
+			.into_iter()
+			.filter(|key| key.public_inputs as usize == WITHDRAW_PUBLIC_INPUTS)
+			.collect();
+		ensure!(!keys.is_empty(), Error::<T>::PublicInputsMismatch);
+
+		// nor can keys of a `Withdraw(k)` for another depth than the pool's tree
+		let keys: Vec<_> =
+			keys.into_iter().filter(|key| key.tree_depth == Some(pool.depth)).collect();
+		ensure!(!keys.is_empty(), Error::<T>::TreeDepthMismatch);
+
+		let keys = keys
+			.into_iter()
+			.map(|key| {
+				CurveVerificationKey::from_bytes(&key.key)
+					.map_err(|_| Error::<T>::VerificationKeyCreationError)
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+
+		// trees are hashed over BLS12-381, a circuit on BN254 can not open their roots
+		let keys: Vec<_> = keys.into_iter().filter(|key| key.curve() == Curve::Bls12381).collect();
+		ensure!(!keys.is_empty(), Error::<T>::UnsupportedCurve);
+
+		Ok(keys)
+	}
+
+	fn parse_proof<T: Config>(
+		proof: &EncodedProof,
+	) -> Result<CurveProof, sp_runtime::DispatchError> {
+		if let EncodedProof::Json(json) = proof {
+			ensure!(json.len() <= T::MaxProofLength::get() as usize, Error::<T>::TooLongProof);
+		}
+		let proof = decode_proof(proof).map_err(parse_error::<T>(Error::<T>::MalformedProof))?;
 
 		Ok(proof)
 	}
 
+	/// Maps a key or proof parsing failure to the pallet error, `malformed` for invalid json.
+	fn parse_error<T: Config>(malformed: Error<T>) -> impl FnOnce(ParseError) -> Error<T> {
+		move |e| match e {
+			ParseError::Malformed => malformed,
+			ParseError::NotSupportedCurve => Error::<T>::NotSupportedCurve,
+			ParseError::NotSupportedProtocol => Error::<T>::NotSupportedProtocol,
+			ParseError::PointCreationError => Error::<T>::ProofCreationError,
+		}
+	}
+
 	pub fn account_id<T: Config>() -> <T as frame_system::Config>::AccountId {
 		<T as Config>::PalletId::get().into_account_truncating()
 	}
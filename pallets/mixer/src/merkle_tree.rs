@@ -1,20 +1,64 @@
-use ark_bn254::Fr;
 use sp_std::{vec, vec::Vec};
 
-use ark_std::{str::FromStr, string::ToString};
+use codec::{Decode, Encode, MaxEncodedLen};
 use mimc::Mimc7;
+use poseidon::Poseidon;
+use scale_info::TypeInfo;
 use sp_core::U256;
 
 use super::*;
 
-// ceil(log2(1<<20))
-static TREE_DEPTH: usize = 8;
+// Depth of the `Withdraw(8)` circuit in `circom/mixer`.
+pub const TREE_DEPTH: usize = 8;
+
+// Deepest tree the on-chain frontier can hold, 1<<32 leaves.
+pub const MAX_TREE_DEPTH: usize = 32;
+
+// Roots of empty MiMC7 subtrees by height, `ZERO_HASHES[d + 1] = MiMC7(ZERO_HASHES[d],
+// ZERO_HASHES[d])` from an empty leaf of `0`. Checked against `zero_hashes` in
+// `test_zero_hashes_table`.
+pub const ZERO_HASHES: [U256; MAX_TREE_DEPTH + 1] = [
+	U256([0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000]),
+	U256([0xcbe5db5f8369f7d8, 0x06f6dbf80df13510, 0x11c8b2e1d33f8e64, 0x0cd7175d5594f7b8]),
+	U256([0x25dd45307d0a59ab, 0x372b42e8ee8ed971, 0x55a9460990156233, 0x5e23a0c7b3e9a39f]),
+	U256([0x7aebc02d34aa2efe, 0x2ff3cbb79da8988b, 0x226735b2ac583572, 0x3c8de304d95d1f86]),
+	U256([0x3badf6662b1e6aa8, 0x3edb2bd31b185f26, 0x81492685f1f0004d, 0x5d9825bbcc68630a]),
+	U256([0xecb9743439afb790, 0xf99a0b1d4f85f2a4, 0xc3fe42fc9a8ac0bf, 0x152a651c31fa1639]),
+	U256([0x9fe228673a95ffff, 0x4bca974b4f3d7e67, 0x7bf51e423ea27f27, 0x1ae6896a0f86f2bc]),
+	U256([0x565f445ec6871ccc, 0x88bebc34702fae12, 0xa9ef4b15cf2ed5cb, 0x5537c78dd3f2431f]),
+	U256([0x4e1bd58cf0ca1007, 0x1073a5e21ea50bac, 0x70998a228b901f98, 0x4f9ed0491d9416ec]),
+	U256([0x4f319b0832a7ceb5, 0x1871ee1a2df9ed2d, 0xde962cf919558d41, 0x2e2288347f8f78d9]),
+	U256([0x0a3c6361831c58b0, 0x1a088b720087db79, 0x9ed481bffef11407, 0x05d45feab4b7cb34]),
+	U256([0xf37f51048394df50, 0x799bdc7cd157e2cd, 0xbebbdf518acd2c68, 0x0fb2e1d09e325298]),
+	U256([0x1c7e166b886ed39d, 0xd65869094c65faaa, 0xcc37f545f03c8941, 0x1af62b352fc8b5b0]),
+	U256([0x2c263c7aeb68cccb, 0xfd54553ddf4b8b63, 0xb2c31a319fb1336a, 0x2b509aac2d15e8b7]),
+	U256([0x5997640475a477f2, 0x46fa256f0b4560cd, 0x016ad9c015eed6ec, 0x0e30507f785fc490]),
+	U256([0x12cd5ce03aa414c6, 0x8dbac23005d1ac0e, 0x0952e5e7a44629ac, 0x5ece531e0a856bd1]),
+	U256([0xccfa12dbdd290194, 0x5916ed0375bda0eb, 0x617f90177556811e, 0x6b065934d9e71a9d]),
+	U256([0x6eb7e9ee10de6928, 0x6b8082b1fecf5c66, 0x700c544d73f48b6c, 0x0af0a933a04a7c60]),
+	U256([0xfa52cfd46b258866, 0x3030b3f55e0bff5f, 0xe210f23d03b11d81, 0x5636b59b39f8a2fa]),
+	U256([0x25aa5361e6bad15b, 0x49f3cb15b41f0454, 0x9b81cc27bc32d475, 0x33f1d0376107921c]),
+	U256([0xcfd00c7d6a26a6cf, 0x8ca5a10baaf9984b, 0x353be2150e082f96, 0x1ebbc98fb77486fe]),
+	U256([0x2692fca84ccc3b25, 0x92cf94d4b75c7ac9, 0x5dfbcfa9cf8676dd, 0x47eb71d5367d019e]),
+	U256([0xd91e2ca7b2759071, 0x12cf20a424769334, 0x7736bdeef2569d56, 0x39babf035753277e]),
+	U256([0xf14526bb7f0939b1, 0x7ac5ac8099df21d2, 0xc0956a079d24c7f5, 0x51727c8f7b18d515]),
+	U256([0x49eaa7b501246909, 0xaac1754c88241a02, 0xc2f2c39421e3fc36, 0x1bf2a9f432f20852]),
+	U256([0xdabee2f65c9a383f, 0x1784d962b826a91b, 0xe817ae845770a19e, 0x1057a673d55b2792]),
+	U256([0x2e2c6d99bd8859d1, 0x82ec17fe8770bf50, 0x4fe3a956c95a0080, 0x5e35a243e27de8bc]),
+	U256([0x3a4d4d39d787ba2a, 0x0898860a427a6b06, 0xb0813f43ef6cd797, 0x3513e25bc94fd58d]),
+	U256([0xf98483cab8a27bed, 0xcfd06c45d96390a1, 0x453b280a5c1a154d, 0x5c96ab5f40e5725b]),
+	U256([0x7612292c175cca04, 0xb04215c5fd7e1d6c, 0xad3d043c2e50962c, 0x4732cf20bae51e5f]),
+	U256([0xaa15fc865b8dfd12, 0xddda4a2df420dafe, 0xc4fecb0b81e28c74, 0x2b4ffe84566e18a6]),
+	U256([0xb3555af3dc8d37a1, 0xd9a89f7a46969e48, 0x66765d68b76245e7, 0x22c86d6ab2fa0e02]),
+	U256([0xbb7d7205341331e6, 0x9e10b8230bbd5846, 0x0b7052c7c72d3ac1, 0x462c635f27037408]),
+];
 
 // 1<<20 leaves
 // const MAX_LEAF_COUNT: usize = 256;
 
 #[derive(Clone, Debug)]
 pub struct MerkleTree {
+	depth: usize,
 	cur: usize,
 	root: U256,
 	leaves: Vec<Vec<U256>>,
@@ -22,18 +66,22 @@ pub struct MerkleTree {
 
 impl Default for MerkleTree {
 	fn default() -> Self {
-		let leaves = (0..TREE_DEPTH + 1)
-			.rev()
-			.map(|x| vec![U256::zero(); 2usize.pow(x as u32)])
+		MerkleTree::new(TREE_DEPTH)
+	}
+}
+
+impl MerkleTree {
+	pub fn new(depth: usize) -> Self {
+		// nodes nothing was inserted under yet hold the root of their empty subtree
+		let leaves = (0..depth + 1)
+			.map(|d| vec![ZERO_HASHES[d]; 2usize.pow((depth - d) as u32)])
 			.collect::<Vec<_>>();
 
-		let mut mt = MerkleTree { cur: 0, root: U256::zero(), leaves };
+		let mut mt = MerkleTree { depth, cur: 0, root: ZERO_HASHES[depth], leaves };
 		mt.init();
 		mt
 	}
-}
 
-impl MerkleTree {
 	fn init(&mut self) {
 		// for depth in 0..TREE_DEPTH {
 		// 	self.leaves[depth]
@@ -57,6 +105,9 @@ impl MerkleTree {
 	//@@
 	pub fn insert(&mut self, message: U256) -> Result<(U256, usize), &'static str> {
 		let offset = self.cur;
+		if offset >= self.leaves[0].len() {
+			return Err("merkle tree is full")
+		}
 		self.leaves[0][self.cur] = message;
 
 		self.root = self.update();
@@ -73,29 +124,19 @@ impl MerkleTree {
 
 		let mimc7 = Mimc7::new(91);
 
-		for depth in 0..TREE_DEPTH {
+		for depth in 0..self.depth {
 			let next_index = current_index / 2;
 			if current_index % 2 == 0 {
 				leaf1 = self.leaves[depth][current_index].clone();
-				leaf2 = MerkleTree::get_unique_leaf(
-					self.leaves[depth][current_index + 1].clone(),
-					depth,
-				);
+				leaf2 = self.leaves[depth][current_index + 1];
 			} else {
-				leaf1 = MerkleTree::get_unique_leaf(
-					self.leaves[depth][current_index - 1].clone(),
-					depth,
-				);
+				leaf1 = self.leaves[depth][current_index - 1];
 				leaf2 = self.leaves[depth][current_index].clone();
 			}
-			let l1 = Fr::from_str(&leaf1.to_string()).unwrap();
-			let l2 = Fr::from_str(&leaf2.to_string()).unwrap();
-
-			self.leaves[depth + 1][next_index] =
-				U256::from_dec_str(&mimc7.hash(&l1, &l2).to_string()).unwrap();
+			self.leaves[depth + 1][next_index] = hash_leaves(&mimc7, &leaf1, &leaf2);
 			current_index = next_index;
 		}
-		self.root = self.leaves[TREE_DEPTH][0].clone();
+		self.root = self.leaves[self.depth][0].clone();
 		self.root.clone()
 	}
 
@@ -109,12 +150,16 @@ impl MerkleTree {
 		self.root.clone()
 	}
 
+	pub fn depth(&self) -> usize {
+		self.depth
+	}
+
 	// Obtain the merkel proof according to the corresponding leaf of the index
 	pub fn get_proof(&self, mut index: usize) -> Vec<U256> {
-		let mut address_bits = vec![false; TREE_DEPTH];
-		let mut proof_path = vec![U256::zero(); TREE_DEPTH];
+		let mut address_bits = vec![false; self.depth];
+		let mut proof_path = vec![U256::zero(); self.depth];
 
-		for depth in 0..TREE_DEPTH {
+		for depth in 0..self.depth {
 			//address_bits[depth] = index % 2 == 0;
 			if index % 2 == 0 {
 				address_bits[depth] = true;
@@ -150,18 +195,6 @@ impl MerkleTree {
 	// 	item
 	// }
 
-	// @@
-	pub fn get_unique_leaf(mut leaf: U256, depth: usize) -> U256 {
-		if leaf.is_zero() {
-			let mimc7 = Mimc7::new(91);
-			for _depth in 0..depth {
-				let l = Fr::from_str(&leaf.to_string()).unwrap();
-				leaf = U256::from_dec_str(&mimc7.hash(&l, &l).to_string()).unwrap();
-			}
-		}
-		leaf
-	}
-
 	// Use two leaves to generate mimc hash
 	// fn hash_impl(left: &U256, right: &U256, iv: &U256) -> U256 {
 	// 	let mut left_arr = [0u8; 32];
@@ -187,6 +220,201 @@ impl MerkleTree {
 	// }
 }
 
+// Use two leaves to generate their parent node
+pub fn hash_leaves(mimc7: &Mimc7, left: &U256, right: &U256) -> U256 {
+	mimc::from_field(&mimc7.hash(&mimc::to_field(left), &mimc::to_field(right)))
+}
+
+/// Hash of two sibling nodes into their parent.
+pub trait MerkleHasher {
+	fn hash_leaves(&self, left: &U256, right: &U256) -> U256;
+}
+
+impl MerkleHasher for Mimc7 {
+	fn hash_leaves(&self, left: &U256, right: &U256) -> U256 {
+		hash_leaves(self, left, right)
+	}
+}
+
+impl MerkleHasher for Poseidon {
+	fn hash_leaves(&self, left: &U256, right: &U256) -> U256 {
+		poseidon::from_field(&self.hash(&poseidon::to_field(left), &poseidon::to_field(right)))
+	}
+}
+
+/// Hash a pool's tree is built with. Each one needs its own withdraw circuit.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Encode, Decode, MaxEncodedLen, TypeInfo)]
+pub enum Hasher {
+	/// MiMC7 with 91 rounds over BLS12-381, the `circom/mixer` circuit.
+	#[default]
+	MiMC7,
+	/// Poseidon255 over the BLS12-381 scalar field, the `circom/mixer-psd` circuit.
+	Poseidon,
+}
+
+impl Hasher {
+	// Run `f` with one instance of the hash.
+	pub fn with<R>(self, f: impl FnOnce(&dyn MerkleHasher) -> R) -> R {
+		match self {
+			Hasher::MiMC7 => f(&Mimc7::new(mimc::N_ROUNDS)),
+			Hasher::Poseidon => f(&Poseidon),
+		}
+	}
+
+	// The circuit's `paths2_root_pos` at a level where the index of the path node ends in `bit`.
+	// `mixer` sets it for left children, `mixer-psd` for right ones.
+	pub fn path_position(self, bit: u32) -> u8 {
+		match self {
+			Hasher::MiMC7 => (bit == 0) as u8,
+			Hasher::Poseidon => bit as u8,
+		}
+	}
+}
+
+// Roots of empty subtrees for every level, from the leaves (`0`) up to the root.
+pub fn zero_hashes(hasher: Hasher, depth: usize) -> Vec<U256> {
+	if hasher == Hasher::MiMC7 && depth <= MAX_TREE_DEPTH {
+		return ZERO_HASHES[..=depth].to_vec()
+	}
+	hasher.with(|h| {
+		let mut zeros = Vec::with_capacity(depth + 1);
+		let mut zero = U256::zero();
+		zeros.push(zero);
+		for _depth in 0..depth {
+			zero = h.hash_leaves(&zero, &zero);
+			zeros.push(zero);
+		}
+		zeros
+	})
+}
+
+/// Inputs of the withdraw circuit proving that `leaf` is in the tree under `root`.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct MerklePath {
+	pub root: U256,
+	pub leaf: U256,
+	pub leaf_index: u32,
+	/// Siblings from the leaves up, the circuit's `paths2_root`.
+	pub path_elements: Vec<U256>,
+	/// The circuit's `paths2_root_pos`, see [`Hasher::path_position`].
+	pub path_positions: Vec<u8>,
+}
+
+// Siblings of the leaf at `index` from the leaves up, the same path as `MerkleTree::get_proof`.
+// Only the levels above `leaves` are hashed, empty subtrees are read from `zeros`.
+pub fn merkle_path(
+	hasher: Hasher,
+	leaves: Vec<U256>,
+	zeros: &[U256],
+	mut index: usize,
+) -> Vec<U256> {
+	hasher.with(|h| {
+		let depth = zeros.len() - 1;
+		let mut level = leaves;
+		let mut path = Vec::with_capacity(depth);
+
+		for depth in 0..depth {
+			path.push(level.get(index ^ 1).copied().unwrap_or(zeros[depth]));
+			level = level
+				.chunks(2)
+				.map(|pair| h.hash_leaves(&pair[0], pair.get(1).unwrap_or(&zeros[depth])))
+				.collect();
+			index /= 2;
+		}
+		path
+	})
+}
+
+/// Append-only Merkle tree that only keeps the right-most frontier.
+///
+/// `filled_subtrees[depth]` is the last left-hand node written at `depth` and `zeros[depth]` the
+/// root of an empty subtree of that height, so inserting a leaf costs exactly `depth` hashes and
+/// yields the same roots as a [`MerkleTree`] of the same depth.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IncrementalMerkleTree {
+	pub hasher: Hasher,
+	pub next_index: u32,
+	pub filled_subtrees: Vec<U256>,
+	pub zeros: Vec<U256>,
+	pub root: U256,
+}
+
+impl Default for IncrementalMerkleTree {
+	fn default() -> Self {
+		IncrementalMerkleTree::new(TREE_DEPTH)
+	}
+}
+
+impl IncrementalMerkleTree {
+	pub fn new(depth: usize) -> Self {
+		IncrementalMerkleTree::with_hasher(depth, Hasher::MiMC7)
+	}
+
+	pub fn with_hasher(depth: usize, hasher: Hasher) -> Self {
+		IncrementalMerkleTree::with_zeros(hasher, zero_hashes(hasher, depth))
+	}
+
+	/// Empty tree of `zeros.len() - 1` levels, `zeros` as returned by `zero_hashes`.
+	pub fn with_zeros(hasher: Hasher, zeros: Vec<U256>) -> Self {
+		let depth = zeros.len() - 1;
+		IncrementalMerkleTree {
+			hasher,
+			next_index: 0,
+			filled_subtrees: zeros[..depth].to_vec(),
+			root: zeros[depth],
+			zeros,
+		}
+	}
+
+	pub fn depth(&self) -> usize {
+		self.filled_subtrees.len()
+	}
+
+	// Append a leaf, return the leaf and the index it was assigned.
+	pub fn insert(&mut self, leaf: U256) -> Result<(U256, usize), &'static str> {
+		self.insert_with_nodes(leaf).map(|(offset, _)| (leaf, offset))
+	}
+
+	/// Append a leaf like [`Self::insert`], also return the nodes on its path to the root, the
+	/// leaf first and the root left out. Node `depth` is at index `offset >> depth` of its level.
+	pub fn insert_with_nodes(&mut self, leaf: U256) -> Result<(usize, Vec<U256>), &'static str> {
+		let offset = self.next_index as usize;
+		if offset as u64 >= 1u64 << self.depth() {
+			return Err("merkle tree is full")
+		}
+
+		let mut current_index = offset;
+		let mut current = leaf;
+		let mut nodes = Vec::with_capacity(self.depth());
+
+		self.hasher.with(|h| {
+			for depth in 0..self.filled_subtrees.len() {
+				nodes.push(current);
+				let (left, right) = if current_index % 2 == 0 {
+					self.filled_subtrees[depth] = current;
+					(current, self.zeros[depth])
+				} else {
+					(self.filled_subtrees[depth], current)
+				};
+				current = h.hash_leaves(&left, &right);
+				current_index /= 2;
+			}
+		});
+
+		self.root = current;
+		self.next_index += 1;
+
+		Ok((offset, nodes))
+	}
+
+	// get merkle tree root
+	pub fn get_root(&self) -> U256 {
+		self.root
+	}
+}
+
 #[test]
 fn test_merkle_tree_root_hash() {
 	let mut mt = MerkleTree::default();
@@ -194,7 +422,7 @@ fn test_merkle_tree_root_hash() {
 	assert_eq!(mt.update(), mt.get_root());
 	assert_eq!(
 		U256::from_dec_str(
-			"15118794022989096240414562605246342209918117228209570959055463092364691057063"
+			"36013314408744245508634225567154564982770282652354006176670717210104248799239"
 		)
 		.unwrap(),
 		mt.get_root()
@@ -204,7 +432,7 @@ fn test_merkle_tree_root_hash() {
 	print!("{:?} {:?}", leaf, index);
 	assert_eq!(
 		U256::from_dec_str(
-			"11918823777688916996440235409179584458198237132535057418448191606750426488941"
+			"6870572112840620857135638769407829567376800590910451492860072723112109574334"
 		)
 		.unwrap(),
 		mt.get_root()
@@ -214,7 +442,7 @@ fn test_merkle_tree_root_hash() {
 	print!("{:?} {:?}", leaf, index);
 	assert_eq!(
 		U256::from_dec_str(
-			"4056297984077945401031160722288226165138515589996813440303114275064200657118"
+			"8394624259789508742186827690604656524433147366810067954593823804113940867142"
 		)
 		.unwrap(),
 		mt.get_root()
@@ -224,3 +452,156 @@ fn test_merkle_tree_root_hash() {
 	// let merkle_proof = mt.get_proof(index);
 	//assert!(mt.verify_merkle_proof(leaf, merkle_proof, index));
 }
+
+#[test]
+fn test_zero_hashes_table() {
+	let mimc7 = Mimc7::new(mimc::N_ROUNDS);
+	for depth in 0..MAX_TREE_DEPTH {
+		assert_eq!(
+			ZERO_HASHES[depth + 1],
+			hash_leaves(&mimc7, &ZERO_HASHES[depth], &ZERO_HASHES[depth])
+		);
+	}
+
+	// pinned from this implementation so a change of the table or the hash shows up, these are not
+	// circomlib vectors: circomlib only publishes BN254 ones, and the proofs of the circuit built
+	// with `-p bls12381` are the independent check
+	for (depth, zero) in [
+		(0, "0"),
+		(1, "5807787558380392048884869472116132327038386181941137901376191747654993704920"),
+		(8, "36013314408744245508634225567154564982770282652354006176670717210104248799239"),
+		(20, "13901176984219713759589909978882933839535823611607679399883858443223186187983"),
+		(32, "31740326509870761700562239605055167351117100008906183277212322396254915211750"),
+	] {
+		assert_eq!(ZERO_HASHES[depth], U256::from_dec_str(zero).unwrap());
+	}
+}
+
+#[test]
+fn test_merkle_tree_zero_leaf() {
+	// a deposited `0` is hashed like any other leaf, empty siblings come from `ZERO_HASHES`
+	let mut mt = MerkleTree::new(3);
+	let mut imt = IncrementalMerkleTree::new(3);
+	for leaf in [0u64, 5, 0] {
+		mt.insert(U256::from(leaf)).unwrap();
+		imt.insert(U256::from(leaf)).unwrap();
+		assert_eq!(mt.get_root(), imt.get_root());
+	}
+	assert_eq!(mt.get_proof(2)[0], ZERO_HASHES[0]);
+	assert_eq!(
+		mt.get_proof(2)[1],
+		hash_leaves(&Mimc7::new(mimc::N_ROUNDS), &U256::zero(), &5.into())
+	);
+}
+
+#[test]
+fn test_incremental_merkle_tree_matches_full_tree() {
+	let mut mt = MerkleTree::default();
+	let mut imt = IncrementalMerkleTree::default();
+
+	assert_eq!(imt.zeros, zero_hashes(Hasher::MiMC7, TREE_DEPTH));
+	assert_eq!(imt.get_root(), mt.update());
+
+	for leaf in 1..=5u64 {
+		let (_, expected) = mt.insert(U256::from(leaf)).unwrap();
+		let (_, index) = imt.insert(U256::from(leaf)).unwrap();
+		assert_eq!(index, expected);
+		assert_eq!(imt.get_root(), mt.get_root());
+	}
+	assert_eq!(imt.next_index, 5);
+}
+
+#[test]
+fn test_incremental_merkle_tree_is_full() {
+	let mut imt = IncrementalMerkleTree { next_index: 1 << TREE_DEPTH, ..Default::default() };
+
+	assert!(imt.insert(U256::one()).is_err());
+}
+
+#[test]
+fn test_merkle_tree_with_custom_depth() {
+	let mut mt = MerkleTree::new(2);
+	let mut imt = IncrementalMerkleTree::new(2);
+	assert_eq!(imt.zeros.len(), 3);
+
+	for leaf in 1..=4u64 {
+		mt.insert(U256::from(leaf)).unwrap();
+		imt.insert(U256::from(leaf)).unwrap();
+		assert_eq!(imt.get_root(), mt.get_root());
+	}
+	assert_eq!(mt.get_proof(3).len(), 2);
+
+	// a full tree errors instead of indexing out of bounds
+	assert!(mt.insert(U256::from(5)).is_err());
+	assert!(imt.insert(U256::from(5)).is_err());
+}
+
+#[test]
+fn test_insert_with_nodes_matches_merkle_tree() {
+	let mut mt = MerkleTree::new(3);
+	let mut imt = IncrementalMerkleTree::new(3);
+
+	for leaf in 1..=8u64 {
+		mt.insert(U256::from(leaf)).unwrap();
+		let (offset, nodes) = imt.insert_with_nodes(U256::from(leaf)).unwrap();
+
+		assert_eq!(nodes.len(), 3);
+		for (depth, node) in nodes.iter().enumerate() {
+			assert_eq!(*node, mt.get_leaf(depth, offset >> depth));
+		}
+	}
+}
+
+#[test]
+fn test_merkle_path_matches_proof() {
+	let mut mt = MerkleTree::new(3);
+	let zeros = zero_hashes(Hasher::MiMC7, 3);
+	let mut leaves = Vec::new();
+
+	for leaf in 1..=5u64 {
+		mt.insert(U256::from(leaf)).unwrap();
+		leaves.push(U256::from(leaf));
+
+		for index in 0..leaves.len() {
+			let path = merkle_path(Hasher::MiMC7, leaves.clone(), &zeros, index);
+			assert_eq!(path, mt.get_proof(index));
+
+			// the path hashes back up to the root
+			let mimc7 = Mimc7::new(91);
+			let root = path.iter().enumerate().fold(leaves[index], |node, (depth, sibling)| {
+				if (index >> depth) & 1 == 0 {
+					hash_leaves(&mimc7, &node, sibling)
+				} else {
+					hash_leaves(&mimc7, sibling, &node)
+				}
+			});
+			assert_eq!(root, mt.get_root());
+		}
+	}
+}
+
+#[test]
+fn test_poseidon_merkle_path() {
+	let mut imt = IncrementalMerkleTree::with_hasher(3, Hasher::Poseidon);
+	let zeros = zero_hashes(Hasher::Poseidon, 3);
+	assert_eq!(imt.zeros, zeros);
+	assert_ne!(zeros, zero_hashes(Hasher::MiMC7, 3));
+
+	let leaves = (1..=5u64).map(U256::from).collect::<Vec<_>>();
+	for leaf in leaves.iter() {
+		imt.insert(*leaf).unwrap();
+	}
+
+	// walk the path the way `mixer-psd` does, the sibling goes first where the position is `1`
+	for index in 0..leaves.len() {
+		let path = merkle_path(Hasher::Poseidon, leaves.clone(), &zeros, index);
+		let root = path.iter().enumerate().fold(leaves[index], |node, (depth, sibling)| {
+			if Hasher::Poseidon.path_position((index >> depth) as u32 & 1) == 1 {
+				Poseidon.hash_leaves(sibling, &node)
+			} else {
+				Poseidon.hash_leaves(&node, sibling)
+			}
+		});
+		assert_eq!(root, imt.get_root());
+	}
+}
@@ -0,0 +1,227 @@
+//! Storage migrations for the mixer pallet.
+
+use super::*;
+use frame_support::{
+	pallet_prelude::*,
+	traits::{GetStorageVersion, OnRuntimeUpgrade},
+};
+use sp_std::marker::PhantomData;
+
+#[cfg(feature = "try-runtime")]
+use sp_runtime::TryRuntimeError;
+
+pub mod v1 {
+	use super::*;
+	use crate::merkle_tree::{Hasher, TREE_DEPTH};
+	use primitives::CircuitId;
+
+	/// Storage of version 0, a single tree of native deposits.
+	pub mod v0 {
+		use super::*;
+
+		/// Every deposited commitment, the tree was rebuilt from it on every deposit.
+		#[frame_support::storage_alias]
+		pub type MerkleVec<T: Config> =
+			StorageValue<Pallet<T>, BoundedVec<U256, ConstU32<{ u32::MAX }>>, ValueQuery>;
+
+		/// Every root the tree ever had.
+		#[frame_support::storage_alias]
+		pub type Roots<T: Config> = StorageMap<Pallet<T>, Blake2_128Concat, U256, bool>;
+
+		#[frame_support::storage_alias]
+		pub type NullifierHashes<T: Config> = StorageMap<Pallet<T>, Blake2_128Concat, U256, bool>;
+
+		#[frame_support::storage_alias]
+		pub type Commitments<T: Config> = StorageMap<Pallet<T>, Blake2_128Concat, U256, bool>;
+
+		/// The snarkjs key of the two input withdraw circuit.
+		#[frame_support::storage_alias]
+		pub type VerificationKeyStorage<T: Config> = StorageValue<Pallet<T>, Vec<u8>, ValueQuery>;
+
+		/// Blacklisted accounts, without a reason.
+		#[frame_support::storage_alias]
+		pub type BlackList<T: Config> =
+			StorageMap<Pallet<T>, Blake2_128Concat, <T as frame_system::Config>::AccountId, bool>;
+	}
+
+	/// Pool every existing deposit is moved into.
+	pub const LEGACY_POOL_ID: PoolId = 0;
+
+	/// Moves the single tree of storage version 0 into pool `0`, a MiMC7 pool of the native
+	/// currency with the old fixed `Denomination` and the old tree depth, `TREE_DEPTH`. Its
+	/// withdraws are verified with the keys of `WithdrawCircuit`.
+	///
+	/// `MerkleVec` is replayed into the tree frontier in deposit order, leaves that do not fit the
+	/// tree are dropped. The old roots were hashed over BN254, no proof could open
+	/// them, so they are dropped and the root history is refilled with the last `RootHistorySize`
+	/// roots of the replay, the ones withdraws would have been proven against. The nullifier hashes
+	/// and the blacklist are moved up to `MaxEntries` each. The old verification key is of a
+	/// circuit with two public inputs and is dropped, withdraw keys are registered in
+	/// `Config::VerificationKeys`.
+	///
+	/// Old and new items share their storage prefixes, so entries are read out before anything is
+	/// written back under the pool key. Every version 0 deposit added a leaf and a root, so the old
+	/// roots and commitments are bounded by the leaves. `pre_upgrade` fails if anything is over its
+	/// bound.
+	pub struct MigrateToV1<T, Denomination, WithdrawCircuit, MaxEntries>(
+		PhantomData<(T, Denomination, WithdrawCircuit, MaxEntries)>,
+	);
+
+	impl<
+			T: Config,
+			Denomination: Get<BalanceOf<T>>,
+			WithdrawCircuit: Get<CircuitId>,
+			MaxEntries: Get<u32>,
+		> OnRuntimeUpgrade for MigrateToV1<T, Denomination, WithdrawCircuit, MaxEntries>
+	{
+		fn on_runtime_upgrade() -> Weight {
+			let on_chain_version = Pallet::<T>::on_chain_storage_version();
+			if on_chain_version != 0 {
+				log::info!(
+					"pallet_mixer: skipping v1 migration, on-chain version {:?}",
+					on_chain_version
+				);
+				return T::DbWeight::get().reads(1)
+			}
+
+			let pool_id = LEGACY_POOL_ID;
+			let limit = MaxEntries::get();
+
+			let leaves = v0::MerkleVec::<T>::take();
+			let nullifiers: Vec<_> =
+				v0::NullifierHashes::<T>::drain().take(limit as usize).collect();
+			let mut black_listed: Vec<_> =
+				v0::BlackList::<T>::iter_keys().take(limit as usize + 1).collect();
+			if v0::NullifierHashes::<T>::iter_keys().next().is_some() {
+				log::error!("pallet_mixer: more than {} nullifiers in v1 migration", limit);
+			}
+			// the keys are the same, accounts left over stay blacklisted without a reason
+			if black_listed.len() > limit as usize {
+				log::error!("pallet_mixer: more than {} blacklisted in v1 migration", limit);
+				black_listed.truncate(limit as usize);
+			}
+			let bound = (leaves.len() as u32).saturating_add(1);
+			let roots = v0::Roots::<T>::clear(bound, None);
+			let commitments = v0::Commitments::<T>::clear(bound, None);
+			if roots.maybe_cursor.is_some() || commitments.maybe_cursor.is_some() {
+				log::error!("pallet_mixer: more roots or commitments than leaves in v1 migration");
+			}
+			v0::VerificationKeyStorage::<T>::kill();
+
+			let pool = PoolInfo {
+				currency_id: T::GetNativeCurrencyId::get(),
+				denomination: Denomination::get(),
+				hasher: Hasher::MiMC7,
+				depth: TREE_DEPTH as u32,
+				circuit_id: WithdrawCircuit::get(),
+			};
+			Pools::<T>::insert(pool_id, &pool);
+			NextPoolId::<T>::put(pool_id + 1);
+
+			let mut moved = 0u64;
+			for leaf in leaves.iter() {
+				match insert_commitment::<T>(pool_id, &pool, *leaf) {
+					Ok((index, root)) => {
+						Commitments::<T>::insert(pool_id, leaf, index);
+						push_root::<T>(pool_id, root);
+						moved += 1;
+					},
+					Err(_) => {
+						log::error!(
+							"pallet_mixer: dropping {} leaves from {:?} in v1 migration",
+							leaves.len() as u64 - moved,
+							leaf
+						);
+						break
+					},
+				}
+			}
+
+			for (nullifier_hash, spent) in nullifiers.iter() {
+				NullifierHashes::<T>::insert(pool_id, nullifier_hash, spent);
+			}
+			for who in black_listed.iter() {
+				BlackList::<T>::insert(who, BlackListReasonOf::<T>::default());
+			}
+			StorageVersion::new(1).put::<Pallet<T>>();
+
+			log::info!(
+				"pallet_mixer: moved {} leaves and {} nullifiers into pool {} in v1 migration",
+				moved,
+				nullifiers.len(),
+				pool_id
+			);
+
+			let entries = (nullifiers.len() + black_listed.len()) as u64;
+			let cleared = (roots.unique + commitments.unique) as u64;
+			T::DbWeight::get()
+				.reads_writes(6 * moved + entries + cleared + 4, 9 * moved + entries + cleared + 8)
+				.saturating_add(
+					T::WeightInfo::merkle_insert(TREE_DEPTH as u32).saturating_mul(moved),
+				)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+			let leaves = v0::MerkleVec::<T>::get().len() as u32;
+			let nullifiers = v0::NullifierHashes::<T>::iter_keys().count() as u32;
+			let black_listed = v0::BlackList::<T>::iter_keys().count() as u32;
+
+			ensure!(
+				leaves as u64 <= 1u64 << TREE_DEPTH,
+				"pallet_mixer: MerkleVec does not fit the tree"
+			);
+			ensure!(
+				v0::Roots::<T>::iter_keys().count() as u32 <= leaves &&
+					v0::Commitments::<T>::iter_keys().count() as u32 <= leaves,
+				"pallet_mixer: more roots or commitments than leaves"
+			);
+			ensure!(
+				nullifiers <= MaxEntries::get() && black_listed <= MaxEntries::get(),
+				"pallet_mixer: more nullifiers or blacklisted accounts than MaxEntries"
+			);
+			Ok((leaves, nullifiers, black_listed).encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+			let (leaves, nullifiers, black_listed): (u32, u32, u32) =
+				Decode::decode(&mut &state[..]).map_err(|_| "pallet_mixer: invalid v1 state")?;
+			let pool_id = LEGACY_POOL_ID;
+
+			ensure!(
+				Pools::<T>::get(pool_id).map(|pool| pool.denomination) == Some(Denomination::get()),
+				"pallet_mixer: legacy pool not created"
+			);
+			ensure!(NextIndex::<T>::get(pool_id) == leaves, "pallet_mixer: leaf count changed");
+			ensure!(
+				Leaves::<T>::iter_prefix(pool_id).count() as u32 == leaves &&
+					Commitments::<T>::iter_prefix(pool_id).count() as u32 == leaves,
+				"pallet_mixer: leaves not moved"
+			);
+			ensure!(
+				leaves == 0 || is_known_root::<T>(pool_id, CurrentRoot::<T>::get(pool_id)),
+				"pallet_mixer: current root not recorded"
+			);
+			ensure!(
+				RootHistory::<T>::iter_prefix(pool_id).count() as u32 ==
+					leaves.min(T::RootHistorySize::get()),
+				"pallet_mixer: root history not refilled"
+			);
+			ensure!(
+				NullifierHashes::<T>::iter_prefix(pool_id).count() as u32 == nullifiers,
+				"pallet_mixer: nullifiers not moved"
+			);
+			ensure!(
+				BlackList::<T>::iter_values().count() as u32 == black_listed,
+				"pallet_mixer: blacklist entries lost"
+			);
+			ensure!(!v0::MerkleVec::<T>::exists(), "pallet_mixer: MerkleVec not removed");
+			ensure!(
+				Pallet::<T>::on_chain_storage_version() == 1,
+				"pallet_mixer: storage version not bumped"
+			);
+			Ok(())
+		}
+	}
+}
@@ -7,10 +7,14 @@ use frame_support::{
 };
 use orml_traits::{currency::MutationHooks, parameter_type_with_key};
 use pallet_currencies::BasicCurrencyAdapter;
-use primitives::currency::{CurrencyId, TokenSymbol};
+use primitives::{
+	currency::{CurrencyId, TokenSymbol},
+	CircuitId,
+};
 use sp_core::H256;
 use sp_runtime::{
 	traits::{AccountIdConversion, BlakeTwo256, IdentityLookup},
+	transaction_validity::{TransactionLongevity, TransactionPriority},
 	BuildStorage,
 };
 use sp_std::marker;
@@ -32,6 +36,7 @@ frame_support::construct_runtime!(
 		Tokens: orml_tokens,
 		Swap: pallet_swap,
 		Otp: pallet_otp,
+		VkRegistry: pallet_vk_registry,
 		MixerModule: pallet_mixer,
 	}
 );
@@ -186,16 +191,36 @@ impl pallet_otp::Config for Test {
 	type WeightInfo = ();
 	type MaxPublicInputsLength = ConstU32<3000>;
 	type MaxProofLength = ConstU32<5000>;
-	type MaxVerificationKeyLength = ConstU32<5000>;
+	type VerificationKeys = VkRegistry;
+	type OtpCircuitId = OtpCircuitId;
 	type TimeProvider = pallet_timestamp::Pallet<Test>;
 }
 
+impl pallet_vk_registry::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type WeightInfo = ();
+	type RegisterOrigin = frame_system::EnsureRoot<AccountId>;
+	type MaxVerificationKeyLength = ConstU32<5000>;
+	type MaxUsableVersions = ConstU32<2>;
+	type DeprecationGracePeriod = ConstU64<10>;
+}
+
 parameter_types! {
 	pub const MixerPalletId: PalletId = PalletId(*b"py/mixer");
 	pub const MaxPublicInputsLength: u32 = 3000;
-	pub const MaxVerificationKeyLength: u32 = 5000;
 	pub const MaxProofLength: u32 = 5000;
 	pub const MixerBalance: Balance = 1_000;
+	pub const MixerTreeDepth: u32 = 8;
+	pub const MixerMaxTreeDepth: u32 = 20;
+	pub const RootHistorySize: u32 = 3;
+	pub const MaxBatchWithdraws: u32 = 4;
+	pub const MaxBlackListReasonLength: u32 = 32;
+	pub const MaxBlackListBatch: u32 = 3;
+	pub const MixerUnsignedPriority: TransactionPriority = TransactionPriority::MAX / 2;
+	pub const MixerUnsignedLongevity: TransactionLongevity = 5;
+	pub const WithdrawCircuitId: CircuitId = 0;
+	pub const OtpCircuitId: CircuitId = 1;
+	pub const PoseidonWithdrawCircuitId: CircuitId = 2;
 }
 
 impl pallet_mixer::Config for Test {
@@ -203,23 +228,35 @@ impl pallet_mixer::Config for Test {
 	type WeightInfo = ();
 	type MaxPublicInputsLength = MaxPublicInputsLength;
 	type MaxProofLength = MaxProofLength;
-	type MaxVerificationKeyLength = MaxVerificationKeyLength;
 	type PalletId = MixerPalletId;
-	type Currency = Balances;
-	type MixerBalance = MixerBalance;
+	type Currency = Currencies;
+	type GetNativeCurrencyId = NativeCurrencyId;
+	type CreatePoolOrigin = frame_system::EnsureRoot<AccountId>;
+	type UnsignedPriority = MixerUnsignedPriority;
+	type UnsignedLongevity = MixerUnsignedLongevity;
+	type MaxTreeDepth = MixerMaxTreeDepth;
+	type RootHistorySize = RootHistorySize;
+	type MaxBatchWithdraws = MaxBatchWithdraws;
+	type BlackListOrigin = frame_system::EnsureRoot<AccountId>;
+	type MaxBlackListReasonLength = MaxBlackListReasonLength;
+	type MaxBlackListBatch = MaxBlackListBatch;
+	type VerificationKeys = VkRegistry;
 	type SwapApi = Swap;
 	type OtpApi = Otp;
+	type TimeProvider = pallet_timestamp::Pallet<Test>;
 }
 
-pub struct ExtBuilder;
-
-impl Default for ExtBuilder {
-	fn default() -> Self {
-		ExtBuilder
-	}
+#[derive(Default)]
+pub struct ExtBuilder {
+	black_list: Vec<(AccountId, Vec<u8>)>,
 }
 
 impl ExtBuilder {
+	pub fn black_list(mut self, black_list: Vec<(AccountId, Vec<u8>)>) -> Self {
+		self.black_list = black_list;
+		self
+	}
+
 	pub fn build(self) -> sp_io::TestExternalities {
 		let mut storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
 
@@ -241,6 +278,10 @@ impl ExtBuilder {
 		.assimilate_storage(&mut storage)
 		.ok();
 
+		pallet_mixer::GenesisConfig::<Test> { black_list: self.black_list }
+			.assimilate_storage(&mut storage)
+			.unwrap();
+
 		let mut ext = sp_io::TestExternalities::new(storage);
 		ext.execute_with(|| System::set_block_number(1));
 		ext
@@ -0,0 +1,329 @@
+//! Poseidon255 over the BLS12-381 scalar field, the hash of the `circom/mixer-psd` circuit.
+//!
+//! Only the two-input instance (`t = 3`) is implemented, it is all a Merkle tree and the note
+//! nullifier need. Constants are the `t == 3` entries of `poseidon255_constants.circom`.
+
+use ark_bls12_381::Fr;
+use ark_ff::{BigInteger, Field, MontFp, PrimeField};
+use ark_std::Zero;
+use sp_core::U256;
+
+const T: usize = 3;
+const FULL_ROUNDS: usize = 8;
+const PARTIAL_ROUNDS: usize = 56;
+
+// Round constants, `t` per round.
+const C: [Fr; T * (FULL_ROUNDS + PARTIAL_ROUNDS)] = [
+	MontFp!("50207570499218320245539736680169582180207201335688461025883902752909290481781"),
+	MontFp!("24448666467656506447555018649749346340705294023832615387641453784702583464707"),
+	MontFp!("34092944507611308604157957266676007619644244199372265837364557849561670729974"),
+	MontFp!("46954129210702959446093971191783182601726081775951103310666314834569091037713"),
+	MontFp!("38612156878839717097806285947575477749087608521505464809942918879152074545066"),
+	MontFp!("19752610610343814834081989345964253902282700341539483876504601969121084774539"),
+	MontFp!("46567545048462867923299713424766325689670511126407629551256255807498976196546"),
+	MontFp!("9520793415506326549109545537894287560752519598132096386048093015534488804808"),
+	MontFp!("22814234098357034097599682726494820560934925862581927123816510593532324971186"),
+	MontFp!("3277621627834606517208177071759088097855048183641615082769528872043050020787"),
+	MontFp!("29230456498980145088774069819561206654397510279226264474986155631775387918911"),
+	MontFp!("19087113294497892618475669593723876605785307026981218038380435259594863105240"),
+	MontFp!("39932371919358015185769877859035474336011770016475087638554815294278664040916"),
+	MontFp!("17645770319151120318035258350885823104235488352935695302274836429012504407725"),
+	MontFp!("17990728141399065004015538797609951295983853332644474801890158217822768128628"),
+	MontFp!("12607949331462269429981198199999740921418125994747028428126661151190418292729"),
+	MontFp!("33067617079394435172767143524489677593390850035349407507374659268468278200906"),
+	MontFp!("10025233623562179533044093426455032352895184661359005809314430689113735312874"),
+	MontFp!("20398677688057466110325934731430812468657996794663167456321709689030080949228"),
+	MontFp!("32085671199853825909918260218834827339732598508827083525700252644622592932757"),
+	MontFp!("36451986593067827349794003109666944974266236856145879921902940325507228739480"),
+	MontFp!("51835224419566813714481533481210630888564327175625175437244377303858990291964"),
+	MontFp!("1944662263588038198375346521900053780907777056656211622999059135594196413076"),
+	MontFp!("12995068374816903282074967132431954020410301768622808407703775963080983755183"),
+	MontFp!("13278128079226679628648689279705910775020794457648431336050464485837924986341"),
+	MontFp!("39207195481789228835625472428521288347432218258431761869689775532020546099642"),
+	MontFp!("21081768833381902942114733002158882075348844281359283013642620389621494952015"),
+	MontFp!("20751788049060260683191405008569080723662271828149227137187075968560831545739"),
+	MontFp!("20820291785607398388900832350860967875629907105847554413318238165275470374689"),
+	MontFp!("6971878585215744613467847324629115462668098071102846520957717612260531709386"),
+	MontFp!("42421164250058173810994728364144776180689735894673627964404703973460802099146"),
+	MontFp!("32890116643831560295329417521056875595733120141391587236744387068135440602102"),
+	MontFp!("42670005614507618780436482775021159957307712089310941922452133588875084445464"),
+	MontFp!("21120353743307986506720883740380468652053382764895882204680310593048134053982"),
+	MontFp!("7853308243263055176258751393326645428041138029306706980470113526802326214700"),
+	MontFp!("17545076036297840030021082424260289805456380863517895917265467158332801090765"),
+	MontFp!("29526223376722400691172584788126610514669516909826971155598997488361793726636"),
+	MontFp!("48421712782536172546302502401679048379568171245541707202282458591545347755349"),
+	MontFp!("10740853637774754893036062076749871837371049036966225040269105665447180116170"),
+	MontFp!("34042041521558704677804677569712674569738576001717295340556848855085089618161"),
+	MontFp!("24290796201833228559129233924595614281891670608675107544294264860003803501509"),
+	MontFp!("26722678647461522072509896114724736555938247563993442152746954157222882824350"),
+	MontFp!("20252491387019425681551488261397157776479297799360691728406809731508542196845"),
+	MontFp!("50322025264206689090790987370440439179141270613911973034521438238687587958097"),
+	MontFp!("17070806525931584028449131949070191143344166668070820337429561524629464200550"),
+	MontFp!("25856554324149146992239414502939942208580094928192925471532421030223074525051"),
+	MontFp!("17714998974036855356530338446243137421735047395517260588250413348153258772076"),
+	MontFp!("44833315250334176776685835079382312848180252180173884969157994737319426976437"),
+	MontFp!("35603718839327251012037553292043899153393807438387129505923567878785822738162"),
+	MontFp!("20515196301761603016197694845695272699608637099106794944737311528118558777570"),
+	MontFp!("10100400556460905874275078234698187530913105549037797180493988678937053918124"),
+	MontFp!("29943022708270799252522211109308629054849337552699067311814388215768905671554"),
+	MontFp!("33400164627534996188947689774080657908147988421361870074239537729877153299092"),
+	MontFp!("45574161704098228712016716221086232277248798839906622903502141601878895917316"),
+	MontFp!("40623265267364613450776577487319920007897396936924051398790906883872334022964"),
+	MontFp!("37929176440858430683261948300797278761072096845318183419284347376614069989808"),
+	MontFp!("12242010394227909997626655999345208835040087302065045201635069094289920778463"),
+	MontFp!("38947272924417356803622776795797899233194116520680026665045628837194239730633"),
+	MontFp!("6838505804652359252670794375725267665530548946030641535297433541475260948424"),
+	MontFp!("21345718918993308853491352363460625447157796362108157527364130872100101143328"),
+	MontFp!("26397988737034501095129796920971941795766209722106383463197090306632188634870"),
+	MontFp!("47092791129593573928369881528796435131623991381197863072979392492232678100884"),
+	MontFp!("36850972241154890671857874025605504779963735054128436776319531005864791472123"),
+	MontFp!("27893799443241349360688137159923920340185830261519093384488134540544971987330"),
+	MontFp!("34031071010517479317003393843135868322188010660871691856659878788331169912272"),
+	MontFp!("3102550735908358465878301372253437950829524988677083749179431098369388780259"),
+	MontFp!("2963742902601529003553690631564645593518709846059084207036841793643477514707"),
+	MontFp!("34538583661636382515652368664945657625216404085453317149263146639486246251503"),
+	MontFp!("49179786922858759927440465310900376749726765337268308911471491527044937447403"),
+	MontFp!("31668552784983283483593666924944066737680315058069542500069213700768949573692"),
+	MontFp!("47303630019147536941220901582952982856517915740884282232588733470564849742080"),
+	MontFp!("41561182787858915334837446901194440640033856888621022207410120224293681204923"),
+	MontFp!("40208795410444394963490428737133513683110766973508056822474493355065333491217"),
+	MontFp!("24620569969402072776192280888011017497854992833864712509770555543278833718751"),
+	MontFp!("31418811028946653724823259636547682581071379929451162101915628592655152015310"),
+	MontFp!("25964807298150242099204032696543021731332498792173212422070959505270506288817"),
+	MontFp!("31766013031271106581980804902159064978010553325475976472264348555438361464655"),
+	MontFp!("15107529391758643095716794813038523751713309080738989300826699946985294497278"),
+	MontFp!("26149402682269665088314773514719203730233986608723938665192802061570851149320"),
+	MontFp!("35053126320072620250684851851709987160095640397875384355477447570643983599564"),
+	MontFp!("14300403008645647974330112479193012555289445502185868105642182233848475582899"),
+	MontFp!("1115361296285111421659408034287929280905078990986385263729179376131648187058"),
+	MontFp!("13081790983218231663826423630402269594642175266089309953018053418396572757728"),
+	MontFp!("8235521536407760690987948268259353704300918036393867110229857008864492272243"),
+	MontFp!("10466479494603471110085160358255184712338985686117376680963274257033378093044"),
+	MontFp!("38664542015334964168214506175380731997802346810981835873522004081805448710010"),
+	MontFp!("10505351732961945434077967966272614185370876266035423475161721043839572600354"),
+	MontFp!("20492577817846125120765219135044390230365666103475157006227551523345028416653"),
+	MontFp!("9609702284002210167411637400029381999579573316818014884056109946803635903949"),
+	MontFp!("51322038232589221532635015162711708420316200766893345085330739304560856124598"),
+	MontFp!("5550990570115355104018261990072269149174220738166262960442108003631983239538"),
+	MontFp!("4918607047827293284267178559571975167840449247468221935183514469924645319431"),
+	MontFp!("29776898345506148020962461848872175656748190897224866998740940474697004907039"),
+	MontFp!("35644299241250297444013458321960523350819444468750920467435977033543433033457"),
+	MontFp!("22327941647779098096798004328483144118875590152725522668881024275272944414051"),
+	MontFp!("33086160099198007131404323726051495973839732697990380905892163657165139239986"),
+	MontFp!("28985472609889148437362238985477137130699370756855680573161102595592058796055"),
+	MontFp!("50023726556606966936520145490840107159372819054348626068296263008644442233773"),
+	MontFp!("37322822573536147831265700718470699656388790614989007043146642171715031349576"),
+	MontFp!("38512783555872270132587822550082534368549655865577551370193909696135736697844"),
+	MontFp!("40346268678138351426917552967030303154104687097467155339798400217567072805784"),
+	MontFp!("12446460574596706595202266827006842340757403121130616325345603812748836460769"),
+	MontFp!("33142108522227543534522360938923911911826451727392146582555409447496143776616"),
+	MontFp!("50427945956936999648989730996925822032835923348540241415608794299831005453973"),
+	MontFp!("27189681619715898792962291676467917480208426216006102231934586868572227499788"),
+	MontFp!("51983328868751841763333305508408581327679527569253121079747950511616126915026"),
+	MontFp!("8764133057432414528430129363242868018774698311348571048821261111352103735418"),
+	MontFp!("44437990705922308248740577994189917023682812546350712130437161069898036041927"),
+	MontFp!("36977856966896247792845521268080245209261495204736600825933277828384329384776"),
+	MontFp!("30195251433359804375013063184171780759912415700876234814106254219569138730083"),
+	MontFp!("30217593532318948476367513324463336316282060252321228784067727486658688307314"),
+	MontFp!("49902385166182411565714880065207184671103831877637178515830399091476338505528"),
+	MontFp!("10306763858151643521688107379000096066251452823515924808024537509180120590301"),
+	MontFp!("18225419295569955709959264540894574108104760504259646575014370705413341659332"),
+	MontFp!("51018556817719970036598482047146806508458652113641304661951802129285113404097"),
+	MontFp!("5586023775523332359482150390241085503538343981397337410273960103664896061318"),
+	MontFp!("1695250059692506203013076949309928562723368039356271828712091742435374824213"),
+	MontFp!("22405375952478438071934186194392756316305143676541092887399118393981076553314"),
+	MontFp!("10458537515857632902862111990551662021418451863412906712791662010765438376282"),
+	MontFp!("8572903186653093823393996934308987796939174550688349948095623496677370491349"),
+	MontFp!("26376584034857786080333290889331925169513316008268823389497001028535947725689"),
+	MontFp!("7665731866090251989409614325607843738907805016631894070026948889862506085680"),
+	MontFp!("33176116617343271952161810376649073288702375057477277258525081699287235591941"),
+	MontFp!("37424032517134964350839532755101908718388519313839739220539192283411958332893"),
+	MontFp!("4477111727391714901720242825845081209726923645743756019648580408082893195544"),
+	MontFp!("24052818921338006126265655523211029781246213697245853990174101542814404796394"),
+	MontFp!("2514052438055955996166191181555087722391472372457485613396039637466284876008"),
+	MontFp!("44108910009444457520873300686957525305558819825686714239837639878380250306456"),
+	MontFp!("50053961381772953474323558208910376421669653848634474356057386006772316553493"),
+	MontFp!("39783515728841708906635525163190887374125387723021402958995674781425394128914"),
+	MontFp!("49641089382544638469661197509889837238814455800574944108216735510964667489172"),
+	MontFp!("15915052994762066788134349126706966018769870301280961502696575741203715471620"),
+	MontFp!("14523364456910312064741833824384915970721514893070438053344001112980722532883"),
+	MontFp!("34195167034522208250614549194479039766098550244164122154197898391689885154093"),
+	MontFp!("8803956670977498145356652907229121212730957151393430875717273509992687004092"),
+	MontFp!("10663098851045790689902302726099843869982323815663085170094254490745070606259"),
+	MontFp!("36313162138032614495842830441584094833593616563230754405897067694289630735768"),
+	MontFp!("41823341436481989980258011654853822920277854389142838706117658518926209530815"),
+	MontFp!("49868962293665097907388782035445001151064884165311457103769721965990239111289"),
+	MontFp!("15771722055033274898971962892589697054998768026073330065443825965063277326619"),
+	MontFp!("24009394270524302139600659893428847877911428012188014930479974124593827497896"),
+	MontFp!("22073551841352628264163147373911841152201793863183403625155779300264423096775"),
+	MontFp!("44333156311183715375429300589583489066015514688705386055995382939123559089268"),
+	MontFp!("51522700609643396689481170171050138217819493198513721015158082512159635890772"),
+	MontFp!("16843535002948632653135556540328830165745557071105115915108598045919908377862"),
+	MontFp!("1518205506066737062294516413663386248913537376120439498858484657705789460110"),
+	MontFp!("30883441257305930623685013660530945116685938710184524403355634034115041291461"),
+	MontFp!("16130557973030629467749350011718803160555727145300402111387384840879624701824"),
+	MontFp!("8889838242573900603893251082243963471048473250580324046918980785903088175746"),
+	MontFp!("26228644761030089864593236267771722990412818339075926138914275040572783608441"),
+	MontFp!("12067734632794838098229971919863170976260163840996333398973186642649256640429"),
+	MontFp!("11968821524205636087247367764990368911340209428687552700819118754759932662079"),
+	MontFp!("45625816685425419297221616842294389119485717475905750422774940908764746840560"),
+	MontFp!("32375789793387988413411781684593792367447324753388061006491590483422128447809"),
+	MontFp!("17799244192253614178470363550506252907691959226278971058585973565519522161793"),
+	MontFp!("20965524183664786470659937049522521683689728298132353372217372464229072328582"),
+	MontFp!("48514243919521853672715701575143024980352323372870136829455127558215514845965"),
+	MontFp!("9204911014842445442204866465738363411508466624558366623130487226017623018723"),
+	MontFp!("11411882783508148290990071077460620643380914910024851216034708345401157598747"),
+	MontFp!("21978759908294544525641933568334804209246435087447053956459074200373748623404"),
+	MontFp!("25127379436060547953737705531945158130854594508326230558835868955168568062197"),
+	MontFp!("32526283415446693762767808732266261131957705956559983435307399781467557580942"),
+	MontFp!("21117898976989526626092655597834329944770204871453734194062340048170016405636"),
+	MontFp!("46380768929628708344269887683008632964580888890026960897520613586079329144581"),
+	MontFp!("39802592033155653528083875761629263174178433791252411027651109778822800913005"),
+	MontFp!("35323380468525756165110923823329391671744887569724755972653963194068807182255"),
+	MontFp!("24542080513519461416891107480874811715922199187558644231625096822693720942512"),
+	MontFp!("36962701654561243810504893765710371796632383024966885888470183474409787980664"),
+	MontFp!("17511538428050954712881014060315994278594220490801476991572564405719714025630"),
+	MontFp!("44608491521180030410798240575362826131791812973304375837127472008890229389357"),
+	MontFp!("39736430432381271700869582597841660878546487586124722290226804171216172513503"),
+	MontFp!("46014166193410208886905995703574300197059187902569422048665032227521333534188"),
+	MontFp!("8596600548778353463008917363466515479003548025312972036722931954019614269077"),
+	MontFp!("15206531116762688146228652191972227971438732578330057803903497943077798425936"),
+	MontFp!("29068148512298892172989152905246739260393303176864606634686224073777164720128"),
+	MontFp!("39586023596728563700931355289714367450122255521347297466457200654811742193440"),
+	MontFp!("27526727392901889312086885391525971454976492466303641804775436239311970881875"),
+	MontFp!("6491223227212249233572575827345665488706358465091526904036815973519451608489"),
+	MontFp!("39661844903100934118095466232381327883373982846336480010883173982561909202696"),
+	MontFp!("36966618109021206099744487145161655726793837298256336549060442500415120367749"),
+	MontFp!("12223512212940898490637099274041460462396141039347535357045458873882555463357"),
+	MontFp!("38303396997432596702386767516397451323593912575332612154360830379392053206914"),
+	MontFp!("22036410604956114612821236049340218857819342570630542797129771263395458080620"),
+	MontFp!("37192730952750566048661687478966837716282449954097744656554007040959020474565"),
+	MontFp!("36081679736007244304057373212485447018505000534036639299643664258145321997492"),
+	MontFp!("16234972515157845156347254546302253756049884771079767299929154819334994133255"),
+	MontFp!("17904936716836157876541779131216061242385468767894988462968562345774780322977"),
+	MontFp!("21714179460476085146012765269900102771027760696893388661664278472625524265920"),
+	MontFp!("202996664767557293309193718693189493265571271995400556847175225021661909888"),
+	MontFp!("37151885815048325002754741932521917166471426068184400588909976421807273328551"),
+	MontFp!("50599005035688736160466092701933521471546835926523505590060682712026791966719"),
+	MontFp!("8986959445646103225184427425621185795926770872760594291948007853933732792000"),
+];
+
+// MDS matrix, `out[i] = sum(in[j] * M[i][j])`.
+const M: [[Fr; T]; T] = [
+	[
+		MontFp!("31132615691953054007607965980645648176597305574689507130935554116487006357561"),
+		MontFp!("28902801205828158269279400851197449199863789284082006023033158644548465111632"),
+		MontFp!("45143210059012869677729928361749392446841468455419349336638033647131514405081"),
+	],
+	[
+		MontFp!("30276006336417635403007075775200220853250027416999261213812478960574352429337"),
+		MontFp!("6483479660469211213971533916480894345050052715231108210417367574936546078899"),
+		MontFp!("42754619542517870646089894161476666104769215818943096496755385097567005145741"),
+	],
+	[
+		MontFp!("10106657307845352796024965964481381662843346958391552039125632774986826122511"),
+		MontFp!("5162319290636350559691695747749883105150230856654096334503565330799341304147"),
+		MontFp!("30234092860338749143199316589007648129032419015841420983683999892965500492330"),
+	],
+];
+
+pub struct Poseidon;
+
+impl Poseidon {
+	// Hash of `[left, right]`, the circuit's `Poseidon255(2)`.
+	pub fn hash(&self, left: &Fr, right: &Fr) -> Fr {
+		let mut state = [Fr::zero(), *left, *right];
+		for round in 0..FULL_ROUNDS + PARTIAL_ROUNDS {
+			for (i, s) in state.iter_mut().enumerate() {
+				*s += C[round * T + i];
+			}
+			if (FULL_ROUNDS / 2..FULL_ROUNDS / 2 + PARTIAL_ROUNDS).contains(&round) {
+				sbox(&mut state[0]);
+			} else {
+				state.iter_mut().for_each(sbox);
+			}
+			state = mix(&state);
+		}
+		state[0]
+	}
+}
+
+fn sbox(x: &mut Fr) {
+	let x2 = x.square();
+	*x *= x2.square();
+}
+
+fn mix(state: &[Fr; T]) -> [Fr; T] {
+	let mut out = [Fr::zero(); T];
+	for (i, row) in M.iter().enumerate() {
+		for (j, m) in row.iter().enumerate() {
+			out[i] += state[j] * m;
+		}
+	}
+	out
+}
+
+// Little-endian bytes of `value`, reduced into the field.
+pub fn to_field(value: &U256) -> Fr {
+	let mut bytes = [0u8; 32];
+	value.to_little_endian(&mut bytes);
+	Fr::from_le_bytes_mod_order(&bytes)
+}
+
+pub fn from_field(value: &Fr) -> U256 {
+	U256::from_little_endian(&value.into_bigint().to_bytes_le())
+}
+
+#[test]
+fn test_poseidon() {
+	let hash = Poseidon.hash(&Fr::from(1u64), &Fr::from(2u64));
+	assert_eq!(
+		from_field(&hash),
+		U256::from_dec_str(
+			"28821147804331559602169231704816259064962739503761913593647409715501647586810"
+		)
+		.unwrap()
+	);
+}
+
+#[test]
+fn test_poseidon_matches_mixer_psd_input() {
+	// `circom/mixer-psd/input.json`
+	let secret = to_field(&U256::from_dec_str("18405586438337379222").unwrap());
+	let positions = [1u64, 1, 0, 1, 0, 0, 0, 0];
+	let path = [
+		"45134982458460434434741502639805645833068498254714591098966256408085391499367",
+		"32124200826581458060310362302006805230894747945845776474697676370297601440652",
+		"33646187916922823865935622258451714952164674255482660942215703235411158105736",
+		"43755924296839343287253321366568672844274569798987759141930641194122300834048",
+		"39404029000907277292464556408734412130261913210564395069696342233560511006152",
+		"24907123534309659921713005795092724527532698077589223246276579583330771465031",
+		"22103361713848256938655449390262013863291224679776344310249539314760174194771",
+		"28665358770471415124367990738618755861132249577405347373337125991381323369983",
+	];
+
+	let mut node = Poseidon.hash(&secret, &Fr::zero());
+	let mut index = 0u64;
+	for (depth, (pos, sibling)) in positions.iter().zip(path).enumerate() {
+		let sibling = to_field(&U256::from_dec_str(sibling).unwrap());
+		node =
+			if *pos == 1 { Poseidon.hash(&sibling, &node) } else { Poseidon.hash(&node, &sibling) };
+		index |= pos << depth;
+	}
+	assert_eq!(
+		from_field(&node),
+		U256::from_dec_str(
+			"8727774203997442153914688469522367247525723677411655131718701219520724781578"
+		)
+		.unwrap()
+	);
+
+	let nullifier_hash = Poseidon.hash(&Fr::from(index), &secret);
+	assert_eq!(
+		from_field(&nullifier_hash),
+		U256::from_dec_str(
+			"43203196448992935798727365005177953750280439357855638397196298546721957815225"
+		)
+		.unwrap()
+	);
+}
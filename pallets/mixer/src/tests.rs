@@ -1,201 +1,2031 @@
-use crate::{mock::*, Error, *};
+use crate::{
+	merkle_tree::{zero_hashes, Hasher, IncrementalMerkleTree},
+	mock::*,
+	Error, *,
+};
 use frame_support::{assert_noop, assert_ok, traits::fungible::Inspect};
 use orml_traits::MultiCurrency;
-use primitives::currency::{CurrencyId, TokenSymbol};
+use primitives::{
+	currency::{CurrencyId, TokenSymbol},
+	CircuitId,
+};
 use sp_core::U256;
+use sp_runtime::DispatchError;
+use zk_verifier::{decode_proof, verify::prepare_public_inputs, Curve, EncodedProof};
 
 #[test]
-fn test_setup_verification() {
+fn test_create_pool() {
 	new_test_ext().execute_with(|| {
-		let vk = prepare_vk_json("groth16", "bls12381", Some("3701847203724321478317961353917758270528478504282408535117312363800157867784070247396381164448597370877483548917602".to_owned()));
-		assert_ok!(MixerModule::setup_verification(
+		let dot = NativeCurrencyId::get();
+		let vbtc = CurrencyId::VToken(TokenSymbol::BTC);
+		assert_noop!(
+			MixerModule::create_pool(RuntimeOrigin::signed(1), dot, 1_000, Hasher::MiMC7, 8, 0),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			MixerModule::create_pool(RuntimeOrigin::root(), dot, 0, Hasher::MiMC7, 8, 0),
+			Error::<Test>::ZeroDenomination
+		);
+		for depth in [0, MixerMaxTreeDepth::get() + 1] {
+			assert_noop!(
+				MixerModule::create_pool(
+					RuntimeOrigin::root(),
+					dot,
+					1_000,
+					Hasher::MiMC7,
+					depth,
+					0
+				),
+				Error::<Test>::InvalidTreeDepth
+			);
+		}
+
+		assert_ok!(MixerModule::create_pool(
+			RuntimeOrigin::root(),
+			dot,
+			1_000,
+			Hasher::MiMC7,
+			8,
+			0
+		));
+		assert_ok!(MixerModule::create_pool(
+			RuntimeOrigin::root(),
+			vbtc,
+			100,
+			Hasher::Poseidon,
+			20,
+			2
+		));
+
+		assert_eq!(
+			Pools::<Test>::get(0),
+			Some(PoolInfo {
+				currency_id: dot,
+				denomination: 1_000,
+				hasher: Hasher::MiMC7,
+				depth: 8,
+				circuit_id: 0
+			})
+		);
+		assert_eq!(
+			Pools::<Test>::get(1),
+			Some(PoolInfo {
+				currency_id: vbtc,
+				denomination: 100,
+				hasher: Hasher::Poseidon,
+				depth: 20,
+				circuit_id: 2
+			})
+		);
+		assert_eq!(NextPoolId::<Test>::get(), 2);
+		System::assert_last_event(
+			Event::<Test>::PoolCreated {
+				pool_id: 1,
+				currency_id: vbtc,
+				denomination: 100,
+				hasher: Hasher::Poseidon,
+				depth: 20,
+				circuit_id: 2,
+			}
+			.into(),
+		);
+
+		assert_noop!(
+			MixerModule::deposit(RuntimeOrigin::signed(1), 2, vec![1]),
+			Error::<Test>::PoolNotFound
+		);
+	});
+}
+
+#[test]
+fn test_withdraw_verification_keys() {
+	new_test_ext().execute_with(|| {
+		let pool_id = create_pool(1_000);
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![1]));
+
+		let root = CurrentRoot::<Test>::get(pool_id);
+		let nullifier = U256::from(7);
+		let withdraw = || {
+			MixerModule::withdraw(
+				RuntimeOrigin::signed(3),
+				pool_id,
+				withdraw_proof(root, nullifier, 2, 3, 100, 0),
+				to_bytes(root),
+				to_bytes(nullifier),
+				2,
+				3,
+				100,
+				0,
+			)
+		};
+
+		assert_noop!(withdraw(), Error::<Test>::VerificationKeyIsNotSet);
+
+		// keys of the old circuit only commit to the root and the nullifier hash
+		let old_vk = prepare_vk_json("groth16", "bls12381", None);
+		assert_ok!(VkRegistry::register_verification_key(
+			RuntimeOrigin::root(),
+			WithdrawCircuitId::get(),
+			old_vk.into_bytes(),
+			Some(MixerTreeDepth::get())
+		));
+		assert_noop!(withdraw(), Error::<Test>::PublicInputsMismatch);
+
+		// nor can keys of a circuit compiled for a deeper tree
+		let (deeper_vk, _proof) =
+			simulate_withdraw_proof(Curve::Bls12381, vec![U256::zero(); WITHDRAW_PUBLIC_INPUTS]);
+		assert_ok!(VkRegistry::register_verification_key(
+			RuntimeOrigin::root(),
+			WithdrawCircuitId::get(),
+			deeper_vk.into_bytes(),
+			Some(MixerTreeDepth::get() + 1)
+		));
+		assert_noop!(withdraw(), Error::<Test>::TreeDepthMismatch);
+
+		// the old key is past its grace window, the deeper one is skipped
+		System::set_block_number(11);
+		register_simulated_verification();
+		assert_ok!(withdraw());
+	});
+}
+
+#[test]
+fn test_withdraw_with_replaced_verification_key() {
+	new_test_ext().execute_with(|| {
+		let pool_id = create_pool(1_000);
+		register_simulated_verification();
+		// default snarkjs keys are on bn128, pools skip them
+		register_simulated_verification_on(Curve::Bn254);
+
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![1]));
+		let root = CurrentRoot::<Test>::get(pool_id);
+
+		// proofs against the replaced key are accepted until its grace window ends
+		assert_ok!(MixerModule::withdraw(
+			RuntimeOrigin::signed(3),
+			pool_id,
+			withdraw_proof(root, U256::from(7), 2, 3, 100, 0),
+			to_bytes(root),
+			to_bytes(U256::from(7)),
+			2,
+			3,
+			100,
+			0
+		));
+
+		System::set_block_number(11);
+		assert_noop!(
+			MixerModule::withdraw(
+				RuntimeOrigin::signed(3),
+				pool_id,
+				withdraw_proof(root, U256::from(8), 2, 3, 100, 0),
+				to_bytes(root),
+				to_bytes(U256::from(8)),
+				2,
+				3,
+				100,
+				0
+			),
+			Error::<Test>::UnsupportedCurve
+		);
+	});
+}
+
+#[test]
+fn test_deposit() {
+	new_test_ext().execute_with(|| {
+		let pool_id = create_pool(1_000);
+		Timestamp::set_timestamp(12_000);
+		let before = Balances::balance(&1);
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![1]));
+		let after = Balances::balance(&1);
+
+		assert_eq!(before, after + 1_000);
+
+		let c = U256::from_big_endian(&vec![1]);
+		assert_eq!(Commitments::<Test>::contains_key(pool_id, c), true);
+
+		assert_eq!(Leaves::<Test>::get(pool_id, 0), Some(c));
+		assert_eq!(NextIndex::<Test>::get(pool_id), 1);
+
+		let root = U256::from_dec_str(
+			"6870572112840620857135638769407829567376800590910451492860072723112109574334",
+		)
+		.unwrap();
+		assert_eq!(Roots::<Test>::contains_key(pool_id, root), true);
+		assert_eq!(CurrentRoot::<Test>::get(pool_id), root);
+		System::assert_last_event(
+			Event::<Test>::Deposited {
+				pool_id,
+				commitment: vec![1],
+				commit_h256: c,
+				leaf_index: 0,
+				root,
+				timestamp: 12_000,
+			}
+			.into(),
+		);
+
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![2]));
+		assert!(matches!(
+			System::events().last().map(|record| &record.event),
+			Some(RuntimeEvent::MixerModule(Event::Deposited { leaf_index: 1, .. }))
+		));
+	});
+}
+
+#[test]
+fn test_otp_deposits_share_the_tree() {
+	use crate::merkle_tree::MerkleTree;
+
+	new_test_ext().execute_with(|| {
+		let pool_id = create_pool(1_000);
+		let otp_root = set_simulated_otp(1);
+		let otp_proof = |timestamp| otp_proof(otp_root, timestamp);
+		Timestamp::set_timestamp(1_000);
+		let pool_before = Balances::balance(&account_id::<Test>());
+
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![1]));
+		assert_ok!(MixerModule::deposit_with_naive_otp(
 			RuntimeOrigin::signed(1),
-			vk.as_bytes().into()
+			pool_id,
+			vec![2],
+			otp_proof(1),
+			otp_root.to_string().into_bytes(),
+			1
+		));
+		assert_ok!(MixerModule::deposit_with_block_time_otp(
+			RuntimeOrigin::signed(1),
+			pool_id,
+			vec![3],
+			otp_proof(2_000),
+			otp_root.to_string().into_bytes(),
+			2_000
+		));
+
+		// every deposit extends the one tree, the latest root covers all three notes
+		let mut tree = MerkleTree::new(MixerTreeDepth::get() as usize);
+		for commitment in 1..=3u64 {
+			tree.insert(U256::from(commitment)).unwrap();
+			assert_eq!(
+				Leaves::<Test>::get(pool_id, commitment as u32 - 1),
+				Some(commitment.into())
+			);
+		}
+		assert_eq!(NextIndex::<Test>::get(pool_id), 3);
+		assert_eq!(CurrentRoot::<Test>::get(pool_id), tree.get_root());
+		assert!(is_known_root::<Test>(pool_id, tree.get_root()));
+		assert_eq!(Balances::balance(&account_id::<Test>()), pool_before + 3_000);
+
+		assert_noop!(
+			MixerModule::deposit_with_naive_otp(
+				RuntimeOrigin::signed(1),
+				pool_id,
+				vec![2],
+				otp_proof(3_000),
+				otp_root.to_string().into_bytes(),
+				3_000
+			),
+			Error::<Test>::CommitmentHasBeanSubmitted
+		);
+	});
+}
+
+#[test]
+fn test_otp_malformed_roots() {
+	new_test_ext().execute_with(|| {
+		let pool_id = create_pool(1_000);
+		let otp_root = set_simulated_otp(1);
+		Timestamp::set_timestamp(1_000);
+
+		let malformed: [Vec<u8>; 4] = [
+			vec![0xff, 0xfe],
+			b"0x1f".to_vec(),
+			b"".to_vec(),
+			format!("{}0", U256::MAX).into_bytes(),
+		];
+		for root in malformed {
+			assert_noop!(
+				Otp::set_otp_commitment(RuntimeOrigin::signed(2), root.clone()),
+				pallet_otp::Error::<Test>::InvalidRoot
+			);
+			assert_noop!(
+				MixerModule::deposit_with_naive_otp(
+					RuntimeOrigin::signed(1),
+					pool_id,
+					vec![1],
+					otp_proof(otp_root, 1),
+					root.clone(),
+					1
+				),
+				pallet_otp::Error::<Test>::InvalidRoot
+			);
+			assert_noop!(
+				MixerModule::deposit_with_block_time_otp(
+					RuntimeOrigin::signed(1),
+					pool_id,
+					vec![1],
+					otp_proof(otp_root, 2_000),
+					root,
+					2_000
+				),
+				pallet_otp::Error::<Test>::InvalidRoot
+			);
+		}
+	});
+}
+
+#[test]
+fn test_otp_guarded_withdraw() {
+	use sp_runtime::traits::SignedExtension;
+
+	new_test_ext().execute_with(|| {
+		let pool_id = create_pool(1_000);
+		register_simulated_verification();
+		let otp_root = set_simulated_otp(1);
+		let other_root = set_simulated_otp(3);
+		Timestamp::set_timestamp(1_000);
+
+		// the leaf of a note guarded by account 1 commits to its guard
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(2), pool_id, vec![1]));
+		let root = CurrentRoot::<Test>::get(pool_id);
+		let nullifier = U256::from(7);
+		let proof = guarded_withdraw_proof(root, nullifier, 2, 3, 100, 0, Some(1));
+		// the guard signs, the password alone is worthless to anyone copying it from the pool
+		let withdraw_with_otp = |who, otp_root: U256, timestamp| {
+			MixerModule::withdraw_with_otp(
+				RuntimeOrigin::signed(who),
+				pool_id,
+				proof.clone(),
+				to_bytes(root),
+				to_bytes(nullifier),
+				2,
+				3,
+				100,
+				0,
+				otp_proof(otp_root, timestamp),
+				otp_root.to_string().into_bytes(),
+				timestamp,
+			)
+		};
+
+		// the note alone is not enough
+		assert_noop!(
+			MixerModule::withdraw(
+				RuntimeOrigin::signed(3),
+				pool_id,
+				proof.clone(),
+				to_bytes(root),
+				to_bytes(nullifier),
+				2,
+				3,
+				100,
+				0,
+			),
+			Error::<Test>::ProofVerificationFalse
+		);
+		assert_noop!(
+			MixerModule::swap(
+				RuntimeOrigin::signed(3),
+				pool_id,
+				guarded_withdraw_proof(root, nullifier, 2, 3, 0, 0, Some(1)),
+				to_bytes(root),
+				to_bytes(nullifier),
+				0,
+				2,
+			),
+			Error::<Test>::ProofVerificationFalse
+		);
+
+		// nor the password of an account the note is not guarded by
+		assert_noop!(
+			withdraw_with_otp(3, other_root, 2_000),
+			Error::<Test>::ProofVerificationFalse
+		);
+		// nor another account's password
+		assert_noop!(
+			withdraw_with_otp(1, other_root, 2_000),
+			pallet_otp::Error::<Test>::NotMerkelRootOwner
+		);
+
+		let call = RuntimeCall::MixerModule(Call::withdraw_with_otp {
+			pool_id,
+			proof: proof.clone(),
+			root: to_bytes(root),
+			nullifier_hash: to_bytes(nullifier),
+			receiver: 2,
+			relayer: 3,
+			fee: 100,
+			refund: 0,
+			otp_proof: otp_proof(otp_root, 2_000),
+			otp_root: otp_root.to_string().into_bytes(),
+			timestamp: 2_000,
+		});
+		let validity = CheckMixerCall::<Test>::new().validate(&1, &call, &Default::default(), 0);
+		assert_eq!(validity, Ok(Default::default()));
+		// the guard's password is no use to the relayer
+		assert_noop!(withdraw_with_otp(3, otp_root, 2_000), Error::<Test>::ProofVerificationFalse);
+
+		let receiver_before = Balances::balance(&2);
+		assert_ok!(withdraw_with_otp(1, otp_root, 2_000));
+		assert_eq!(Balances::balance(&2), receiver_before + 900);
+		assert!(NullifierHashes::<Test>::contains_key(pool_id, nullifier));
+
+		assert_noop!(withdraw_with_otp(1, otp_root, 3_000), Error::<Test>::NoteHasBeanSpent);
+		assert!(CheckMixerCall::<Test>::new()
+			.validate(&1, &call, &Default::default(), 0)
+			.is_err());
+		// the note is checked before the password, which a stale swap does not get to
+		assert_noop!(
+			MixerModule::swap_with_otp(
+				RuntimeOrigin::signed(1),
+				pool_id,
+				guarded_withdraw_proof(root, nullifier, 2, 1, 0, 0, Some(1)),
+				to_bytes(root),
+				to_bytes(nullifier),
+				0,
+				2,
+				EncodedProof::Json(vec![]),
+				otp_root.to_string().into_bytes(),
+				4_000,
+			),
+			Error::<Test>::NoteHasBeanSpent
+		);
+	});
+}
+
+#[test]
+fn test_pools_are_isolated() {
+	new_test_ext().execute_with(|| {
+		let small = create_pool(100);
+		let large = create_pool(1_000);
+
+		let before = Balances::balance(&1);
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), small, vec![1]));
+		assert_eq!(Balances::balance(&1), before - 100);
+
+		// the same commitment is fresh in another pool and lands at its first leaf
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), large, vec![1]));
+		assert_eq!(Balances::balance(&1), before - 1_100);
+		assert_eq!(NextIndex::<Test>::get(large), 1);
+		assert_eq!(CurrentRoot::<Test>::get(small), CurrentRoot::<Test>::get(large));
+
+		assert_noop!(
+			MixerModule::deposit(RuntimeOrigin::signed(1), small, vec![1]),
+			Error::<Test>::CommitmentHasBeanSubmitted
+		);
+
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), small, vec![2]));
+		let root = CurrentRoot::<Test>::get(small);
+		assert!(is_known_root::<Test>(small, root));
+		assert!(!is_known_root::<Test>(large, root));
+	});
+}
+
+#[test]
+fn test_merkle_path_queries() {
+	use crate::merkle_tree::MerkleTree;
+
+	new_test_ext().execute_with(|| {
+		assert_eq!(merkle_root::<Test>(0), None);
+		let pool_id = create_pool(1_000);
+		assert_eq!(merkle_root::<Test>(pool_id), Some(MerkleTree::new(8).update()));
+		assert_eq!(get_merkle_path::<Test>(pool_id, 0), None);
+
+		let mut tree = MerkleTree::new(8);
+		for commitment in 1..=3u8 {
+			assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![commitment]));
+			tree.insert(U256::from(commitment)).unwrap();
+		}
+		assert_eq!(merkle_root::<Test>(pool_id), Some(tree.get_root()));
+		assert_eq!(leaf_index::<Test>(pool_id, U256::from(3)), Some(2));
+		assert_eq!(leaf_index::<Test>(pool_id, U256::from(4)), None);
+
+		let path = get_merkle_path::<Test>(pool_id, 2).unwrap();
+		assert_eq!(path.root, tree.get_root());
+		assert_eq!(path.leaf, U256::from(3));
+		assert_eq!(path.path_elements, tree.get_proof(2));
+		assert_eq!(&path.path_positions[..3], &[1, 0, 1]);
+		assert_eq!(get_merkle_path::<Test>(pool_id, 3), None);
+
+		// siblings are read from the stored nodes, zero hashes right of the latest leaf
+		for commitment in 4..=5u8 {
+			assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(2), pool_id, vec![commitment]));
+			tree.insert(U256::from(commitment)).unwrap();
+		}
+		for index in 0..5 {
+			let path = get_merkle_path::<Test>(pool_id, index).unwrap();
+			assert_eq!(path.path_elements, tree.get_proof(index as usize));
+		}
+		assert_eq!(Nodes::<Test>::get(pool_id, (1, 2)), Some(tree.get_leaf(1, 2)));
+		assert_eq!(Nodes::<Test>::get(pool_id, (1, 3)), None);
+
+		assert!(!is_spent::<Test>(pool_id, U256::from(7)));
+		NullifierHashes::<Test>::insert(pool_id, U256::from(7), true);
+		assert!(is_spent::<Test>(pool_id, U256::from(7)));
+	});
+}
+
+#[test]
+fn test_migrate_to_v1() {
+	use crate::migrations::v1::{v0, MigrateToV1, LEGACY_POOL_ID};
+	use frame_support::traits::{ConstU32, GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(0).put::<MixerModule>();
+		let leaves: Vec<_> = (1..=3u64).map(U256::from).collect();
+		v0::MerkleVec::<Test>::put(BoundedVec::truncate_from(leaves.clone()));
+		// the BN254 roots of every deposit
+		for stale in 11..=13u64 {
+			v0::Roots::<Test>::insert(U256::from(stale), true);
+		}
+		for leaf in &leaves {
+			v0::Commitments::<Test>::insert(leaf, true);
+		}
+		v0::NullifierHashes::<Test>::insert(U256::from(7), true);
+		v0::VerificationKeyStorage::<Test>::put(vec![1u8, 2, 3]);
+		v0::BlackList::<Test>::insert(3, true);
+
+		MigrateToV1::<Test, MixerBalance, WithdrawCircuitId, ConstU32<10>>::on_runtime_upgrade();
+
+		let pool_id = LEGACY_POOL_ID;
+		assert_eq!(
+			Pools::<Test>::get(pool_id),
+			Some(PoolInfo {
+				currency_id: NativeCurrencyId::get(),
+				denomination: 1_000,
+				hasher: Hasher::MiMC7,
+				depth: 8,
+				circuit_id: WithdrawCircuitId::get()
+			})
+		);
+		assert_eq!(NextPoolId::<Test>::get(), 1);
+
+		let mut tree = IncrementalMerkleTree::new(8);
+		let roots: Vec<_> = leaves
+			.iter()
+			.map(|leaf| {
+				tree.insert(*leaf).unwrap();
+				tree.get_root()
+			})
+			.collect();
+		assert_eq!(NextIndex::<Test>::get(pool_id), 3);
+		assert_eq!(CurrentRoot::<Test>::get(pool_id), roots[2]);
+		// the replayed roots fill the history, in the order of the deposits
+		for root in &roots {
+			assert!(is_known_root::<Test>(pool_id, *root));
+		}
+		assert!(!is_known_root::<Test>(pool_id, U256::from(13)));
+		assert_eq!(Roots::<Test>::iter_key_prefix(pool_id).count(), 3);
+		assert_eq!(
+			RootHistory::<Test>::get(pool_id, CurrentRootIndex::<Test>::get(pool_id)),
+			Some(roots[2])
+		);
+		assert_eq!(Leaves::<Test>::get(pool_id, 1), Some(U256::from(2)));
+		assert_eq!(Commitments::<Test>::iter_key_prefix(pool_id).count(), 3);
+		assert_eq!(leaf_index::<Test>(pool_id, U256::from(3)), Some(2));
+		assert_eq!(get_merkle_path::<Test>(pool_id, 2).unwrap().root, roots[2]);
+		assert!(is_spent::<Test>(pool_id, U256::from(7)));
+		assert_eq!(BlackList::<Test>::get(3), Some(BoundedVec::default()));
+		assert!(!v0::MerkleVec::<Test>::exists());
+		assert!(!v0::VerificationKeyStorage::<Test>::exists());
+		assert_eq!(MixerModule::on_chain_storage_version(), 1);
+
+		// the next deposit continues from the replayed frontier and pushes out the oldest root
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![4]));
+		tree.insert(U256::from(4)).unwrap();
+		assert_eq!(CurrentRoot::<Test>::get(pool_id), tree.get_root());
+		assert!(!is_known_root::<Test>(pool_id, roots[0]));
+		assert_noop!(
+			MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![3]),
+			Error::<Test>::CommitmentHasBeanSubmitted
+		);
+
+		// and it runs once
+		MigrateToV1::<Test, MixerBalance, WithdrawCircuitId, ConstU32<10>>::on_runtime_upgrade();
+		assert_eq!(NextIndex::<Test>::get(pool_id), 4);
+	});
+}
+
+#[test]
+fn test_migrate_to_v1_bounds() {
+	use crate::migrations::v1::{v0, MigrateToV1, LEGACY_POOL_ID};
+	use frame_support::traits::{ConstU32, OnRuntimeUpgrade, StorageVersion};
+
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(0).put::<MixerModule>();
+		// one leaf more than a tree of depth 8 holds
+		let leaves: Vec<_> = (1..=257u64).map(U256::from).collect();
+		v0::MerkleVec::<Test>::put(BoundedVec::truncate_from(leaves));
+		for account in 1..=3 {
+			v0::BlackList::<Test>::insert(account, true);
+		}
+
+		MigrateToV1::<Test, MixerBalance, WithdrawCircuitId, ConstU32<2>>::on_runtime_upgrade();
+
+		let pool_id = LEGACY_POOL_ID;
+		assert_eq!(NextIndex::<Test>::get(pool_id), 256);
+		assert_eq!(Roots::<Test>::iter_key_prefix(pool_id).count(), 3);
+		assert_eq!(Leaves::<Test>::get(pool_id, 255), Some(U256::from(256)));
+		assert!(!Commitments::<Test>::contains_key(pool_id, U256::from(257)));
+		// accounts over the bound stay blacklisted, without a reason
+		assert_eq!(BlackList::<Test>::iter_keys().count(), 3);
+		assert_eq!(BlackList::<Test>::iter_values().count(), 2);
+		assert_noop!(
+			MixerModule::deposit(RuntimeOrigin::signed(3), pool_id, vec![1]),
+			Error::<Test>::BlacklistRejected
+		);
+	});
+}
+
+#[test]
+fn test_root_history_is_bounded() {
+	new_test_ext().execute_with(|| {
+		let pool_id = create_pool(1_000);
+		let mut roots = vec![];
+		for c in 1..=4u8 {
+			assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![c]));
+			roots.push(CurrentRoot::<Test>::get(pool_id));
+		}
+
+		// RootHistorySize is 3 in the mock, the first root has been pruned
+		assert!(!is_known_root::<Test>(pool_id, roots[0]));
+		for root in &roots[1..] {
+			assert!(is_known_root::<Test>(pool_id, *root));
+		}
+		assert_eq!(Roots::<Test>::iter_key_prefix(pool_id).count(), 3);
+		assert_eq!(
+			RootHistory::<Test>::get(pool_id, CurrentRootIndex::<Test>::get(pool_id)),
+			Some(roots[3])
+		);
+	});
+}
+
+#[test]
+fn test_withdraw() {
+	new_test_ext().execute_with(|| {
+		let pool_id = create_pool(1_000);
+		register_simulated_verification();
+
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![1]));
+
+		assert_noop!(
+			MixerModule::withdraw(
+				RuntimeOrigin::signed(1),
+				pool_id,
+				EncodedProof::Json(vec![1]),
+				vec![1],
+				vec![1],
+				2,
+				3,
+				0,
+				0
+			),
+			Error::<Test>::CanNotFindMerkelRoot
+		);
+
+		let root = CurrentRoot::<Test>::get(pool_id);
+		let root_bytes = to_bytes(root);
+
+		assert_noop!(
+			MixerModule::withdraw(
+				RuntimeOrigin::signed(1),
+				pool_id,
+				EncodedProof::Json(vec![1]),
+				root_bytes.clone(),
+				vec![1],
+				2,
+				3,
+				0,
+				0
+			),
+			Error::<Test>::MalformedProof
+		);
+
+		let incorrect_proof = prepare_incorrect_proof_json("groth16", "bls12381", None);
+
+		assert_noop!(
+			MixerModule::withdraw(
+				RuntimeOrigin::signed(1),
+				pool_id,
+				incorrect_proof.as_bytes().into(),
+				root_bytes.clone(),
+				vec![1u8],
+				2,
+				3,
+				0,
+				0
+			),
+			Error::<Test>::ProofCreationError
+		);
+
+		let nullifier = U256::from(7);
+		let nullifier_bytes = to_bytes(nullifier);
+		let proof = withdraw_proof(root, nullifier, 2, 3, 100, 0);
+
+		assert_noop!(
+			MixerModule::withdraw(
+				RuntimeOrigin::signed(3),
+				pool_id,
+				proof.clone(),
+				root_bytes.clone(),
+				nullifier_bytes.clone(),
+				2,
+				3,
+				1_001,
+				0
+			),
+			Error::<Test>::FeeExceedsDenomination
+		);
+		assert_noop!(
+			MixerModule::withdraw(
+				RuntimeOrigin::signed(3),
+				pool_id,
+				proof.clone(),
+				root_bytes.clone(),
+				nullifier_bytes.clone(),
+				2,
+				3,
+				100,
+				1
+			),
+			Error::<Test>::InvalidRefund
+		);
+
+		// a copied proof can not be redirected to another receiver, relayer or fee
+		for (receiver, relayer, fee) in [(4, 3, 100), (2, 4, 100), (2, 3, 200)] {
+			assert_noop!(
+				MixerModule::withdraw(
+					RuntimeOrigin::signed(4),
+					pool_id,
+					proof.clone(),
+					root_bytes.clone(),
+					nullifier_bytes.clone(),
+					receiver,
+					relayer,
+					fee,
+					0
+				),
+				Error::<Test>::ProofVerificationFalse
+			);
+		}
+
+		let receiver_before = Balances::balance(&2);
+		let relayer_before = Balances::balance(&3);
+		assert_ok!(MixerModule::withdraw(
+			RuntimeOrigin::signed(3),
+			pool_id,
+			proof.clone(),
+			root_bytes.clone(),
+			nullifier_bytes.clone(),
+			2,
+			3,
+			100,
+			0
+		));
+		assert_eq!(Balances::balance(&2), receiver_before + 900);
+		assert_eq!(Balances::balance(&3), relayer_before + 100);
+		System::assert_last_event(
+			Event::<Test>::Withdrawed {
+				pool_id,
+				nullifier_hash: nullifier,
+				receiver: 2,
+				relayer: 3,
+				fee: 100,
+			}
+			.into(),
+		);
+
+		assert_noop!(
+			MixerModule::withdraw(
+				RuntimeOrigin::signed(3),
+				pool_id,
+				proof,
+				root_bytes,
+				nullifier_bytes,
+				2,
+				3,
+				100,
+				0
+			),
+			Error::<Test>::NoteHasBeanSpent
+		);
+	});
+}
+
+#[test]
+fn test_withdraw_on_bn128_is_unsupported() {
+	new_test_ext().execute_with(|| {
+		let pool_id = create_pool(1_000);
+		register_simulated_verification_on(Curve::Bn254);
+
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![1]));
+
+		// a bn128 circuit hashes in another field than the tree, it can not prove the root
+		let root = CurrentRoot::<Test>::get(pool_id);
+		let nullifier = U256::from(7);
+		let inputs = withdraw_public_inputs::<Test>(root, nullifier, &2, &3, 100, 0, None);
+		let (_vk, proof) = simulate_withdraw_proof(Curve::Bn254, inputs);
+
+		assert_noop!(
+			MixerModule::withdraw(
+				RuntimeOrigin::signed(3),
+				pool_id,
+				EncodedProof::Json(proof.into_bytes()),
+				to_bytes(root),
+				to_bytes(nullifier),
+				2,
+				3,
+				100,
+				0
+			),
+			Error::<Test>::UnsupportedCurve
+		);
+	});
+}
+
+#[test]
+fn test_withdraw_rejects_public_inputs_outside_of_field() {
+	new_test_ext().execute_with(|| {
+		let pool_id = create_pool(1_000);
+		register_simulated_verification();
+
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![1]));
+
+		// out of range nullifier hashes used to be read as zero, so one proof could be
+		// replayed under many nullifiers
+		let root = CurrentRoot::<Test>::get(pool_id);
+		let inputs = withdraw_public_inputs::<Test>(root, U256::zero(), &2, &3, 0, 0, None);
+		let (_vk, proof) = simulate_withdraw_proof(Curve::Bls12381, inputs);
+
+		assert_noop!(
+			MixerModule::withdraw(
+				RuntimeOrigin::signed(3),
+				pool_id,
+				EncodedProof::Json(proof.into_bytes()),
+				to_bytes(root),
+				to_bytes(U256::MAX),
+				2,
+				3,
+				0,
+				0
+			),
+			Error::<Test>::MalformedPublicInputs
+		);
+	});
+}
+
+#[test]
+fn test_withdraw_with_compressed_proof() {
+	new_test_ext().execute_with(|| {
+		let pool_id = create_pool(1_000);
+		register_simulated_verification();
+
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![1]));
+		let root = CurrentRoot::<Test>::get(pool_id);
+		let nullifier = U256::from(7);
+		let inputs = withdraw_public_inputs::<Test>(root, nullifier, &2, &3, 100, 0, None);
+		let (_vk, proof) = simulate_withdraw_proof(Curve::Bls12381, inputs);
+		let proof = decode_proof(&EncodedProof::Json(proof.into_bytes())).unwrap().to_compressed();
+
+		assert_noop!(
+			MixerModule::withdraw(
+				RuntimeOrigin::signed(3),
+				pool_id,
+				proof.clone(),
+				to_bytes(root),
+				to_bytes(nullifier),
+				2,
+				3,
+				200,
+				0
+			),
+			Error::<Test>::ProofVerificationFalse
+		);
+		let relayer_before = Balances::balance(&3);
+		assert_ok!(MixerModule::withdraw(
+			RuntimeOrigin::signed(3),
+			pool_id,
+			proof,
+			to_bytes(root),
+			to_bytes(nullifier),
+			2,
+			3,
+			100,
+			0
+		));
+		assert_eq!(Balances::balance(&3), relayer_before + 100);
+	});
+}
+
+#[test]
+fn test_withdraw_with_compressed_proof_off_the_curve() {
+	new_test_ext().execute_with(|| {
+		let pool_id = create_pool(1_000);
+		register_simulated_verification();
+
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![1]));
+		let root = CurrentRoot::<Test>::get(pool_id);
+
+		assert_noop!(
+			MixerModule::withdraw(
+				RuntimeOrigin::signed(3),
+				pool_id,
+				EncodedProof::Bls12381([0x9f; 48], [0x9f; 96], [0x9f; 48]),
+				to_bytes(root),
+				to_bytes(U256::from(7)),
+				2,
+				3,
+				0,
+				0
+			),
+			Error::<Test>::ProofCreationError
+		);
+	});
+}
+
+#[test]
+fn test_withdraw_with_refund() {
+	new_test_ext().execute_with(|| {
+		let vbtc = CurrencyId::VToken(TokenSymbol::BTC);
+		let pool_id = create_pool_in(vbtc, 10);
+		register_simulated_verification();
+
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![1]));
+		let root = CurrentRoot::<Test>::get(pool_id);
+		let nullifier = U256::from(7);
+		let proof = withdraw_proof(root, nullifier, 2, 3, 1, 50);
+
+		// only the relayer itself can pay the refund
+		assert_noop!(
+			MixerModule::withdraw(
+				RuntimeOrigin::signed(1),
+				pool_id,
+				proof.clone(),
+				to_bytes(root),
+				to_bytes(nullifier),
+				2,
+				3,
+				1,
+				50
+			),
+			Error::<Test>::InvalidRefund
+		);
+
+		let native_before = (Balances::balance(&2), Balances::balance(&3));
+		assert_ok!(MixerModule::withdraw(
+			RuntimeOrigin::signed(3),
+			pool_id,
+			proof,
+			to_bytes(root),
+			to_bytes(nullifier),
+			2,
+			3,
+			1,
+			50
+		));
+
+		assert_eq!(Currencies::free_balance(vbtc, &2), 5_009);
+		assert_eq!(Currencies::free_balance(vbtc, &3), 5_001);
+		assert_eq!(Balances::balance(&2), native_before.0 + 50);
+		assert_eq!(Balances::balance(&3), native_before.1 - 50);
+	});
+}
+
+#[test]
+fn test_batch_withdraw() {
+	new_test_ext().execute_with(|| {
+		let pool_id = create_pool(1_000);
+		register_simulated_verification();
+
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![1]));
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![2]));
+		let root = CurrentRoot::<Test>::get(pool_id);
+		let withdraw = |nullifier: u64, receiver, fee| BatchedWithdraw {
+			proof: withdraw_proof(root, nullifier.into(), receiver, 3, fee, 0),
+			root: to_bytes(root),
+			nullifier_hash: to_bytes(nullifier.into()),
+			receiver,
+			fee,
+		};
+
+		// a proof for another fee fails the whole batch
+		let mut forged = withdraw(8, 4, 100);
+		forged.fee = 0;
+		assert_noop!(
+			MixerModule::batch_withdraw(
+				RuntimeOrigin::signed(3),
+				pool_id,
+				vec![withdraw(7, 2, 100), forged]
+			),
+			Error::<Test>::ProofVerificationFalse
+		);
+
+		let receiver_before = Balances::balance(&2);
+		let relayer_before = Balances::balance(&3);
+		assert_ok!(MixerModule::batch_withdraw(
+			RuntimeOrigin::signed(3),
+			pool_id,
+			vec![withdraw(7, 2, 100), withdraw(8, 4, 50)]
+		));
+		assert_eq!(Balances::balance(&2), receiver_before + 900);
+		assert_eq!(Balances::balance(&4), 950);
+		assert_eq!(Balances::balance(&3), relayer_before + 150);
+		assert!(NullifierHashes::<Test>::contains_key(pool_id, U256::from(7)));
+		assert!(NullifierHashes::<Test>::contains_key(pool_id, U256::from(8)));
+
+		assert_noop!(
+			MixerModule::batch_withdraw(RuntimeOrigin::signed(3), pool_id, vec![withdraw(7, 2, 0)]),
+			Error::<Test>::NoteHasBeanSpent
+		);
+	});
+}
+
+#[test]
+fn test_batch_withdraw_bounds() {
+	new_test_ext().execute_with(|| {
+		let pool_id = create_pool(1_000);
+		register_simulated_verification();
+
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![1]));
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![2]));
+		let root = CurrentRoot::<Test>::get(pool_id);
+		let withdraw = BatchedWithdraw {
+			proof: withdraw_proof(root, 7.into(), 2, 3, 0, 0),
+			root: to_bytes(root),
+			nullifier_hash: to_bytes(7.into()),
+			receiver: 2,
+			fee: 0,
+		};
+
+		assert_noop!(
+			MixerModule::batch_withdraw(RuntimeOrigin::signed(3), pool_id, vec![]),
+			Error::<Test>::EmptyBatch
+		);
+		assert_noop!(
+			MixerModule::batch_withdraw(
+				RuntimeOrigin::signed(3),
+				pool_id,
+				vec![withdraw.clone(); MaxBatchWithdraws::get() as usize + 1]
+			),
+			Error::<Test>::TooManyWithdraws
+		);
+		// the same note can not be spent twice within a batch either
+		assert_noop!(
+			MixerModule::batch_withdraw(
+				RuntimeOrigin::signed(3),
+				pool_id,
+				vec![withdraw.clone(), withdraw]
+			),
+			Error::<Test>::NoteHasBeanSpent
+		);
+	});
+}
+
+#[test]
+fn test_withdraw_unsigned() {
+	use codec::Encode;
+	use frame_support::unsigned::ValidateUnsigned;
+	use sp_runtime::transaction_validity::{InvalidTransaction, TransactionSource};
+
+	new_test_ext().execute_with(|| {
+		let pool_id = create_pool(1_000);
+		register_simulated_verification();
+
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![1]));
+		let root = CurrentRoot::<Test>::get(pool_id);
+		let nullifier = U256::from(7);
+		// account 4 has never held funds
+		let proof = withdraw_proof(root, nullifier, 4, 3, 100, 0);
+		let call = |receiver| crate::Call::<Test>::withdraw_unsigned {
+			pool_id,
+			proof: proof.clone(),
+			root: to_bytes(root),
+			nullifier_hash: to_bytes(nullifier),
+			receiver,
+			relayer: 3,
+			fee: 100,
+		};
+
+		let valid = MixerModule::validate_unsigned(TransactionSource::External, &call(4)).unwrap();
+		assert_eq!(valid.provides, vec![("MixerWithdraw", (pool_id, nullifier)).encode()]);
+		assert_eq!(valid.longevity, 5);
+		assert_eq!(
+			MixerModule::validate_unsigned(TransactionSource::External, &call(2)),
+			Err(InvalidTransaction::BadProof.into())
+		);
+		// the proof is left to dispatch
+		assert_ok!(MixerModule::pre_dispatch(&call(2)));
+
+		assert_noop!(
+			MixerModule::withdraw_unsigned(
+				RuntimeOrigin::signed(4),
+				pool_id,
+				proof.clone(),
+				to_bytes(root),
+				to_bytes(nullifier),
+				4,
+				3,
+				100
+			),
+			DispatchError::BadOrigin
+		);
+
+		let relayer_before = Balances::balance(&3);
+		assert_ok!(MixerModule::withdraw_unsigned(
+			RuntimeOrigin::none(),
+			pool_id,
+			proof.clone(),
+			to_bytes(root),
+			to_bytes(nullifier),
+			4,
+			3,
+			100
+		));
+		assert_eq!(Balances::balance(&4), 900);
+		assert_eq!(Balances::balance(&3), relayer_before + 100);
+
+		assert_eq!(
+			MixerModule::validate_unsigned(TransactionSource::External, &call(4)),
+			Err(InvalidTransaction::Stale.into())
+		);
+		assert_eq!(MixerModule::pre_dispatch(&call(4)), Err(InvalidTransaction::Stale.into()));
+	});
+}
+
+#[test]
+fn test_check_mixer_call() {
+	use frame_support::dispatch::DispatchInfo;
+	use sp_runtime::{traits::SignedExtension, transaction_validity::InvalidTransaction};
+
+	new_test_ext().execute_with(|| {
+		let pool_id = create_pool(1_000);
+		register_simulated_verification();
+
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![1]));
+		let root = CurrentRoot::<Test>::get(pool_id);
+		let nullifier = U256::from(7);
+		let withdraw = |root: U256, receiver| {
+			RuntimeCall::MixerModule(crate::Call::withdraw {
+				pool_id,
+				// proofs are only checked in dispatch
+				proof: EncodedProof::Json(vec![]),
+				root: to_bytes(root),
+				nullifier_hash: to_bytes(nullifier),
+				receiver,
+				relayer: 3,
+				fee: 100,
+				refund: 0,
+			})
+		};
+		let validate = |who, call: &RuntimeCall| {
+			CheckMixerCall::<Test>::new().validate(&who, call, &DispatchInfo::default(), 0)
+		};
+		let blacklisted = Err(InvalidTransaction::Custom(extension::BLACKLISTED).into());
+
+		// the proof is unchecked, so the call claims no tag a junk proof could take from the note
+		assert_eq!(validate(3, &withdraw(root, 2)), Ok(Default::default()));
+		assert_eq!(validate(3, &withdraw(U256::from(1), 2)), Err(InvalidTransaction::Stale.into()));
+		// other pallets' calls pass untouched
+		let remark = RuntimeCall::System(frame_system::Call::remark { remark: vec![] });
+		assert_eq!(validate(3, &remark), Ok(Default::default()));
+
+		assert_ok!(MixerModule::add_black_list(RuntimeOrigin::root(), 2, vec![]));
+		assert_eq!(validate(3, &withdraw(root, 2)), blacklisted);
+		let deposit =
+			RuntimeCall::MixerModule(crate::Call::deposit { pool_id, commitment: vec![2] });
+		assert_eq!(validate(2, &deposit), blacklisted);
+		assert_ok!(validate(1, &deposit));
+		assert_ok!(MixerModule::remove_black_list(RuntimeOrigin::root(), 2));
+
+		let batch = |receivers: Vec<AccountId>| {
+			RuntimeCall::MixerModule(crate::Call::batch_withdraw {
+				pool_id,
+				withdraws: receivers
+					.into_iter()
+					.map(|receiver| BatchedWithdraw {
+						proof: EncodedProof::Json(vec![]),
+						root: to_bytes(root),
+						nullifier_hash: to_bytes(U256::from(receiver)),
+						receiver,
+						fee: 0,
+					})
+					.collect(),
+			})
+		};
+		assert_eq!(validate(3, &batch(vec![1, 2])), Ok(Default::default()));
+		assert_eq!(validate(3, &batch(vec![2, 2])), Err(InvalidTransaction::Stale.into()));
+
+		assert_ok!(MixerModule::withdraw(
+			RuntimeOrigin::signed(3),
+			pool_id,
+			withdraw_proof(root, nullifier, 2, 3, 100, 0),
+			to_bytes(root),
+			to_bytes(nullifier),
+			2,
+			3,
+			100,
+			0
 		));
+		assert_eq!(validate(3, &withdraw(root, 2)), Err(InvalidTransaction::Stale.into()));
+		assert_eq!(
+			CheckMixerCall::<Test>::new().pre_dispatch(
+				&3,
+				&withdraw(root, 2),
+				&DispatchInfo::default(),
+				0
+			),
+			Err(InvalidTransaction::Stale.into())
+		);
+	});
+}
+
+#[test]
+fn test_check_mixer_call_refund() {
+	use frame_support::dispatch::DispatchInfo;
+	use sp_runtime::{traits::SignedExtension, transaction_validity::InvalidTransaction};
+
+	new_test_ext().execute_with(|| {
+		let pool_id = create_pool_in(CurrencyId::VToken(TokenSymbol::BTC), 10);
+		register_simulated_verification();
+
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![1]));
+		let root = CurrentRoot::<Test>::get(pool_id);
+		let withdraw = RuntimeCall::MixerModule(crate::Call::withdraw {
+			pool_id,
+			proof: EncodedProof::Json(vec![]),
+			root: to_bytes(root),
+			nullifier_hash: to_bytes(U256::from(7)),
+			receiver: 2,
+			relayer: 3,
+			fee: 1,
+			refund: 50,
+		});
+		let validate = |who| {
+			CheckMixerCall::<Test>::new().validate(&who, &withdraw, &DispatchInfo::default(), 0)
+		};
 
+		// only the relayer itself can pay the refund, as in dispatch
+		assert_eq!(validate(1), Err(InvalidTransaction::Call.into()));
+		assert_ok!(validate(3));
 	});
 }
 
 #[test]
-fn test_deposit() {
+fn test_malformed_inputs() {
+	use frame_support::{dispatch::DispatchInfo, unsigned::ValidateUnsigned};
+	use sp_runtime::{
+		traits::SignedExtension,
+		transaction_validity::{InvalidTransaction, TransactionSource},
+	};
+
 	new_test_ext().execute_with(|| {
-		let before = Balances::balance(&1);
-		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), vec![1]));
-		let after = Balances::balance(&1);
+		let pool_id = create_pool(1_000);
+		register_simulated_verification();
 
-		assert_eq!(before, after + 1_000);
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![1]));
+		let root = to_bytes(CurrentRoot::<Test>::get(pool_id));
+		let nullifier = to_bytes(U256::from(7));
+		// a leading zero byte still reads as the same number, but does not fit a U256
+		let too_long = |bytes: &Vec<u8>| [vec![0], bytes.clone()].concat();
 
-		let c = U256::from_big_endian(&vec![1]);
-		assert_eq!(Commitments::<Test>::contains_key(c), true);
+		assert_noop!(
+			MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![1; 33]),
+			Error::<Test>::MalformedCommitment
+		);
 
-		assert_eq!(MerkleVec::<Test>::get(), vec![c]);
+		let malformed: [(_, _, DispatchError); 2] = [
+			(too_long(&root), nullifier.clone(), Error::<Test>::MalformedRoot.into()),
+			(root.clone(), too_long(&nullifier), Error::<Test>::MalformedNullifierHash.into()),
+		];
+		for (root, nullifier, error) in malformed {
+			assert_noop!(
+				MixerModule::withdraw(
+					RuntimeOrigin::signed(3),
+					pool_id,
+					EncodedProof::Json(vec![]),
+					root.clone(),
+					nullifier.clone(),
+					2,
+					3,
+					0,
+					0
+				),
+				error
+			);
+			assert_noop!(
+				MixerModule::swap(
+					RuntimeOrigin::signed(1),
+					pool_id,
+					EncodedProof::Json(vec![]),
+					root.clone(),
+					nullifier.clone(),
+					0,
+					2
+				),
+				error
+			);
 
-		let root = U256::from_dec_str(
-			"11918823777688916996440235409179584458198237132535057418448191606750426488941",
-		)
-		.unwrap();
-		assert_eq!(Roots::<Test>::contains_key(root), true);
+			let withdraw_unsigned = crate::Call::<Test>::withdraw_unsigned {
+				pool_id,
+				proof: EncodedProof::Json(vec![]),
+				root: root.clone(),
+				nullifier_hash: nullifier.clone(),
+				receiver: 2,
+				relayer: 3,
+				fee: 0,
+			};
+			assert_eq!(
+				MixerModule::validate_unsigned(TransactionSource::External, &withdraw_unsigned),
+				Err(InvalidTransaction::Call.into())
+			);
+			let withdraw = RuntimeCall::MixerModule(crate::Call::withdraw {
+				pool_id,
+				proof: EncodedProof::Json(vec![]),
+				root,
+				nullifier_hash: nullifier,
+				receiver: 2,
+				relayer: 3,
+				fee: 0,
+				refund: 0,
+			});
+			assert_eq!(
+				CheckMixerCall::<Test>::new().validate(&3, &withdraw, &DispatchInfo::default(), 0),
+				Err(InvalidTransaction::Call.into())
+			);
+		}
 	});
 }
 
 #[test]
-fn test_withdraw() {
+fn test_swap() {
 	new_test_ext().execute_with(|| {
-		let vk = prepare_vk_json("groth16", "bls12381", None);
-		assert_ok!(MixerModule::setup_verification(RuntimeOrigin::signed(1), vk.as_bytes().into()));
+		let pool_id = create_pool(1_000);
+		register_simulated_verification();
 
-		let com = U256::from_dec_str(
-			"21230726593955799921294235288119148384132470247301542251439992487769590832624",
-		)
-		.unwrap();
-		let mut com_bytes = [0u8; 32];
-		com.to_big_endian(&mut com_bytes);
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![1]));
+
+		let root = CurrentRoot::<Test>::get(pool_id);
+		let nullifier = U256::from(7);
+		// swaps are relayed by the submitter for free
+		let proof = withdraw_proof(root, nullifier, 2, 1, 0, 0);
+
+		assert_eq!(Currencies::free_balance(CurrencyId::VToken(TokenSymbol::BTC), &2), 5_000);
+		assert_eq!(Currencies::free_balance(CurrencyId::VToken(TokenSymbol::BTC), &3), 5_000);
+		assert_eq!(Balances::balance(&3), 5000);
 
-		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), com_bytes.to_vec()));
+		assert_ok!(Swap::submit_order(
+			RuntimeOrigin::signed(3),
+			CurrencyId::VToken(TokenSymbol::BTC),
+			10,
+			CurrencyId::Token(TokenSymbol::DOT),
+			1000
+		));
 
 		assert_noop!(
-			MixerModule::withdraw(RuntimeOrigin::signed(1), vec![1], vec![1], vec![1], 2),
-			Error::<Test>::MalformedProof
+			MixerModule::swap(
+				RuntimeOrigin::signed(4),
+				pool_id,
+				proof.clone(),
+				to_bytes(root),
+				to_bytes(nullifier),
+				0,
+				2,
+			),
+			Error::<Test>::ProofVerificationFalse
 		);
 
-		let root = U256::from_dec_str(
-			"11707923398010884771104902347581583507748139574064506485019337720597328298281",
-		)
-		.unwrap();
+		assert_ok!(MixerModule::swap(
+			RuntimeOrigin::signed(1),
+			pool_id,
+			proof,
+			to_bytes(root),
+			to_bytes(nullifier),
+			0,
+			2,
+		),);
+
+		assert_eq!(Currencies::free_balance(CurrencyId::VToken(TokenSymbol::BTC), &2), 5_010);
+		assert_eq!(Balances::balance(&3), 6000);
+		System::assert_has_event(
+			Event::<Test>::Swaped { pool_id, nullifier_hash: nullifier, order_id: 0, receiver: 2 }
+				.into(),
+		);
+	});
+}
 
-		let mut root_bytes = [0u8; 32];
-		root.to_big_endian(&mut root_bytes);
+#[test]
+fn test_multi_asset_pool() {
+	new_test_ext().execute_with(|| {
+		let vbtc = CurrencyId::VToken(TokenSymbol::BTC);
+		let pool_id = create_pool_in(vbtc, 10);
+		register_simulated_verification();
 
-		let incorrect_proof = prepare_incorrect_proof_json("groth16", "bls12381", None);
+		let native_before = Balances::balance(&1);
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![1]));
+		assert_eq!(Currencies::free_balance(vbtc, &1), 4_990);
+		assert_eq!(Currencies::free_balance(vbtc, &account_id::<Test>()), 10);
+		assert_eq!(Balances::balance(&1), native_before);
 
+		let root = CurrentRoot::<Test>::get(pool_id);
+		let nullifier = U256::from(7);
+		let proof = withdraw_proof(root, nullifier, 2, 1, 0, 0);
+
+		// an order paying out DOT can not be taken with VBTC notes
+		assert_ok!(Swap::submit_order(
+			RuntimeOrigin::signed(3),
+			CurrencyId::VToken(TokenSymbol::BTC),
+			10,
+			CurrencyId::Token(TokenSymbol::DOT),
+			10
+		));
 		assert_noop!(
-			MixerModule::withdraw(
+			MixerModule::swap(
 				RuntimeOrigin::signed(1),
-				incorrect_proof.as_bytes().into(),
-				root_bytes.to_vec(),
-				vec![1u8],
-				2
+				pool_id,
+				proof.clone(),
+				to_bytes(root),
+				to_bytes(nullifier),
+				0,
+				2,
 			),
-			Error::<Test>::ProofCreationError
+			Error::<Test>::SwapCurrencyMismatch
 		);
 
-		let proof = prepare_proof_json("groth16", "bls12381", None);
+		assert_ok!(MixerModule::withdraw(
+			RuntimeOrigin::signed(1),
+			pool_id,
+			proof,
+			to_bytes(root),
+			to_bytes(nullifier),
+			2,
+			1,
+			0,
+			0
+		));
+		assert_eq!(Currencies::free_balance(vbtc, &2), 5_010);
+		assert_eq!(Currencies::free_balance(vbtc, &account_id::<Test>()), 0);
+	});
+}
 
-		let nullifier = U256::from_dec_str(
-			"25552435442991663747900835940687199996982543695763714737597223653118621902822",
-		)
-		.unwrap();
+#[test]
+fn test_blacklist() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(MixerModule::add_black_list(RuntimeOrigin::root(), 1, b"sanctioned".to_vec()));
 
-		let mut nullifier_bytes = [0u8; 32];
-		nullifier.to_big_endian(&mut nullifier_bytes);
+		let pool_id = create_pool(1_000);
+		register_simulated_verification();
 
-		let before = Balances::balance(&2);
-		assert_ok!(MixerModule::withdraw(
-			RuntimeOrigin::signed(1),
-			proof.as_bytes().into(),
-			root_bytes.to_vec(),
-			nullifier_bytes.to_vec(),
-			2
-		),);
-		let after = Balances::balance(&2);
-		assert_eq!(before + 1_000, after);
+		assert_noop!(
+			MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![1]),
+			Error::<Test>::BlacklistRejected
+		);
 
 		assert_noop!(
 			MixerModule::withdraw(
 				RuntimeOrigin::signed(1),
-				proof.as_bytes().into(),
-				root_bytes.to_vec(),
-				nullifier_bytes.to_vec(),
-				2
+				pool_id,
+				EncodedProof::Json(vec![1]),
+				vec![1],
+				vec![1],
+				2,
+				3,
+				0,
+				0
 			),
-			Error::<Test>::NoteHasBeanSpent
+			Error::<Test>::BlacklistRejected
+		);
+		assert_noop!(
+			MixerModule::withdraw(
+				RuntimeOrigin::signed(3),
+				pool_id,
+				EncodedProof::Json(vec![1]),
+				vec![1],
+				vec![1],
+				2,
+				1,
+				0,
+				0
+			),
+			Error::<Test>::BlacklistRejected
 		);
 	});
 }
 
 #[test]
-fn test_swap() {
+fn test_blacklist_management() {
 	new_test_ext().execute_with(|| {
-		let vk = prepare_vk_json("groth16", "bls12381", None);
-		assert_ok!(MixerModule::setup_verification(RuntimeOrigin::signed(1), vk.as_bytes().into()));
+		assert_noop!(
+			MixerModule::add_black_list(RuntimeOrigin::signed(1), 2, vec![]),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			MixerModule::add_black_list(RuntimeOrigin::root(), 2, vec![0; 33]),
+			Error::<Test>::TooLongBlackListReason
+		);
 
-		let com = U256::from_dec_str(
-			"21230726593955799921294235288119148384132470247301542251439992487769590832624",
-		)
-		.unwrap();
-		let mut com_bytes = [0u8; 32];
-		com.to_big_endian(&mut com_bytes);
+		assert_ok!(MixerModule::add_black_list(RuntimeOrigin::root(), 2, b"sanctioned".to_vec()));
+		assert_eq!(BlackList::<Test>::get(2).unwrap().into_inner(), b"sanctioned".to_vec());
+		System::assert_last_event(
+			Event::<Test>::BlackListAdded {
+				who: 2,
+				reason: BoundedVec::truncate_from(b"sanctioned".to_vec()),
+			}
+			.into(),
+		);
 
-		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), com_bytes.to_vec()));
+		// listing an account again replaces its reason
+		assert_ok!(MixerModule::add_black_list(RuntimeOrigin::root(), 2, b"stolen funds".to_vec()));
+		assert_eq!(BlackList::<Test>::get(2).unwrap().into_inner(), b"stolen funds".to_vec());
 
-		let root = U256::from_dec_str(
-			"11707923398010884771104902347581583507748139574064506485019337720597328298281",
-		)
-		.unwrap();
+		assert_noop!(
+			MixerModule::remove_black_list(RuntimeOrigin::signed(2), 2),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(MixerModule::remove_black_list(RuntimeOrigin::root(), 2));
+		assert!(!BlackList::<Test>::contains_key(2));
+		System::assert_last_event(Event::<Test>::BlackListRemoved { who: 2 }.into());
+		assert_noop!(
+			MixerModule::remove_black_list(RuntimeOrigin::root(), 2),
+			Error::<Test>::NotBlackListed
+		);
 
-		let mut root_bytes = [0u8; 32];
-		root.to_big_endian(&mut root_bytes);
+		// removed accounts can deposit again
+		let pool_id = create_pool(1_000);
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(2), pool_id, vec![1]));
+	});
+}
+
+#[test]
+fn test_blacklist_batch() {
+	new_test_ext().execute_with(|| {
+		let entries = vec![(1, b"a".to_vec()), (2, b"b".to_vec()), (3, b"c".to_vec())];
+		assert_noop!(
+			MixerModule::add_black_list_batch(RuntimeOrigin::signed(1), entries.clone()),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			MixerModule::add_black_list_batch(
+				RuntimeOrigin::root(),
+				[entries.clone(), vec![(4, vec![])]].concat()
+			),
+			Error::<Test>::TooManyBlackListEntries
+		);
+		// one invalid entry rejects the whole batch
+		assert_noop!(
+			MixerModule::add_black_list_batch(
+				RuntimeOrigin::root(),
+				vec![(1, b"a".to_vec()), (2, vec![0; 33])]
+			),
+			Error::<Test>::TooLongBlackListReason
+		);
 
-		let proof = prepare_proof_json("groth16", "bls12381", None);
+		assert_ok!(MixerModule::add_black_list_batch(RuntimeOrigin::root(), entries));
+		assert_eq!(BlackList::<Test>::iter_keys().count(), 3);
+		assert_eq!(BlackList::<Test>::get(3).unwrap().into_inner(), b"c".to_vec());
 
-		let nullifier = U256::from_dec_str(
-			"25552435442991663747900835940687199996982543695763714737597223653118621902822",
-		)
-		.unwrap();
+		assert_noop!(
+			MixerModule::remove_black_list_batch(RuntimeOrigin::root(), vec![1, 4]),
+			Error::<Test>::NotBlackListed
+		);
+		assert_ok!(MixerModule::remove_black_list_batch(RuntimeOrigin::root(), vec![1, 3]));
+		assert_eq!(BlackList::<Test>::iter_keys().collect::<Vec<_>>(), vec![2]);
+		System::assert_last_event(Event::<Test>::BlackListRemoved { who: 3 }.into());
+	});
+}
 
-		let mut nullifier_bytes = [0u8; 32];
-		nullifier.to_big_endian(&mut nullifier_bytes);
+#[test]
+fn test_genesis_blacklist() {
+	ExtBuilder::default()
+		.black_list(vec![(3, b"sanctioned".to_vec())])
+		.build()
+		.execute_with(|| {
+			assert_eq!(BlackList::<Test>::get(3).unwrap().into_inner(), b"sanctioned".to_vec());
 
-		assert_eq!(Currencies::free_balance(CurrencyId::VToken(TokenSymbol::BTC), &2), 5_000);
-		assert_eq!(Currencies::free_balance(CurrencyId::VToken(TokenSymbol::BTC), &3), 5_000);
-		assert_eq!(Balances::balance(&3), 5000);
+			let pool_id = create_pool(1_000);
+			assert_noop!(
+				MixerModule::deposit(RuntimeOrigin::signed(3), pool_id, vec![1]),
+				Error::<Test>::BlacklistRejected
+			);
+		});
+}
 
-		assert_ok!(Swap::submit_order(
-			RuntimeOrigin::signed(3),
-			CurrencyId::VToken(TokenSymbol::BTC),
+#[test]
+fn test_pools_of_different_depths() {
+	new_test_ext().execute_with(|| {
+		let shallow_pool = create_pool(1_000);
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), shallow_pool, vec![1]));
+		assert_eq!(ZeroHashes::<Test>::get(Hasher::MiMC7).len(), 9);
+
+		let pool_id = NextPoolId::<Test>::get();
+		assert_ok!(MixerModule::create_pool(
+			RuntimeOrigin::root(),
+			NativeCurrencyId::get(),
+			1_000,
+			Hasher::MiMC7,
 			10,
-			CurrencyId::Token(TokenSymbol::DOT),
-			1000
+			WithdrawCircuitId::get()
 		));
+		let mut tree = IncrementalMerkleTree::new(10);
+		assert_eq!(merkle_root::<Test>(pool_id), Some(tree.get_root()));
+		for c in 1..=3u8 {
+			assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![c]));
+			tree.insert(U256::from(c)).unwrap();
+			assert_eq!(CurrentRoot::<Test>::get(pool_id), tree.get_root());
+		}
+		assert_eq!(ZeroHashes::<Test>::get(Hasher::MiMC7).len(), 11);
+		assert_eq!(get_merkle_path::<Test>(pool_id, 2).unwrap().path_elements.len(), 10);
 
-		assert_ok!(MixerModule::swap(
-			RuntimeOrigin::signed(1),
-			proof.as_bytes().into(),
-			root_bytes.to_vec(),
-			nullifier_bytes.to_vec(),
-			0,
-			2,
-		),);
+		// the shallower tree keeps its depth, it uses a prefix of the deeper zero hashes
+		let mut shallow_tree = IncrementalMerkleTree::new(8);
+		shallow_tree.insert(U256::from(1)).unwrap();
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), shallow_pool, vec![2]));
+		shallow_tree.insert(U256::from(2)).unwrap();
+		assert_eq!(CurrentRoot::<Test>::get(shallow_pool), shallow_tree.get_root());
+		assert_eq!(get_merkle_path::<Test>(shallow_pool, 1).unwrap().path_elements.len(), 8);
 
-		assert_eq!(Currencies::free_balance(CurrencyId::VToken(TokenSymbol::BTC), &2), 5_010);
-		assert_eq!(Balances::balance(&3), 6000);
+		// withdraws of the deeper pool need a key registered for its depth
+		assert_noop!(
+			MixerModule::withdraw(
+				RuntimeOrigin::signed(3),
+				pool_id,
+				withdraw_proof(tree.get_root(), U256::from(7), 2, 3, 100, 0),
+				to_bytes(tree.get_root()),
+				to_bytes(U256::from(7)),
+				2,
+				3,
+				100,
+				0,
+			),
+			Error::<Test>::VerificationKeyIsNotSet
+		);
 	});
 }
 
 #[test]
-fn test_blacklist() {
+fn test_poseidon_pool() {
 	new_test_ext().execute_with(|| {
-        assert_ok!(MixerModule::add_black_list(RuntimeOrigin::signed(1), 1));
+		let pool_id = create_pool_with(NativeCurrencyId::get(), 1_000, Hasher::Poseidon);
+		let depth = MixerTreeDepth::get() as usize;
+		let mut tree = IncrementalMerkleTree::with_hasher(depth, Hasher::Poseidon);
+		assert_eq!(merkle_root::<Test>(pool_id), Some(tree.get_root()));
 
-        let vk = prepare_vk_json("groth16", "bls12381", Some("3701847203724321478317961353917758270528478504282408535117312363800157867784070247396381164448597370877483548917602".to_owned()));
-		assert_ok!(MixerModule::setup_verification(
-			RuntimeOrigin::signed(1),
-			vk.as_bytes().into()
+		for c in 1..=3u8 {
+			assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![c]));
+			tree.insert(U256::from(c)).unwrap();
+			assert_eq!(CurrentRoot::<Test>::get(pool_id), tree.get_root());
+		}
+		assert_eq!(
+			ZeroHashes::<Test>::get(Hasher::Poseidon).into_inner(),
+			zero_hashes(Hasher::Poseidon, depth)
+		);
+
+		// `mixer-psd` sets the position where the path node is a right child
+		let path = get_merkle_path::<Test>(pool_id, 2).unwrap();
+		assert_eq!(&path.path_positions[..3], &[0, 1, 0]);
+
+		let root = tree.get_root();
+		let nullifier = U256::from(7);
+		let withdraw = || {
+			MixerModule::withdraw(
+				RuntimeOrigin::signed(3),
+				pool_id,
+				withdraw_proof(root, nullifier, 2, 3, 100, 0),
+				to_bytes(root),
+				to_bytes(nullifier),
+				2,
+				3,
+				100,
+				0,
+			)
+		};
+
+		// keys of another pool's circuit do not verify its withdraws
+		register_simulated_verification();
+		assert_noop!(withdraw(), Error::<Test>::VerificationKeyIsNotSet);
+
+		register_simulated_verification_for(PoseidonWithdrawCircuitId::get(), Curve::Bls12381);
+		assert_ok!(withdraw());
+	});
+}
+
+#[test]
+fn test_pool_circuit_id() {
+	new_test_ext().execute_with(|| {
+		let circuit_id = 5;
+		let pool_id = NextPoolId::<Test>::get();
+		assert_ok!(MixerModule::create_pool(
+			RuntimeOrigin::root(),
+			NativeCurrencyId::get(),
+			1_000,
+			Hasher::MiMC7,
+			MixerTreeDepth::get(),
+			circuit_id
 		));
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, vec![1]));
 
-		assert_noop!(
-            MixerModule::deposit(RuntimeOrigin::signed(1),vec![1]),
-            Error::<Test>::BlacklistRejected
-        );
+		let root = CurrentRoot::<Test>::get(pool_id);
+		let nullifier = U256::from(7);
+		let withdraw = || {
+			MixerModule::withdraw(
+				RuntimeOrigin::signed(3),
+				pool_id,
+				withdraw_proof(root, nullifier, 2, 3, 100, 0),
+				to_bytes(root),
+				to_bytes(nullifier),
+				2,
+				3,
+				100,
+				0,
+			)
+		};
 
-        assert_noop!(
-			MixerModule::withdraw(RuntimeOrigin::signed(1), vec![1], vec![1], vec![1], 2),
-			Error::<Test>::BlacklistRejected
-		);
+		// a MiMC7 pool of the same depth, but keys of the default circuit are not its keys
+		register_simulated_verification();
+		assert_noop!(withdraw(), Error::<Test>::VerificationKeyIsNotSet);
+
+		register_simulated_verification_for(circuit_id, Curve::Bls12381);
+		assert_ok!(withdraw());
+	});
+}
+
+#[test]
+fn test_call_weights() {
+	use crate::weights::WeightInfo;
+	use frame_support::dispatch::GetDispatchInfo;
+
+	// deposits hash a tree of the deepest depth a pool may have
+	let deposit = Call::<Test>::deposit { pool_id: 0, commitment: vec![1] };
+	assert_eq!(
+		deposit.get_dispatch_info().weight,
+		<() as WeightInfo>::deposit()
+			.saturating_add(<() as WeightInfo>::merkle_insert(MixerMaxTreeDepth::get()))
+	);
+
+	// proofs may be checked against every usable key
+	let withdraw = Call::<Test>::withdraw {
+		pool_id: 0,
+		proof: EncodedProof::Json(vec![]),
+		root: vec![],
+		nullifier_hash: vec![],
+		receiver: 2,
+		relayer: 3,
+		fee: 0,
+		refund: 0,
+	};
+	assert_eq!(withdraw.get_dispatch_info().weight, <() as WeightInfo>::withdraw(2));
+	assert!(
+		<() as WeightInfo>::withdraw(2).ref_time() > <() as WeightInfo>::withdraw(1).ref_time()
+	);
+}
+
+#[test]
+fn test_otp_deposit_with_generated_proof() {
+	use rand::{rngs::StdRng, Rng, SeedableRng};
+
+	let prover = zk_prover::Prover::load(zk_prover::Circuit::Otp).unwrap();
+	assert_eq!(prover.public_inputs(), pallet_otp::OTP_PUBLIC_INPUTS);
+
+	new_test_ext().execute_with(|| {
+		let pool_id = create_pool(1_000);
+		assert_ok!(VkRegistry::register_verification_key(
+			RuntimeOrigin::root(),
+			OtpCircuitId::get(),
+			prover.verification_key_json().into_bytes(),
+			None
+		));
+
+		// a random password in a random tree, the circuit outputs the root it is committed to
+		let mut rng = StdRng::seed_from_u64(7);
+		let time = 1 + rng.gen::<u32>() as u128;
+		let proof = prover
+			.prove(
+				&[
+					("time", vec![time.into()]),
+					("otp", vec![(rng.gen::<u32>() % 1_000_000).into()]),
+					("path_elements", (0..7).map(|_| rng.gen::<u128>().into()).collect()),
+					("path_index", (0..7).map(|_| (rng.gen::<bool>() as u8).into()).collect()),
+				],
+				&mut rng,
+			)
+			.unwrap();
+		assert_eq!(proof.public_inputs[1], time.into());
+		let otp_root = proof.public_inputs[0].to_string().into_bytes();
+		assert_ok!(Otp::set_otp_commitment(RuntimeOrigin::signed(1), otp_root.clone()));
+
+		let deposit = |timestamp| {
+			MixerModule::deposit_with_naive_otp(
+				RuntimeOrigin::signed(1),
+				pool_id,
+				vec![1],
+				EncodedProof::Json(proof.json.clone().into_bytes()),
+				otp_root.clone(),
+				timestamp,
+			)
+		};
+		assert_noop!(deposit(time + 1), pallet_otp::Error::<Test>::ProofVerificationFalse);
+		assert_ok!(deposit(time));
+		assert_noop!(deposit(time), pallet_otp::Error::<Test>::TimestampMustBeLargerThanLast);
+	});
+}
+
+#[test]
+#[ignore = "circom/mixer/build is the two input circuit, rebuild it with `just`"]
+fn test_mimc_withdraw_with_generated_proof() {
+	withdraw_with_generated_proof(zk_prover::Circuit::Mixer, Hasher::MiMC7);
+}
+
+#[test]
+#[ignore = "circom/mixer-psd/build is the two input circuit, rebuild it with `just`"]
+fn test_poseidon_withdraw_with_generated_proof() {
+	withdraw_with_generated_proof(zk_prover::Circuit::MixerPoseidon, Hasher::Poseidon);
+}
+
+// Deposit a note into a `hasher` pool among others and withdraw it with a proof of `circuit`.
+fn withdraw_with_generated_proof(circuit: zk_prover::Circuit, hasher: Hasher) {
+	use rand::{rngs::StdRng, Rng, SeedableRng};
+
+	let prover = zk_prover::Prover::load(circuit).unwrap();
+	assert_eq!(prover.public_inputs(), WITHDRAW_PUBLIC_INPUTS);
+	new_test_ext().execute_with(|| {
+		let pool_id = create_pool_with(NativeCurrencyId::get(), 1_000, hasher);
+		assert_ok!(VkRegistry::register_verification_key(
+			RuntimeOrigin::root(),
+			Pools::<Test>::get(pool_id).unwrap().circuit_id,
+			prover.verification_key_json().into_bytes(),
+			Some(MixerTreeDepth::get())
+		));
+
+		// the note of `secret` lands after a random number of others
+		let mut rng = StdRng::seed_from_u64(hasher as u64);
+		let hash = |left: U256, right: U256| hasher.with(|h| h.hash_leaves(&left, &right));
+		let secret = U256::from(rng.gen::<u128>());
+		let leaf_index = rng.gen::<u32>() % 8;
+		for _ in 0..leaf_index {
+			let other = hash(rng.gen::<u128>().into(), U256::zero());
+			assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, to_bytes(other)));
+		}
+		let commitment = hash(secret, U256::zero());
+		assert_ok!(MixerModule::deposit(RuntimeOrigin::signed(1), pool_id, to_bytes(commitment)));
 
+		let path = get_merkle_path::<Test>(pool_id, leaf_index).unwrap();
+		assert_eq!(path.leaf, commitment);
+		// the circuit's `Bits2Num` of the path positions, the leaf index on Poseidon trees and
+		// its complement on MiMC7 ones
+		let position = path
+			.path_positions
+			.iter()
+			.enumerate()
+			.fold(0u32, |position, (level, &bit)| position | ((bit as u32) << level));
+		let nullifier = hash(position.into(), secret);
+		let inputs = withdraw_public_inputs::<Test>(path.root, nullifier, &2, &3, 100, 0, None);
+		let proof = prover
+			.prove(
+				&[
+					("root", vec![inputs[0]]),
+					("nullifierHash", vec![inputs[1]]),
+					("recipient", vec![inputs[2]]),
+					("relayer", vec![inputs[3]]),
+					("fee", vec![inputs[4]]),
+					("refund", vec![inputs[5]]),
+					("guard", vec![inputs[6]]),
+					("secret", vec![secret]),
+					("paths2_root", path.path_elements.clone()),
+					("paths2_root_pos", path.path_positions.iter().map(|&p| p.into()).collect()),
+				],
+				&mut rng,
+			)
+			.unwrap();
+		assert_eq!(proof.public_inputs, inputs);
+
+		let receiver_before = Balances::balance(&2);
+		assert_ok!(MixerModule::withdraw(
+			RuntimeOrigin::signed(3),
+			pool_id,
+			EncodedProof::Json(proof.json.into_bytes()),
+			to_bytes(path.root),
+			to_bytes(nullifier),
+			2,
+			3,
+			100,
+			0,
+		));
+		assert_eq!(Balances::balance(&2), receiver_before + 900);
 	});
 }
 
+fn to_bytes(value: U256) -> Vec<u8> {
+	let mut bytes = [0u8; 32];
+	value.to_big_endian(&mut bytes);
+	bytes.to_vec()
+}
+
+fn register_simulated_verification() {
+	register_simulated_verification_on(Curve::Bls12381)
+}
+
+fn register_simulated_verification_on(curve: Curve) {
+	register_simulated_verification_for(WithdrawCircuitId::get(), curve)
+}
+
+fn register_simulated_verification_for(circuit_id: CircuitId, curve: Curve) {
+	let (vk, _proof) = simulate_withdraw_proof(curve, vec![U256::zero(); WITHDRAW_PUBLIC_INPUTS]);
+	assert_ok!(VkRegistry::register_verification_key(
+		RuntimeOrigin::root(),
+		circuit_id,
+		vk.into_bytes(),
+		Some(MixerTreeDepth::get())
+	));
+}
+
+/// Registers the simulated otp key and the OTP commitment of `who`, return its root.
+fn set_simulated_otp(who: AccountId) -> U256 {
+	if pallet_vk_registry::VerificationKeys::<Test>::iter_prefix(OtpCircuitId::get())
+		.next()
+		.is_none()
+	{
+		let (vk, _proof) = simulate_withdraw_proof(
+			Curve::Bls12381,
+			vec![U256::zero(); pallet_otp::OTP_PUBLIC_INPUTS],
+		);
+		assert_ok!(VkRegistry::register_verification_key(
+			RuntimeOrigin::root(),
+			OtpCircuitId::get(),
+			vk.into_bytes(),
+			None
+		));
+	}
+	let root = U256::from(1_234_567 + who);
+	assert_ok!(Otp::set_otp_commitment(RuntimeOrigin::signed(who), root.to_string().into_bytes()));
+	root
+}
+
+/// Proof of the password for `timestamp` of the OTP commitment `root`.
+fn otp_proof(root: U256, timestamp: u128) -> EncodedProof {
+	let (_vk, proof) = simulate_withdraw_proof(Curve::Bls12381, vec![root, U256::from(timestamp)]);
+	EncodedProof::Json(proof.into_bytes())
+}
+
+fn withdraw_proof(
+	root: U256,
+	nullifier_hash: U256,
+	receiver: AccountId,
+	relayer: AccountId,
+	fee: Balance,
+	refund: Balance,
+) -> EncodedProof {
+	guarded_withdraw_proof(root, nullifier_hash, receiver, relayer, fee, refund, None)
+}
+
+/// Proof of withdrawing a note whose leaf commits to the guard of `owner`.
+fn guarded_withdraw_proof(
+	root: U256,
+	nullifier_hash: U256,
+	receiver: AccountId,
+	relayer: AccountId,
+	fee: Balance,
+	refund: Balance,
+	owner: Option<AccountId>,
+) -> EncodedProof {
+	let inputs = withdraw_public_inputs::<Test>(
+		root,
+		nullifier_hash,
+		&receiver,
+		&relayer,
+		fee,
+		refund,
+		owner.as_ref(),
+	);
+	let (_vk, proof) = simulate_withdraw_proof(Curve::Bls12381, inputs);
+	EncodedProof::Json(proof.into_bytes())
+}
+
+fn create_pool(denomination: Balance) -> PoolId {
+	create_pool_in(NativeCurrencyId::get(), denomination)
+}
+
+fn create_pool_in(currency_id: CurrencyId, denomination: Balance) -> PoolId {
+	create_pool_with(currency_id, denomination, Hasher::MiMC7)
+}
+
+/// Pool of `hasher` trees of `MixerTreeDepth`, verified with the circuit of that hasher.
+fn create_pool_with(currency_id: CurrencyId, denomination: Balance, hasher: Hasher) -> PoolId {
+	let circuit_id = match hasher {
+		Hasher::MiMC7 => WithdrawCircuitId::get(),
+		Hasher::Poseidon => PoseidonWithdrawCircuitId::get(),
+	};
+	let pool_id = NextPoolId::<Test>::get();
+	assert_ok!(MixerModule::create_pool(
+		RuntimeOrigin::root(),
+		currency_id,
+		denomination,
+		hasher,
+		MixerTreeDepth::get(),
+		circuit_id
+	));
+	pool_id
+}
+
 fn _prepare_correct_public_inputs_json() -> String {
 	r#"[
         "12154017155188732043720388494527814426846884333686418648942396484836291069935",
@@ -324,43 +2154,112 @@ fn prepare_vk_json(protocol: &str, curve: &str, alpha_x: Option<String>) -> Stri
 		.replace("<curve>", curve)
 		.replace("<alpha_x>", &alpha_x)
 }
+// Groth16 key and proof accepting exactly `public_inputs` on `curve`. The key is built from known
+// scalars instead of a trusted setup, which lets the proof be solved for without a circuit.
+fn simulate_withdraw_proof(curve: Curve, public_inputs: Vec<U256>) -> (String, String) {
+	let n_public = public_inputs.len();
+	let (name, ([alpha, beta, gamma, delta], ic, [a, b, c])) = match curve {
+		Curve::Bls12381 => ("bls12381", simulate_bls12381_points(public_inputs)),
+		Curve::Bn254 => ("bn128", simulate_bn254_points(public_inputs)),
+	};
 
-// input is
-// {
-// 	"root": "11707923398010884771104902347581583507748139574064506485019337720597328298281",
-// 	"nullifierHash":
-// "25552435442991663747900835940687199996982543695763714737597223653118621902822", 	"secret":
-// "15308241608160268350", 	"paths2_root": ["0",
-// "51576823595707970152643159819788304363803754756066229172775779360774743019614",
-// "33646187916922823865935622258451714952164674255482660942215703235411158105736",
-// "27818645450144846908742692719385898720249207574255739267233226464286012246073",
-// "39404029000907277292464556408734412130261913210564395069696342233560511006152",
-// "24907123534309659921713005795092724527532698077589223246276579583330771465031",
-// "22103361713848256938655449390262013863291224679776344310249539314760174194771",
-// "28665358770471415124367990738618755861132249577405347373337125991381323369983"],
-// 	"paths2_root_pos": [0, 0, 0, 0, 0, 0, 0, 0]
-// }
-fn prepare_proof_json(protocol: &str, curve: &str, pi_a_x: Option<String>) -> String {
-	let pi_a_x = pi_a_x.unwrap_or_else(|| "1177564558550769956785339462842115460663322334705567835885914518147582035494482474444088433269065054572726278485401".to_owned());
-	let proof_template = r#"{
-"pi_a": [
-"<pi_a_x>",
-    "3463774854543115711511322796130875262715327601213314678256509445986239775532424704720161136511271046778428668115295", "1"],
-	"pi_b": [
-		["3939735334779363728874826927428882850014215914525443141528712663073265693419464582354918395773933609568093166281375", "2762082853524568870366722466346836656196165120714407758945309012775931831893159584521431747408484009464992517674096"],
-		["1238881836365727332955464755544056303340279533650879933373712064446408017638369002208624038950250892465747100623589", "2133327084326797619312560177340933012701302717709822268851609732238727555832689865031979464510335339231088766600526"],
-		["1", "0"]
-	],
-	"pi_c": ["352516744368930426190313000951685856589231636023979896326701901794604226910259816430279862303227124005715360165947", "1207590512348526635165457329307974841887001330405831957414491116032670409554156238728603276568528858063625545112694", "1"],
-	
-"protocol": "<protocol>",
-"curve": "<curve>"
-}"#;
+	let vk = format!(
+		r#"{{"protocol": "groth16", "curve": "{}", "nPublic": {}, "vk_alpha_1": {}, "vk_beta_2": {}, "vk_gamma_2": {}, "vk_delta_2": {}, "IC": [{}]}}"#,
+		name,
+		n_public,
+		alpha,
+		beta,
+		gamma,
+		delta,
+		ic.join(", ")
+	);
+	let proof = format!(
+		r#"{{"pi_a": {}, "pi_b": {}, "pi_c": {}, "protocol": "groth16", "curve": "{}"}}"#,
+		a, b, c, name
+	);
+	(vk, proof)
+}
 
-	proof_template
-		.replace("<protocol>", protocol)
-		.replace("<curve>", curve)
-		.replace("<pi_a_x>", &pi_a_x)
+// alpha, beta, gamma, delta, IC and the proof points, as snarkjs json
+type SimulatedPoints = ([String; 4], Vec<String>, [String; 3]);
+
+fn simulate_bls12381_points(public_inputs: Vec<U256>) -> SimulatedPoints {
+	use ark_bls12_381::{Fr, G1Affine, G2Affine};
+	use ark_ec::{AffineRepr, CurveGroup};
+	use ark_ff::{Field, PrimeField};
+
+	fn g1(s: Fr) -> String {
+		let p = (G1Affine::generator() * s).into_affine();
+		format!(r#"["{}", "{}", "1"]"#, p.x.into_bigint(), p.y.into_bigint())
+	}
+	fn g2(s: Fr) -> String {
+		let p = (G2Affine::generator() * s).into_affine();
+		format!(
+			r#"[["{}", "{}"], ["{}", "{}"], ["1", "0"]]"#,
+			p.x.c0.into_bigint(),
+			p.x.c1.into_bigint(),
+			p.y.c0.into_bigint(),
+			p.y.c1.into_bigint()
+		)
+	}
+
+	let (alpha, beta, gamma, delta) =
+		(Fr::from(2u64), Fr::from(3u64), Fr::from(5u64), Fr::from(7u64));
+	let ic: Vec<Fr> = (0..=public_inputs.len() as u64).map(|i| Fr::from(11 + i)).collect();
+	let acc = prepare_public_inputs(public_inputs)
+		.unwrap()
+		.iter()
+		.zip(ic.iter().skip(1))
+		.fold(ic[0], |acc, (input, ic)| acc + *input * ic);
+
+	// e(a, b) = e(alpha, beta) * e(acc, gamma) * e(c, delta)
+	let (a, b) = (Fr::from(13u64), Fr::from(17u64));
+	let c = (a * b - alpha * beta - acc * gamma) * delta.inverse().unwrap();
+
+	(
+		[g1(alpha), g2(beta), g2(gamma), g2(delta)],
+		ic.into_iter().map(g1).collect(),
+		[g1(a), g2(b), g1(c)],
+	)
+}
+
+fn simulate_bn254_points(public_inputs: Vec<U256>) -> SimulatedPoints {
+	use ark_bn254::{Fr, G1Affine, G2Affine};
+	use ark_ec::{AffineRepr, CurveGroup};
+	use ark_ff::{Field, PrimeField};
+
+	fn g1(s: Fr) -> String {
+		let p = (G1Affine::generator() * s).into_affine();
+		format!(r#"["{}", "{}", "1"]"#, p.x.into_bigint(), p.y.into_bigint())
+	}
+	fn g2(s: Fr) -> String {
+		let p = (G2Affine::generator() * s).into_affine();
+		format!(
+			r#"[["{}", "{}"], ["{}", "{}"], ["1", "0"]]"#,
+			p.x.c0.into_bigint(),
+			p.x.c1.into_bigint(),
+			p.y.c0.into_bigint(),
+			p.y.c1.into_bigint()
+		)
+	}
+
+	let (alpha, beta, gamma, delta) =
+		(Fr::from(2u64), Fr::from(3u64), Fr::from(5u64), Fr::from(7u64));
+	let ic: Vec<Fr> = (0..=public_inputs.len() as u64).map(|i| Fr::from(11 + i)).collect();
+	let acc = zk_verifier::verify_bn254::prepare_public_inputs(public_inputs)
+		.unwrap()
+		.iter()
+		.zip(ic.iter().skip(1))
+		.fold(ic[0], |acc, (input, ic)| acc + *input * ic);
+
+	let (a, b) = (Fr::from(13u64), Fr::from(17u64));
+	let c = (a * b - alpha * beta - acc * gamma) * delta.inverse().unwrap();
+
+	(
+		[g1(alpha), g2(beta), g2(gamma), g2(delta)],
+		ic.into_iter().map(g1).collect(),
+		[g1(a), g2(b), g1(c)],
+	)
 }
 
 fn prepare_incorrect_proof_json(protocol: &str, curve: &str, pi_a_x: Option<String>) -> String {
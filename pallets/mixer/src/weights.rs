@@ -1,29 +1,9 @@
-
-//! Autogenerated weights for pallet_template
+//! Weights for pallet_mixer
 //!
-//! THIS FILE WAS AUTO-GENERATED USING THE SUBSTRATE BENCHMARK CLI VERSION 4.0.0-dev
-//! DATE: 2023-04-06, STEPS: `50`, REPEAT: `20`, LOW RANGE: `[]`, HIGH RANGE: `[]`
-//! WORST CASE MAP SIZE: `1000000`
-//! HOSTNAME: `Alexs-MacBook-Pro-2.local`, CPU: `<UNKNOWN>`
-//! EXECUTION: Some(Wasm), WASM-EXECUTION: Compiled, CHAIN: Some("dev"), DB CACHE: 1024
-
-// Executed Command:
-// ../../target/release/node-template
-// benchmark
-// pallet
-// --chain
-// dev
-// --pallet
-// pallet_template
-// --extrinsic
-// *
-// --steps=50
-// --repeat=20
-// --wasm-execution=compiled
-// --output
-// pallets/template/src/weights.rs
-// --template
-// ../../.maintain/frame-weight-template.hbs
+//! Not generated yet: estimated from the storage accessed by each call, a pairing check per
+//! usable key `k` and two MiMC7 hashes per tree level `d`, with `MaxBatchWithdraws` 64 and
+//! `MaxUsableVersions` 4 as in the runtime. `scripts/benchmark.sh` overwrites this file with the
+//! weights measured on reference hardware.
 
 #![cfg_attr(rustfmt, rustfmt_skip)]
 #![allow(unused_parens)]
@@ -32,59 +12,239 @@
 use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
 use core::marker::PhantomData;
 
-/// Weight functions needed for pallet_template.
+/// Weight functions needed for pallet_mixer.
 pub trait WeightInfo {
-	fn do_something() -> Weight;
-	fn cause_error() -> Weight;
+	fn deposit() -> Weight;
+	fn withdraw(k: u32, ) -> Weight;
+	fn withdraw_unsigned(k: u32, ) -> Weight;
+	fn batch_withdraw(n: u32, k: u32, ) -> Weight;
+	fn add_black_list() -> Weight;
+	fn remove_black_list() -> Weight;
+	fn add_black_list_batch(n: u32, ) -> Weight;
+	fn remove_black_list_batch(n: u32, ) -> Weight;
+	fn create_pool() -> Weight;
+	fn merkle_insert(d: u32, ) -> Weight;
 }
 
-/// Weights for pallet_template using the Substrate node and recommended hardware.
+/// Weights for pallet_mixer using the Substrate node and recommended hardware.
 pub struct SubstrateWeight<T>(PhantomData<T>);
 impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
-	/// Storage: TemplateModule Something (r:0 w:1)
-	/// Proof: TemplateModule Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
-	fn do_something() -> Weight {
-		// Proof Size summary in bytes:
-		//  Measured:  `0`
-		//  Estimated: `0`
-		// Minimum execution time: 8_000_000 picoseconds.
-		Weight::from_parts(9_000_000, 0)
+	/// Storage: Mixer BlackList (r:1 w:0)
+	/// Storage: Mixer Pools (r:1 w:0)
+	/// Storage: Mixer Commitments (r:1 w:1)
+	/// Storage: Mixer FilledSubtrees (r:1 w:1)
+	/// Storage: Mixer ZeroHashes (r:1 w:1)
+	/// Storage: Mixer NextIndex (r:0 w:1)
+	/// Storage: Mixer CurrentRoot (r:0 w:1)
+	/// Storage: Mixer Leaves (r:0 w:1)
+	/// Storage: Mixer CurrentRootIndex (r:1 w:1)
+	/// Storage: Mixer RootHistory (r:1 w:1)
+	/// Storage: Mixer Roots (r:0 w:2)
+	/// Storage: System Account (r:2 w:2)
+	/// Storage: Timestamp Now (r:1 w:0)
+	fn deposit() -> Weight {
+		Weight::from_parts(60_000_000, 6_196)
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().writes(13_u64))
+	}
+	/// Storage: Mixer BlackList (r:3 w:0)
+	/// Storage: Mixer Pools (r:1 w:0)
+	/// Storage: Mixer NullifierHashes (r:1 w:1)
+	/// Storage: Mixer Roots (r:1 w:0)
+	/// Storage: VkRegistry UsableVersions (r:1 w:0)
+	/// Storage: VkRegistry VerificationKeys (r:4 w:0)
+	/// Storage: System Account (r:3 w:3)
+	/// The range of component `k` is `[1, 4]`.
+	fn withdraw(k: u32, ) -> Weight {
+		Weight::from_parts(500_000_000, 5_640)
+			.saturating_add(Weight::from_parts(1_500_000_000, 3_159).saturating_mul(k.into()))
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(k.into())))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+	/// Storage: Mixer BlackList (r:2 w:0)
+	/// Storage: Mixer Pools (r:1 w:0)
+	/// Storage: Mixer NullifierHashes (r:1 w:1)
+	/// Storage: Mixer Roots (r:1 w:0)
+	/// Storage: VkRegistry UsableVersions (r:1 w:0)
+	/// Storage: VkRegistry VerificationKeys (r:4 w:0)
+	/// Storage: System Account (r:3 w:3)
+	/// The range of component `k` is `[1, 4]`.
+	fn withdraw_unsigned(k: u32, ) -> Weight {
+		Weight::from_parts(500_000_000, 5_640)
+			.saturating_add(Weight::from_parts(1_500_000_000, 3_159).saturating_mul(k.into()))
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(k.into())))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+	/// Storage: Mixer BlackList (r:1 w:0)
+	/// Storage: Mixer Pools (r:1 w:0)
+	/// Storage: VkRegistry UsableVersions (r:1 w:0)
+	/// Storage: VkRegistry VerificationKeys (r:4 w:0)
+	/// Storage: Mixer BlackList (r:2 w:0)
+	/// Storage: Mixer NullifierHashes (r:1 w:1)
+	/// Storage: Mixer Roots (r:1 w:0)
+	/// Storage: System Account (r:2 w:2)
+	/// The range of component `n` is `[1, 64]`.
+	/// The range of component `k` is `[1, 4]`.
+	fn batch_withdraw(n: u32, k: u32, ) -> Weight {
+		Weight::from_parts(300_000_000, 434)
+			.saturating_add(Weight::from_parts(1_900_000_000, 5_206).saturating_mul(n.into()))
+			.saturating_add(Weight::from_parts(20_000_000_000, 3_159).saturating_mul(k.into()))
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().reads((6_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(k.into())))
+			.saturating_add(T::DbWeight::get().writes((3_u64).saturating_mul(n.into())))
+	}
+	/// Storage: Mixer BlackList (r:0 w:1)
+	fn add_black_list() -> Weight {
+		Weight::from_parts(12_000_000, 0)
 			.saturating_add(T::DbWeight::get().writes(1_u64))
 	}
-	/// Storage: TemplateModule Something (r:1 w:1)
-	/// Proof: TemplateModule Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
-	fn cause_error() -> Weight {
-		// Proof Size summary in bytes:
-		//  Measured:  `32`
-		//  Estimated: `1489`
-		// Minimum execution time: 6_000_000 picoseconds.
-		Weight::from_parts(6_000_000, 1489)
+	/// Storage: Mixer BlackList (r:1 w:1)
+	fn remove_black_list() -> Weight {
+		Weight::from_parts(15_000_000, 3_541)
 			.saturating_add(T::DbWeight::get().reads(1_u64))
 			.saturating_add(T::DbWeight::get().writes(1_u64))
 	}
+	/// Storage: Mixer BlackList (r:0 w:1)
+	/// The range of component `n` is `[1, 100]`.
+	fn add_black_list_batch(n: u32, ) -> Weight {
+		Weight::from_parts(5_000_000, 0)
+			.saturating_add(Weight::from_parts(8_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(n.into())))
+	}
+	/// Storage: Mixer BlackList (r:1 w:1)
+	/// The range of component `n` is `[1, 100]`.
+	fn remove_black_list_batch(n: u32, ) -> Weight {
+		Weight::from_parts(5_000_000, 0)
+			.saturating_add(Weight::from_parts(10_000_000, 2_541).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(n.into())))
+	}
+	/// Storage: Mixer NextPoolId (r:1 w:1)
+	/// Storage: Mixer Pools (r:0 w:1)
+	fn create_pool() -> Weight {
+		Weight::from_parts(15_000_000, 1_489)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: Mixer Nodes (r:0 w:31)
+	/// The range of component `d` is `[1, 32]`.
+	fn merkle_insert(d: u32, ) -> Weight {
+		Weight::from_parts(2_000_000, 0)
+			.saturating_add(Weight::from_parts(50_000_000, 0).saturating_mul(d.into()))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(d.into())))
+	}
 }
 
 // For backwards compatibility and tests
 impl WeightInfo for () {
-	/// Storage: TemplateModule Something (r:0 w:1)
-	/// Proof: TemplateModule Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
-	fn do_something() -> Weight {
-		// Proof Size summary in bytes:
-		//  Measured:  `0`
-		//  Estimated: `0`
-		// Minimum execution time: 8_000_000 picoseconds.
-		Weight::from_parts(9_000_000, 0)
+	/// Storage: Mixer BlackList (r:1 w:0)
+	/// Storage: Mixer Pools (r:1 w:0)
+	/// Storage: Mixer Commitments (r:1 w:1)
+	/// Storage: Mixer FilledSubtrees (r:1 w:1)
+	/// Storage: Mixer ZeroHashes (r:1 w:1)
+	/// Storage: Mixer NextIndex (r:0 w:1)
+	/// Storage: Mixer CurrentRoot (r:0 w:1)
+	/// Storage: Mixer Leaves (r:0 w:1)
+	/// Storage: Mixer CurrentRootIndex (r:1 w:1)
+	/// Storage: Mixer RootHistory (r:1 w:1)
+	/// Storage: Mixer Roots (r:0 w:2)
+	/// Storage: System Account (r:2 w:2)
+	/// Storage: Timestamp Now (r:1 w:0)
+	fn deposit() -> Weight {
+		Weight::from_parts(60_000_000, 6_196)
+			.saturating_add(RocksDbWeight::get().reads(10_u64))
+			.saturating_add(RocksDbWeight::get().writes(13_u64))
+	}
+	/// Storage: Mixer BlackList (r:3 w:0)
+	/// Storage: Mixer Pools (r:1 w:0)
+	/// Storage: Mixer NullifierHashes (r:1 w:1)
+	/// Storage: Mixer Roots (r:1 w:0)
+	/// Storage: VkRegistry UsableVersions (r:1 w:0)
+	/// Storage: VkRegistry VerificationKeys (r:4 w:0)
+	/// Storage: System Account (r:3 w:3)
+	/// The range of component `k` is `[1, 4]`.
+	fn withdraw(k: u32, ) -> Weight {
+		Weight::from_parts(500_000_000, 5_640)
+			.saturating_add(Weight::from_parts(1_500_000_000, 3_159).saturating_mul(k.into()))
+			.saturating_add(RocksDbWeight::get().reads(10_u64))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(k.into())))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+	/// Storage: Mixer BlackList (r:2 w:0)
+	/// Storage: Mixer Pools (r:1 w:0)
+	/// Storage: Mixer NullifierHashes (r:1 w:1)
+	/// Storage: Mixer Roots (r:1 w:0)
+	/// Storage: VkRegistry UsableVersions (r:1 w:0)
+	/// Storage: VkRegistry VerificationKeys (r:4 w:0)
+	/// Storage: System Account (r:3 w:3)
+	/// The range of component `k` is `[1, 4]`.
+	fn withdraw_unsigned(k: u32, ) -> Weight {
+		Weight::from_parts(500_000_000, 5_640)
+			.saturating_add(Weight::from_parts(1_500_000_000, 3_159).saturating_mul(k.into()))
+			.saturating_add(RocksDbWeight::get().reads(9_u64))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(k.into())))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+	/// Storage: Mixer BlackList (r:1 w:0)
+	/// Storage: Mixer Pools (r:1 w:0)
+	/// Storage: VkRegistry UsableVersions (r:1 w:0)
+	/// Storage: VkRegistry VerificationKeys (r:4 w:0)
+	/// Storage: Mixer BlackList (r:2 w:0)
+	/// Storage: Mixer NullifierHashes (r:1 w:1)
+	/// Storage: Mixer Roots (r:1 w:0)
+	/// Storage: System Account (r:2 w:2)
+	/// The range of component `n` is `[1, 64]`.
+	/// The range of component `k` is `[1, 4]`.
+	fn batch_withdraw(n: u32, k: u32, ) -> Weight {
+		Weight::from_parts(300_000_000, 434)
+			.saturating_add(Weight::from_parts(1_900_000_000, 5_206).saturating_mul(n.into()))
+			.saturating_add(Weight::from_parts(20_000_000_000, 3_159).saturating_mul(k.into()))
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().reads((6_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(k.into())))
+			.saturating_add(RocksDbWeight::get().writes((3_u64).saturating_mul(n.into())))
+	}
+	/// Storage: Mixer BlackList (r:0 w:1)
+	fn add_black_list() -> Weight {
+		Weight::from_parts(12_000_000, 0)
 			.saturating_add(RocksDbWeight::get().writes(1_u64))
 	}
-	/// Storage: TemplateModule Something (r:1 w:1)
-	/// Proof: TemplateModule Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
-	fn cause_error() -> Weight {
-		// Proof Size summary in bytes:
-		//  Measured:  `32`
-		//  Estimated: `1489`
-		// Minimum execution time: 6_000_000 picoseconds.
-		Weight::from_parts(6_000_000, 1489)
+	/// Storage: Mixer BlackList (r:1 w:1)
+	fn remove_black_list() -> Weight {
+		Weight::from_parts(15_000_000, 3_541)
 			.saturating_add(RocksDbWeight::get().reads(1_u64))
 			.saturating_add(RocksDbWeight::get().writes(1_u64))
 	}
+	/// Storage: Mixer BlackList (r:0 w:1)
+	/// The range of component `n` is `[1, 100]`.
+	fn add_black_list_batch(n: u32, ) -> Weight {
+		Weight::from_parts(5_000_000, 0)
+			.saturating_add(Weight::from_parts(8_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(n.into())))
+	}
+	/// Storage: Mixer BlackList (r:1 w:1)
+	/// The range of component `n` is `[1, 100]`.
+	fn remove_black_list_batch(n: u32, ) -> Weight {
+		Weight::from_parts(5_000_000, 0)
+			.saturating_add(Weight::from_parts(10_000_000, 2_541).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(n.into())))
+	}
+	/// Storage: Mixer NextPoolId (r:1 w:1)
+	/// Storage: Mixer Pools (r:0 w:1)
+	fn create_pool() -> Weight {
+		Weight::from_parts(15_000_000, 1_489)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	/// Storage: Mixer Nodes (r:0 w:31)
+	/// The range of component `d` is `[1, 32]`.
+	fn merkle_insert(d: u32, ) -> Weight {
+		Weight::from_parts(2_000_000, 0)
+			.saturating_add(Weight::from_parts(50_000_000, 0).saturating_mul(d.into()))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(d.into())))
+	}
 }
@@ -1,35 +1,90 @@
-//! Benchmarking setup for pallet-template
+//! Benchmarking setup for pallet-otp
 #![cfg(feature = "runtime-benchmarks")]
 use super::*;
 
-#[allow(unused)]
-use crate::Pallet as Template;
+use crate::Pallet as OtpPallet;
 use frame_benchmarking::v2::*;
+use frame_support::traits::{Get, UnixTime};
 use frame_system::RawOrigin;
+use primitives::{Otp, VerificationKeyRegistry};
+use scale_info::prelude::string::ToString;
+use sp_std::vec;
+use zk_verifier::{
+	benchmarking::{simulated_proof, simulated_verification_key},
+	EncodedProof,
+};
+
+const ROOT: u64 = 1_234_567;
+
+/// Registers the simulated key for the otp circuit.
+fn set_verification_key<T: Config>() {
+	T::VerificationKeys::set_verification_keys(
+		T::OtpCircuitId::get(),
+		OTP_PUBLIC_INPUTS as u32,
+		None,
+		vec![simulated_verification_key(OTP_PUBLIC_INPUTS).to_bytes()],
+	);
+}
+
+/// Json proof of `ROOT` at `timestamp`, the largest encoding the pallet accepts.
+fn proof(timestamp: u128) -> EncodedProof {
+	simulated_proof(vec![U256::from(ROOT), U256::from(timestamp)])
+}
+
+fn root() -> Vec<u8> {
+	ROOT.to_string().into_bytes()
+}
+
+/// `who` owning `ROOT`, the way `set_otp_commitment` records it.
+fn set_root<T: Config>(who: &T::AccountId) {
+	UserRoots::<T>::insert(who, U256::from(ROOT));
+}
 
 #[benchmarks]
 mod benchmarks {
 	use super::*;
 
 	#[benchmark]
-	fn do_something() {
-		let value = 100u32.into();
+	fn set_otp_commitment() {
 		let caller: T::AccountId = whitelisted_caller();
+
 		#[extrinsic_call]
-		do_something(RawOrigin::Signed(caller), value);
+		set_otp_commitment(RawOrigin::Signed(caller.clone()), root());
 
-		assert_eq!(Something::<T>::get(), Some(value));
+		assert_eq!(UserRoots::<T>::get(caller), Some(U256::from(ROOT)));
 	}
 
 	#[benchmark]
-	fn cause_error() {
-		Something::<T>::put(100u32);
+	fn naive_approval() {
 		let caller: T::AccountId = whitelisted_caller();
-		#[extrinsic_call]
-		cause_error(RawOrigin::Signed(caller));
+		set_verification_key::<T>();
+		set_root::<T>(&caller);
+		let timestamp = 1;
+		let proof = proof(timestamp);
 
-		assert_eq!(Something::<T>::get(), Some(101u32));
+		#[block]
+		{
+			OtpPallet::<T>::naive_approval(caller.clone(), proof, root(), timestamp)
+				.expect("simulated proof verifies");
+		}
+
+		assert_eq!(UserLastTimestamp::<T>::get(caller), timestamp);
 	}
 
-	impl_benchmark_test_suite!(Template, crate::mock::new_test_ext(), crate::mock::Test);
+	#[benchmark]
+	fn block_time_approval() {
+		let caller: T::AccountId = whitelisted_caller();
+		set_verification_key::<T>();
+		set_root::<T>(&caller);
+		let timestamp = T::TimeProvider::now().as_millis() + 1;
+		let proof = proof(timestamp);
+
+		#[block]
+		{
+			OtpPallet::<T>::block_time_approval(caller.clone(), proof, root(), timestamp)
+				.expect("simulated proof verifies");
+		}
+
+		assert_eq!(UserLastTimestamp::<T>::get(caller), timestamp);
+	}
 }
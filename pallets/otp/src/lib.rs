@@ -58,45 +58,40 @@ mod tests;
 // for each dispatchable and generates this pallet's weight.rs file. Learn more about benchmarking here: https://docs.substrate.io/test/benchmark/
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
+pub mod migrations;
 pub mod weights;
 pub use weights::*;
 
-pub mod common;
-pub mod deserialization;
-pub mod verify;
-
 use frame_support::storage::bounded_vec::BoundedVec;
 pub use pallet::*;
 use sp_std::vec::Vec;
 
 type PublicInputsDef<T> = BoundedVec<u8, <T as Config>::MaxPublicInputsLength>;
-type ProofDef<T> = BoundedVec<u8, <T as Config>::MaxProofLength>;
-type VerificationKeyDef<T> = BoundedVec<u8, <T as Config>::MaxVerificationKeyLength>;
 
 // All pallet logic is defined in its own module and must be annotated by the `pallet` attribute.
 #[frame_support::pallet]
 pub mod pallet {
 	// Import various useful types required by all FRAME pallets.
 	use super::*;
-	use crate::{
-		common::prepare_verification_key,
-		deserialization::{Proof, VKey},
-		verify::{
-			prepare_public_inputs, verify, G1UncompressedBytes, G2UncompressedBytes, GProof,
-			VerificationKey, SUPPORTED_CURVE, SUPPORTED_PROTOCOL,
-		},
-	};
 	use frame_support::{pallet_prelude::*, traits::UnixTime};
 	use frame_system::pallet_prelude::*;
-	use primitives::Otp;
-	use scale_info::prelude::string::String;
+	use primitives::{CircuitId, Otp, VerificationKeyRegistry};
 	use sp_std::vec;
+	use zk_verifier::{
+		decode_proof, verify_any, CurveProof, CurveVerificationError, CurveVerificationKey,
+		EncodedProof, ParseError,
+	};
 
-	//const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+	/// The in-code storage version.
+	pub const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
+	/// Public inputs of the otp circuit: the root and the timestamp.
+	pub const OTP_PUBLIC_INPUTS: usize = 2;
 
 	// The `Pallet` struct serves as a placeholder to implement traits, methods and dispatchables
 	// (`Call`s) in this pallet.
 	#[pallet::pallet]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(_);
 
 	/// The pallet's configuration trait.
@@ -118,9 +113,12 @@ pub mod pallet {
 		#[pallet::constant]
 		type MaxProofLength: Get<u32>;
 
-		/// The maximum length of the verification key.
+		/// Registry the otp circuit's verification keys are read from.
+		type VerificationKeys: VerificationKeyRegistry;
+
+		/// Registry id of the otp circuit.
 		#[pallet::constant]
-		type MaxVerificationKeyLength: Get<u32>;
+		type OtpCircuitId: Get<CircuitId>;
 
 		type TimeProvider: UnixTime;
 	}
@@ -146,10 +144,6 @@ pub mod pallet {
 	#[pallet::storage]
 	pub type PublicInputStorage<T: Config> = StorageValue<_, PublicInputsDef<T>, ValueQuery>;
 
-	/// Storing a verification key.
-	#[pallet::storage]
-	pub type VerificationKeyStorage<T: Config> = StorageValue<_, VerificationKeyDef<T>, ValueQuery>;
-
 	/// Events that functions in this pallet can emit.
 	///
 	/// Events are a simple means of indicating to the outside world (such as dApps, chain explorers
@@ -163,7 +157,6 @@ pub mod pallet {
 	#[pallet::event]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
 	pub enum Event<T: Config> {
-		VerificationSetupCompleted,
 		OtpCommitmentSeted,
 	}
 
@@ -185,8 +178,6 @@ pub mod pallet {
 		PublicInputsMismatch,
 		/// Public inputs vector is to long.
 		TooLongPublicInputs,
-		/// The verification key is to long.
-		TooLongVerificationKey,
 		/// The proof is too long.
 		TooLongProof,
 		/// The proof is too short.
@@ -225,6 +216,10 @@ pub mod pallet {
 		TimestampMustBeLargerThanLast,
 		/// timestampe must be larger than chain
 		TimestampMustBeLargerThanChain,
+		/// Proof is on another curve than the verification key
+		CurveMismatch,
+		/// The OTP root is not a decimal number of the field
+		InvalidRoot,
 	}
 
 	/// The pallet's dispatchable functions ([`Call`]s).
@@ -241,21 +236,13 @@ pub mod pallet {
 	/// The [`weight`] macro is used to assign a weight to each call.
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
-		#[pallet::call_index(0)]
-		#[pallet::weight(0)]
-		pub fn setup_verification(_origin: OriginFor<T>, vec_vk: Vec<u8>) -> DispatchResult {
-			let _vk = store_verification_key::<T>(vec_vk)?;
-			Self::deposit_event(Event::<T>::VerificationSetupCompleted);
-			Ok(())
-		}
-
 		#[pallet::call_index(1)]
-		#[pallet::weight(0)]
+		#[pallet::weight(T::WeightInfo::set_otp_commitment())]
 		pub fn set_otp_commitment(origin: OriginFor<T>, root: Vec<u8>) -> DispatchResult {
 			// Check that the extrinsic was signed and get the signer.
 			let who = ensure_signed(origin)?;
 
-			let r = U256::from_dec_str(&String::from_utf8(root).unwrap()).unwrap();
+			let r = parse_root::<T>(&root)?;
 
 			ensure!(!Roots::<T>::contains_key(r), Error::<T>::CommitmentHasBeanSubmitted);
 
@@ -267,153 +254,142 @@ pub mod pallet {
 		}
 	}
 
-	fn get_verification_key<T: Config>() -> Result<VerificationKey, sp_runtime::DispatchError> {
-		let vk = VerificationKeyStorage::<T>::get();
+	/// Usable keys of the otp circuit, the latest registered version first.
+	fn get_verification_keys<T: Config>(
+	) -> Result<Vec<CurveVerificationKey>, sp_runtime::DispatchError> {
+		let keys = T::VerificationKeys::verification_keys(T::OtpCircuitId::get());
+		ensure!(!keys.is_empty(), Error::<T>::VerificationKeyIsNotSet);
+
+		let keys: Vec<_> = keys
+			.into_iter()
+			.filter(|key| key.public_inputs as usize == OTP_PUBLIC_INPUTS)
+			.collect();
+		ensure!(!keys.is_empty(), Error::<T>::PublicInputsMismatch);
+
+		keys.into_iter()
+			.map(|key| {
+				CurveVerificationKey::from_bytes(&key.key)
+					.map_err(|_| Error::<T>::VerificationKeyCreationError.into())
+			})
+			.collect()
+	}
+
+	fn parse_proof<T: Config>(
+		proof: &EncodedProof,
+	) -> Result<CurveProof, sp_runtime::DispatchError> {
+		log::info!("before check in parse_proof length");
+		if let EncodedProof::Json(json) = proof {
+			ensure!(json.len() <= T::MaxProofLength::get() as usize, Error::<T>::TooLongProof);
+		}
+		let proof = decode_proof(proof).map_err(parse_error::<T>(Error::<T>::MalformedProof))?;
 
-		ensure!(!vk.is_empty(), Error::<T>::VerificationKeyIsNotSet);
-		let deserialized_vk = VKey::from_json_u8_slice(vk.as_slice())
-			.map_err(|_| Error::<T>::MalformedVerificationKey)?;
-		let vk = prepare_verification_key(deserialized_vk)
-			.map_err(|_| Error::<T>::VerificationKeyCreationError)?;
-		Ok(vk)
+		Ok(proof)
 	}
 
-	fn store_verification_key<T: Config>(
-		vec_vk: Vec<u8>,
-	) -> Result<VKey, sp_runtime::DispatchError> {
-		let vk: VerificationKeyDef<T> = vec_vk.try_into().map_err(|e| {
-			log::info!("@@@ store_verification_key err: {:?}", e);
-			Error::<T>::TooLongVerificationKey
-		})?;
-		let deserialized_vk = VKey::from_json_u8_slice(vk.as_slice())
-			.map_err(|_| Error::<T>::MalformedVerificationKey)?;
-		ensure!(deserialized_vk.curve == SUPPORTED_CURVE.as_bytes(), Error::<T>::NotSupportedCurve);
-		ensure!(
-			deserialized_vk.protocol == SUPPORTED_PROTOCOL.as_bytes(),
-			Error::<T>::NotSupportedProtocol
-		);
-
-		VerificationKeyStorage::<T>::put(vk);
-		Ok(deserialized_vk)
+	/// Decimal OTP root as given to [`Pallet::set_otp_commitment`] and the approvals.
+	fn parse_root<T: Config>(root: &[u8]) -> Result<U256, sp_runtime::DispatchError> {
+		ensure!(!root.is_empty(), Error::<T>::InvalidRoot);
+		let root = sp_std::str::from_utf8(root).map_err(|_| Error::<T>::InvalidRoot)?;
+		Ok(U256::from_dec_str(root).map_err(|_| Error::<T>::InvalidRoot)?)
 	}
 
-	fn parse_proof<T: Config>(vec_proof: Vec<u8>) -> Result<GProof, sp_runtime::DispatchError> {
-		log::info!("before check in parse_proof try_into");
-		let proof: ProofDef<T> = vec_proof.try_into().map_err(|_| Error::<T>::TooLongProof)?;
-
-		log::info!("before check in parse_proof deserialized_proof MalformedProof");
-		let deserialized_proof =
-			Proof::from_json_u8_slice(proof.as_slice()).map_err(|_| Error::<T>::MalformedProof)?;
-		ensure!(
-			deserialized_proof.curve == SUPPORTED_CURVE.as_bytes(),
-			Error::<T>::NotSupportedCurve
-		);
-		ensure!(
-			deserialized_proof.protocol == SUPPORTED_PROTOCOL.as_bytes(),
-			Error::<T>::NotSupportedProtocol
-		);
-
-		log::info!("before check in from_uncompressed");
-		let proof = GProof::from_uncompressed(
-			&G1UncompressedBytes::new(deserialized_proof.a[0], deserialized_proof.a[1]),
-			&G2UncompressedBytes::new(
-				deserialized_proof.b[0][0],
-				deserialized_proof.b[0][1],
-				deserialized_proof.b[1][0],
-				deserialized_proof.b[1][1],
-			),
-			&G1UncompressedBytes::new(deserialized_proof.c[0], deserialized_proof.c[1]),
-		)
-		.map_err(|_| Error::<T>::ProofCreationError)?;
+	/// Maps a key or proof parsing failure to the pallet error, `malformed` for invalid json.
+	fn parse_error<T: Config>(malformed: Error<T>) -> impl FnOnce(ParseError) -> Error<T> {
+		move |e| match e {
+			ParseError::Malformed => malformed,
+			ParseError::NotSupportedCurve => Error::<T>::NotSupportedCurve,
+			ParseError::NotSupportedProtocol => Error::<T>::NotSupportedProtocol,
+			ParseError::PointCreationError => Error::<T>::ProofCreationError,
+		}
+	}
 
-		Ok(proof)
+	/// Runs the Groth16 check against every usable key on the curve the proof was given on.
+	fn verify_proof<T: Config>(
+		vks: &[CurveVerificationKey],
+		proof: &CurveProof,
+		public_inputs: Vec<U256>,
+	) -> Result<bool, sp_runtime::DispatchError> {
+		verify_any(vks, proof, public_inputs).map_err(|e| {
+			log::info!("verify error {:?}", e);
+			match e {
+				CurveVerificationError::CurveMismatch => Error::<T>::CurveMismatch,
+				CurveVerificationError::PublicInputsOutOfRange => Error::<T>::MalformedPublicInputs,
+				CurveVerificationError::InvalidVerificationKey =>
+					Error::<T>::ProofVerificationError,
+			}
+			.into()
+		})
 	}
 
 	impl<T: Config> Otp<T::AccountId> for Pallet<T> {
+		fn approval_weight() -> Weight {
+			T::WeightInfo::naive_approval().max(T::WeightInfo::block_time_approval())
+		}
+
 		//Only checks that time in the proof is larger than lastUsedTime, i.e. behaves like HOTP
 		fn naive_approval(
 			owner: T::AccountId,
-			proof: Vec<u8>,
+			proof: EncodedProof,
 			root: Vec<u8>,
 			timestamp: u128,
 		) -> DispatchResult {
-			log::info!("before check in get_verification_key");
-			let vk = get_verification_key::<T>()?;
+			log::info!("before check in get_verification_keys");
+			let vks = get_verification_keys::<T>()?;
 
 			log::info!("before check in parse_proof");
-			let proof = parse_proof::<T>(proof)?;
+			let proof = parse_proof::<T>(&proof)?;
 
-			log::info!("before U256::from_dec_str(&root);");
-			let root = U256::from_dec_str(&String::from_utf8(root).unwrap()).unwrap();
+			log::info!("before parse_root(&root);");
+			let root = parse_root::<T>(&root)?;
+			ensure!(UserRoots::<T>::get(&owner) == Some(root), Error::<T>::NotMerkelRootOwner);
 
 			log::info!("before UserLastTimestamp::<T>::get(owner);;");
-			let user_last_timestamp = UserLastTimestamp::<T>::get(owner);
+			let user_last_timestamp = UserLastTimestamp::<T>::get(&owner);
 			log::info!("before timestamp > user_last_timestamp");
 			ensure!(timestamp > user_last_timestamp, Error::<T>::TimestampMustBeLargerThanLast);
 
-			let timestamp = U256::from(timestamp);
-			let public_inputs = vec![root, timestamp];
+			let public_inputs = vec![root, U256::from(timestamp)];
 			log::info!("before public_inputs {:?}", public_inputs);
-			let public_inputs = prepare_public_inputs(public_inputs);
 
 			log::info!("before verify");
-			match verify(vk, proof, public_inputs) {
-				Ok(true) => {
-					log::info!("verify OK");
-					//Ok(())
-				},
-				Ok(false) => {
-					log::info!("verify false");
-					//Self::deposit_event(Event::<T>::VerificationFailed);
-					return Err(Error::<T>::ProofVerificationFalse.into())
-				},
-				Err(e) => {
-					log::info!("verify error {:?}", e);
-					return Err(Error::<T>::ProofVerificationError.into())
-				},
-			};
-
+			ensure!(
+				verify_proof::<T>(&vks, &proof, public_inputs)?,
+				Error::<T>::ProofVerificationFalse
+			);
+			log::info!("verify OK");
+
+			// a password is accepted once
+			UserLastTimestamp::<T>::insert(owner, timestamp);
 			Ok(())
 		}
 
 		//Uses block timestamp to validate time, TOTP
 		fn block_time_approval(
 			owner: T::AccountId,
-			proof: Vec<u8>,
+			proof: EncodedProof,
 			root: Vec<u8>,
 			timestamp: u128,
 		) -> DispatchResult {
-			let vk = get_verification_key::<T>()?;
-			let proof = parse_proof::<T>(proof)?;
+			let vks = get_verification_keys::<T>()?;
+			let proof = parse_proof::<T>(&proof)?;
 
-			let root = U256::from_dec_str(&String::from_utf8(root).unwrap()).unwrap();
+			let root = parse_root::<T>(&root)?;
+			ensure!(UserRoots::<T>::get(&owner) == Some(root), Error::<T>::NotMerkelRootOwner);
 
-			let user_last_timestamp = UserLastTimestamp::<T>::get(owner);
+			let user_last_timestamp = UserLastTimestamp::<T>::get(&owner);
 			ensure!(timestamp > user_last_timestamp, Error::<T>::TimestampMustBeLargerThanLast);
 
 			let timestamp_now = T::TimeProvider::now();
 			let block_time = timestamp_now.as_millis();
 			ensure!(timestamp > block_time, Error::<T>::TimestampMustBeLargerThanChain);
 
-			let timestamp = U256::from(timestamp);
-			let public_inputs = vec![root, timestamp];
-			let public_inputs = prepare_public_inputs(public_inputs);
-
-			match verify(vk, proof, public_inputs) {
-				Ok(true) => {
-					//Ok(())
-				},
-				Ok(false) => {
-					//Self::deposit_event(Event::<T>::VerificationFailed);
-					return Err(Error::<T>::ProofVerificationFalse.into())
-				},
-				Err(e) => {
-					log::info!("verify error {:?}", e);
-
-					return Err(Error::<T>::ProofVerificationError.into())
-				},
-			};
+			let public_inputs = vec![root, U256::from(timestamp)];
+			ensure!(
+				verify_proof::<T>(&vks, &proof, public_inputs)?,
+				Error::<T>::ProofVerificationFalse
+			);
 
+			UserLastTimestamp::<T>::insert(owner, timestamp);
 			Ok(())
 		}
 	}
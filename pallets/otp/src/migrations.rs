@@ -0,0 +1,55 @@
+//! Storage migrations for the otp pallet.
+
+use super::*;
+use frame_support::{
+	pallet_prelude::*,
+	traits::{GetStorageVersion, OnRuntimeUpgrade},
+};
+use sp_std::marker::PhantomData;
+
+#[cfg(feature = "try-runtime")]
+use sp_runtime::TryRuntimeError;
+
+pub mod v1 {
+	use super::*;
+
+	#[frame_support::storage_alias]
+	pub type VerificationKeyStorage<T: Config> = StorageValue<Pallet<T>, Vec<u8>, ValueQuery>;
+
+	/// Drops the verification key `setup_verification` used to store. Otp keys now live in
+	/// `Config::VerificationKeys` and have to be registered there under `OtpCircuitId`.
+	pub struct MigrateToV1<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+		fn on_runtime_upgrade() -> Weight {
+			let on_chain_version = Pallet::<T>::on_chain_storage_version();
+			if on_chain_version != 0 {
+				log::info!(
+					"pallet_otp: skipping v1 migration, on-chain version {:?}",
+					on_chain_version
+				);
+				return T::DbWeight::get().reads(1)
+			}
+
+			v1::VerificationKeyStorage::<T>::kill();
+			StorageVersion::new(1).put::<Pallet<T>>();
+
+			log::info!("pallet_otp: removed the verification key in v1 migration");
+
+			T::DbWeight::get().reads_writes(1, 2)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(_state: Vec<u8>) -> Result<(), TryRuntimeError> {
+			ensure!(
+				!v1::VerificationKeyStorage::<T>::exists(),
+				"pallet_otp: verification key not removed"
+			);
+			ensure!(
+				Pallet::<T>::on_chain_storage_version() == 1,
+				"pallet_otp: storage version not bumped"
+			);
+			Ok(())
+		}
+	}
+}
@@ -1,29 +1,7 @@
-
-//! Autogenerated weights for pallet_template
+//! Weights for pallet_otp
 //!
-//! THIS FILE WAS AUTO-GENERATED USING THE SUBSTRATE BENCHMARK CLI VERSION 4.0.0-dev
-//! DATE: 2023-04-06, STEPS: `50`, REPEAT: `20`, LOW RANGE: `[]`, HIGH RANGE: `[]`
-//! WORST CASE MAP SIZE: `1000000`
-//! HOSTNAME: `Alexs-MacBook-Pro-2.local`, CPU: `<UNKNOWN>`
-//! EXECUTION: Some(Wasm), WASM-EXECUTION: Compiled, CHAIN: Some("dev"), DB CACHE: 1024
-
-// Executed Command:
-// ../../target/release/node-template
-// benchmark
-// pallet
-// --chain
-// dev
-// --pallet
-// pallet_template
-// --extrinsic
-// *
-// --steps=50
-// --repeat=20
-// --wasm-execution=compiled
-// --output
-// pallets/template/src/weights.rs
-// --template
-// ../../.maintain/frame-weight-template.hbs
+//! Estimated from the storage accessed by each call until they are generated on reference
+//! hardware with `scripts/benchmark.sh`, which overwrites this file.
 
 #![cfg_attr(rustfmt, rustfmt_skip)]
 #![allow(unused_parens)]
@@ -32,59 +10,70 @@
 use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
 use core::marker::PhantomData;
 
-/// Weight functions needed for pallet_template.
+/// Weight functions needed for pallet_otp.
 pub trait WeightInfo {
-	fn do_something() -> Weight;
-	fn cause_error() -> Weight;
+	fn set_otp_commitment() -> Weight;
+	fn naive_approval() -> Weight;
+	fn block_time_approval() -> Weight;
 }
 
-/// Weights for pallet_template using the Substrate node and recommended hardware.
+/// Weights for pallet_otp using the Substrate node and recommended hardware.
 pub struct SubstrateWeight<T>(PhantomData<T>);
 impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
-	/// Storage: TemplateModule Something (r:0 w:1)
-	/// Proof: TemplateModule Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
-	fn do_something() -> Weight {
-		// Proof Size summary in bytes:
-		//  Measured:  `0`
-		//  Estimated: `0`
-		// Minimum execution time: 8_000_000 picoseconds.
-		Weight::from_parts(9_000_000, 0)
+	/// Storage: Otp Roots (r:1 w:0)
+	/// Storage: Otp UserRoots (r:0 w:1)
+	fn set_otp_commitment() -> Weight {
+		Weight::from_parts(15_000_000, 3_541)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
 			.saturating_add(T::DbWeight::get().writes(1_u64))
 	}
-	/// Storage: TemplateModule Something (r:1 w:1)
-	/// Proof: TemplateModule Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
-	fn cause_error() -> Weight {
-		// Proof Size summary in bytes:
-		//  Measured:  `32`
-		//  Estimated: `1489`
-		// Minimum execution time: 6_000_000 picoseconds.
-		Weight::from_parts(6_000_000, 1489)
-			.saturating_add(T::DbWeight::get().reads(1_u64))
+	/// Storage: VkRegistry UsableVersions (r:1 w:0)
+	/// Storage: VkRegistry VerificationKeys (r:1 w:0)
+	/// Storage: Otp UserRoots (r:1 w:0)
+	/// Storage: Otp UserLastTimestamp (r:1 w:1)
+	fn naive_approval() -> Weight {
+		Weight::from_parts(1_900_000_000, 6_260)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: VkRegistry UsableVersions (r:1 w:0)
+	/// Storage: VkRegistry VerificationKeys (r:1 w:0)
+	/// Storage: Otp UserRoots (r:1 w:0)
+	/// Storage: Otp UserLastTimestamp (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	fn block_time_approval() -> Weight {
+		Weight::from_parts(1_900_000_000, 6_260)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
 			.saturating_add(T::DbWeight::get().writes(1_u64))
 	}
 }
 
 // For backwards compatibility and tests
 impl WeightInfo for () {
-	/// Storage: TemplateModule Something (r:0 w:1)
-	/// Proof: TemplateModule Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
-	fn do_something() -> Weight {
-		// Proof Size summary in bytes:
-		//  Measured:  `0`
-		//  Estimated: `0`
-		// Minimum execution time: 8_000_000 picoseconds.
-		Weight::from_parts(9_000_000, 0)
+	/// Storage: Otp Roots (r:1 w:0)
+	/// Storage: Otp UserRoots (r:0 w:1)
+	fn set_otp_commitment() -> Weight {
+		Weight::from_parts(15_000_000, 3_541)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
 			.saturating_add(RocksDbWeight::get().writes(1_u64))
 	}
-	/// Storage: TemplateModule Something (r:1 w:1)
-	/// Proof: TemplateModule Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
-	fn cause_error() -> Weight {
-		// Proof Size summary in bytes:
-		//  Measured:  `32`
-		//  Estimated: `1489`
-		// Minimum execution time: 6_000_000 picoseconds.
-		Weight::from_parts(6_000_000, 1489)
-			.saturating_add(RocksDbWeight::get().reads(1_u64))
+	/// Storage: VkRegistry UsableVersions (r:1 w:0)
+	/// Storage: VkRegistry VerificationKeys (r:1 w:0)
+	/// Storage: Otp UserRoots (r:1 w:0)
+	/// Storage: Otp UserLastTimestamp (r:1 w:1)
+	fn naive_approval() -> Weight {
+		Weight::from_parts(1_900_000_000, 6_260)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: VkRegistry UsableVersions (r:1 w:0)
+	/// Storage: VkRegistry VerificationKeys (r:1 w:0)
+	/// Storage: Otp UserRoots (r:1 w:0)
+	/// Storage: Otp UserLastTimestamp (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	fn block_time_approval() -> Weight {
+		Weight::from_parts(1_900_000_000, 6_260)
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
 			.saturating_add(RocksDbWeight::get().writes(1_u64))
 	}
 }
@@ -1,35 +1,88 @@
-//! Benchmarking setup for pallet-template
+//! Benchmarking setup for pallet-swap
 #![cfg(feature = "runtime-benchmarks")]
 use super::*;
 
-#[allow(unused)]
-use crate::Pallet as Template;
+use crate::Pallet as Swap;
 use frame_benchmarking::v2::*;
 use frame_system::RawOrigin;
+use orml_traits::{MultiCurrency, MultiReservableCurrency};
+use sp_runtime::traits::Saturating;
 
-#[benchmarks]
+/// Account holding `amount` of the default currency.
+fn funded_account<T: Config>(name: &'static str, amount: BalanceOf<T>) -> T::AccountId
+where
+	CurrencyIdOf<T>: Default,
+{
+	let who = account(name, 0, 0);
+	T::Currency::deposit(Default::default(), &who, amount).expect("deposit into a new account");
+	who
+}
+
+/// Amount traded by the benchmark orders, far above the existential deposit.
+fn amount<T: Config>() -> BalanceOf<T>
+where
+	CurrencyIdOf<T>: Default,
+{
+	T::Currency::minimum_balance(Default::default()).saturating_mul(1_000u32.into())
+}
+
+/// Order of `owner` selling `amount` for `amount` of the default currency.
+fn submit<T: Config>(owner: &T::AccountId) -> u32
+where
+	CurrencyIdOf<T>: Default,
+{
+	let order_id = NextOrderId::<T>::get();
+	Swap::<T>::submit_order(
+		RawOrigin::Signed(owner.clone()).into(),
+		Default::default(),
+		amount::<T>(),
+		Default::default(),
+		amount::<T>(),
+	)
+	.expect("owner holds the base amount");
+	order_id
+}
+
+#[benchmarks(where CurrencyIdOf<T>: Default)]
 mod benchmarks {
 	use super::*;
 
 	#[benchmark]
-	fn do_something() {
-		let value = 100u32.into();
-		let caller: T::AccountId = whitelisted_caller();
+	fn submit_order() {
+		let owner = funded_account::<T>("owner", amount::<T>().saturating_mul(2u32.into()));
+
 		#[extrinsic_call]
-		do_something(RawOrigin::Signed(caller), value);
+		submit_order(
+			RawOrigin::Signed(owner.clone()),
+			Default::default(),
+			amount::<T>(),
+			Default::default(),
+			amount::<T>(),
+		);
 
-		assert_eq!(Something::<T>::get(), Some(value));
+		assert_eq!(T::Currency::reserved_balance(Default::default(), &owner), amount::<T>());
 	}
 
 	#[benchmark]
-	fn cause_error() {
-		Something::<T>::put(100u32);
-		let caller: T::AccountId = whitelisted_caller();
+	fn take_order() {
+		let owner = funded_account::<T>("owner", amount::<T>().saturating_mul(2u32.into()));
+		let taker = funded_account::<T>("taker", amount::<T>().saturating_mul(2u32.into()));
+		let order_id = submit::<T>(&owner);
+
 		#[extrinsic_call]
-		cause_error(RawOrigin::Signed(caller));
+		take_order(RawOrigin::Signed(taker), order_id);
 
-		assert_eq!(Something::<T>::get(), Some(101u32));
+		assert!(Orders::<T>::get(order_id).is_none());
 	}
 
-	impl_benchmark_test_suite!(Template, crate::mock::new_test_ext(), crate::mock::Test);
+	#[benchmark]
+	fn cancel_order() {
+		let owner = funded_account::<T>("owner", amount::<T>().saturating_mul(2u32.into()));
+		let order_id = submit::<T>(&owner);
+
+		#[extrinsic_call]
+		cancel_order(RawOrigin::Signed(owner), order_id);
+
+		assert!(Orders::<T>::get(order_id).is_none());
+	}
 }
@@ -83,12 +83,13 @@ pub mod pallet {
 		pub owner: AccountId,
 	}
 
-	type BalanceOf<T> =
+	pub type BalanceOf<T> =
 		<<T as Config>::Currency as MultiCurrency<<T as frame_system::Config>::AccountId>>::Balance;
-	type CurrencyIdOf<T> = <<T as Config>::Currency as MultiCurrency<
+	pub type CurrencyIdOf<T> = <<T as Config>::Currency as MultiCurrency<
 		<T as frame_system::Config>::AccountId,
 	>>::CurrencyId;
-	type OrderOf<T> = Order<CurrencyIdOf<T>, BalanceOf<T>, <T as frame_system::Config>::AccountId>;
+	pub type OrderOf<T> =
+		Order<CurrencyIdOf<T>, BalanceOf<T>, <T as frame_system::Config>::AccountId>;
 
 	// The `Pallet` struct serves as a placeholder to implement traits, methods and dispatchables
 	// (`Call`s) in this pallet.
@@ -193,7 +194,7 @@ pub mod pallet {
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
 		#[pallet::call_index(0)]
-		#[pallet::weight(0)]
+		#[pallet::weight(T::WeightInfo::submit_order())]
 		pub fn submit_order(
 			origin: OriginFor<T>,
 			base_currency_id: CurrencyIdOf<T>,
@@ -226,7 +227,7 @@ pub mod pallet {
 		}
 
 		#[pallet::call_index(1)]
-		#[pallet::weight(0)]
+		#[pallet::weight(T::WeightInfo::take_order())]
 		pub fn take_order(origin: OriginFor<T>, order_id: u32) -> DispatchResult {
 			let who = ensure_signed(origin)?;
 
@@ -258,7 +259,7 @@ pub mod pallet {
 		}
 
 		#[pallet::call_index(2)]
-		#[pallet::weight(0)]
+		#[pallet::weight(T::WeightInfo::cancel_order())]
 		pub fn cancel_order(origin: OriginFor<T>, order_id: u32) -> DispatchResult {
 			let who = ensure_signed(origin)?;
 
@@ -275,7 +276,10 @@ pub mod pallet {
 		}
 	}
 
-	impl<T: Config> Swap<BalanceOf<T>, T::AccountId> for Pallet<T> {
+	impl<T: Config> Swap<CurrencyIdOf<T>, BalanceOf<T>, T::AccountId> for Pallet<T> {
+		fn get_target_currency_id(order_id: u32) -> Option<CurrencyIdOf<T>> {
+			Orders::<T>::get(order_id).map(|o| o.target_currency_id)
+		}
 		fn get_target_amount(order_id: u32) -> BalanceOf<T> {
 			let order = Orders::<T>::get(order_id);
 			match order {
@@ -283,6 +287,9 @@ pub mod pallet {
 				None => Default::default(),
 			}
 		}
+		fn take_order_weight() -> Weight {
+			T::WeightInfo::take_order()
+		}
 		fn inter_take_order(
 			taker: T::AccountId,
 			order_id: u32,
@@ -0,0 +1,85 @@
+//! Benchmarking setup for pallet-vk-registry
+//!
+//! Keys are [`zk_verifier::benchmarking::simulated_verification_key`] in json, with as many public
+//! inputs as fit the benchmarked length, so registering them runs the same point checks and pairing
+//! as registering a real key.
+#![cfg(feature = "runtime-benchmarks")]
+use super::*;
+
+use crate::Pallet as VkRegistry;
+use frame_benchmarking::v2::*;
+use frame_support::traits::{EnsureOrigin, Get};
+use zk_verifier::benchmarking::simulated_verification_key_json;
+
+const CIRCUIT: CircuitId = 0;
+const TREE_DEPTH: Option<u32> = Some(8);
+
+/// `verification_key.json` of `len` bytes, with whitespace after the largest key that fits.
+fn vk_json(len: u32) -> Vec<u8> {
+	let mut public_inputs = 0;
+	while public_inputs < u8::MAX as usize &&
+		simulated_verification_key_json(public_inputs + 1).len() <= len as usize
+	{
+		public_inputs += 1;
+	}
+	let mut json = simulated_verification_key_json(public_inputs);
+	if json.len() < len as usize {
+		json.resize(len as usize, b' ');
+	}
+	json
+}
+
+/// Registers `n` versions of `CIRCUIT`, all of them usable.
+fn register_versions<T: Config>(origin: &T::RuntimeOrigin, n: u32) {
+	for _ in 0..n {
+		VkRegistry::<T>::register_verification_key(
+			origin.clone(),
+			CIRCUIT,
+			vk_json(T::MaxVerificationKeyLength::get()),
+			TREE_DEPTH,
+		)
+		.expect("the benchmark key is valid");
+	}
+}
+
+#[benchmarks]
+mod benchmarks {
+	use super::*;
+
+	/// Replaces the active version while all other usable versions are still in their grace
+	/// window.
+	#[benchmark]
+	fn register_verification_key(
+		l: Linear<0, { T::MaxVerificationKeyLength::get() }>,
+	) -> Result<(), BenchmarkError> {
+		let origin =
+			T::RegisterOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		register_versions::<T>(&origin, T::MaxUsableVersions::get() - 1);
+		let vk = vk_json(l);
+
+		#[extrinsic_call]
+		register_verification_key(origin as T::RuntimeOrigin, CIRCUIT, vk, TREE_DEPTH);
+
+		assert_eq!(UsableVersions::<T>::get(CIRCUIT).len() as u32, T::MaxUsableVersions::get());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn deprecate_verification_key() -> Result<(), BenchmarkError> {
+		let origin =
+			T::RegisterOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		register_versions::<T>(&origin, T::MaxUsableVersions::get());
+		let version = ActiveVersion::<T>::get(CIRCUIT).expect("versions were registered");
+
+		#[extrinsic_call]
+		deprecate_verification_key(origin as T::RuntimeOrigin, CIRCUIT, version);
+
+		assert!(VerificationKeys::<T>::get(CIRCUIT, version)
+			.expect("version was registered")
+			.expires_at
+			.is_some());
+		Ok(())
+	}
+
+	impl_benchmark_test_suite!(VkRegistry, crate::mock::new_test_ext(), crate::mock::Test);
+}
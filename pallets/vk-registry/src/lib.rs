@@ -0,0 +1,349 @@
+//! # Verification Key Registry Pallet
+//!
+//! Keeps the Groth16 verification keys the mixer and otp pallets check proofs against.
+//!
+//! ## Overview
+//!
+//! Keys are addressed by `(CircuitId, KeyVersion)` and can only be registered by
+//! [`Config::RegisterOrigin`]. Registering a key for a circuit gives it the next version and makes
+//! it the circuit's active key. The version it replaces is deprecated and, like any version
+//! deprecated through [`Pallet::deprecate_verification_key`], stays usable for
+//! [`Config::DeprecationGracePeriod`] blocks so proofs generated against it are not rejected
+//! straight away.
+//!
+//! Keys are registered as snarkjs `verification_key.json` but stored in the compact form of
+//! [`zk_verifier::CurveVerificationKey::to_bytes`], with their points validated and `e(alpha,
+//! beta)` computed once at registration. Other pallets read the usable keys through
+//! [`primitives::VerificationKeyRegistry`].
+//!
+//! A key can not tell which depth of Merkle tree its circuit was compiled for, so the registering
+//! origin declares it along with the key and pallets proving membership skip keys of other depths.
+
+// We make sure this pallet uses `no_std` for compiling to Wasm.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+// Re-export pallet items so that they can be accessed from the crate namespace.
+pub use pallet::*;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod migrations;
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+pub mod weights;
+pub use weights::*;
+
+use frame_support::storage::bounded_vec::BoundedVec;
+use primitives::{CircuitId, KeyVersion, RegisteredVerificationKey, VerificationKeyRegistry};
+use sp_std::vec::Vec;
+use zk_verifier::Curve;
+
+type VerificationKeyDef<T> = BoundedVec<u8, <T as Config>::MaxVerificationKeyLength>;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::Saturating;
+
+	/// The in-code storage version.
+	pub const STORAGE_VERSION: StorageVersion = StorageVersion::new(2);
+
+	#[pallet::pallet]
+	#[pallet::storage_version(STORAGE_VERSION)]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching runtime event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+		/// A type representing the weights required by the dispatchables of this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Origin allowed to register and deprecate verification keys.
+		type RegisterOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// The maximum length of the verification key.
+		#[pallet::constant]
+		type MaxVerificationKeyLength: Get<u32>;
+
+		/// How many versions of a circuit's key can be usable at once.
+		#[pallet::constant]
+		type MaxUsableVersions: Get<u32>;
+
+		/// Blocks a deprecated key stays usable for.
+		#[pallet::constant]
+		type DeprecationGracePeriod: Get<BlockNumberFor<Self>>;
+	}
+
+	/// A registered verification key.
+	#[derive(
+		Encode,
+		Decode,
+		CloneNoBound,
+		PartialEqNoBound,
+		EqNoBound,
+		RuntimeDebugNoBound,
+		TypeInfo,
+		MaxEncodedLen,
+	)]
+	#[scale_info(skip_type_params(T))]
+	#[codec(mel_bound())]
+	pub struct VerificationKeyInfo<T: Config> {
+		/// Compact form of the prepared key
+		pub key: VerificationKeyDef<T>,
+		pub curve: Curve,
+		pub public_inputs: u32,
+		/// Depth of the Merkle tree the circuit proves membership in. The key can not tell, so it
+		/// is declared by [`Config::RegisterOrigin`].
+		pub tree_depth: Option<u32>,
+		/// First block the key is no longer usable in, set once it is deprecated.
+		pub expires_at: Option<BlockNumberFor<T>>,
+	}
+
+	/// Every key ever registered, by circuit and version.
+	#[pallet::storage]
+	#[pallet::getter(fn verification_key)]
+	pub type VerificationKeys<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		CircuitId,
+		Twox64Concat,
+		KeyVersion,
+		VerificationKeyInfo<T>,
+	>;
+
+	/// Latest registered version of every circuit.
+	#[pallet::storage]
+	#[pallet::getter(fn active_version)]
+	pub type ActiveVersion<T: Config> = StorageMap<_, Twox64Concat, CircuitId, KeyVersion>;
+
+	/// Versions of a circuit that were not past their grace window when it last changed, newest
+	/// first.
+	#[pallet::storage]
+	pub type UsableVersions<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		CircuitId,
+		BoundedVec<KeyVersion, T::MaxUsableVersions>,
+		ValueQuery,
+	>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		VerificationKeyRegistered {
+			circuit_id: CircuitId,
+			version: KeyVersion,
+			curve: Curve,
+			public_inputs: u32,
+			tree_depth: Option<u32>,
+		},
+		VerificationKeyDeprecated {
+			circuit_id: CircuitId,
+			version: KeyVersion,
+			expires_at: BlockNumberFor<T>,
+		},
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The verification key is to long.
+		TooLongVerificationKey,
+		/// Malformed key
+		MalformedVerificationKey,
+		/// Curve is not supported
+		NotSupportedCurve,
+		/// Protocol is not supported
+		NotSupportedProtocol,
+		/// Key has another number of `IC` points than `nPublic + 1`
+		PublicInputsMismatch,
+		/// Verification Key creation error
+		VerificationKeyCreationError,
+		/// No key with the given circuit id and version
+		VerificationKeyNotFound,
+		/// The key is already deprecated
+		AlreadyDeprecated,
+		/// `MaxUsableVersions` of the circuit are still usable
+		TooManyUsableVersions,
+		/// There was an attempt to increment the version over `u32::MAX`.
+		StorageOverflow,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Register `vec_vk` as the next version of `circuit_id` and deprecate the version it
+		/// replaces. `tree_depth` is the depth of the Merkle tree the circuit was compiled for, if
+		/// it proves membership in one.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::WeightInfo::register_verification_key(vec_vk.len() as u32))]
+		pub fn register_verification_key(
+			origin: OriginFor<T>,
+			circuit_id: CircuitId,
+			vec_vk: Vec<u8>,
+			tree_depth: Option<u32>,
+		) -> DispatchResult {
+			T::RegisterOrigin::ensure_origin(origin)?;
+
+			ensure!(
+				vec_vk.len() <= T::MaxVerificationKeyLength::get() as usize,
+				Error::<T>::TooLongVerificationKey
+			);
+			let deserialized_vk =
+				zk_verifier::parse_verification_key(&vec_vk).map_err(|e| match e {
+					zk_verifier::ParseError::NotSupportedCurve => Error::<T>::NotSupportedCurve,
+					zk_verifier::ParseError::NotSupportedProtocol =>
+						Error::<T>::NotSupportedProtocol,
+					_ => Error::<T>::MalformedVerificationKey,
+				})?;
+			let public_inputs = deserialized_vk.public_inputs_len as u32;
+			ensure!(
+				deserialized_vk.ic.len() == public_inputs as usize + 1,
+				Error::<T>::PublicInputsMismatch
+			);
+			let curve =
+				Curve::from_name(&deserialized_vk.curve).ok_or(Error::<T>::NotSupportedCurve)?;
+			// only keys with valid points are handed out
+			let vk = zk_verifier::prepare_verification_key(deserialized_vk)
+				.map_err(|_| Error::<T>::VerificationKeyCreationError)?;
+			let key: VerificationKeyDef<T> =
+				vk.to_bytes().try_into().map_err(|_| Error::<T>::TooLongVerificationKey)?;
+
+			let previous = ActiveVersion::<T>::get(circuit_id);
+			let version = match previous {
+				Some(previous) => previous.checked_add(1).ok_or(Error::<T>::StorageOverflow)?,
+				None => 0,
+			};
+
+			let now = frame_system::Pallet::<T>::block_number();
+			UsableVersions::<T>::try_mutate(circuit_id, |versions| {
+				versions.retain(|v| Self::is_usable(circuit_id, *v, now));
+				versions.try_insert(0, version).map_err(|_| Error::<T>::TooManyUsableVersions)
+			})?;
+			if let Some(previous) = previous {
+				// already deprecated by hand, keep its earlier expiry
+				let _ = Self::deprecate(circuit_id, previous, now);
+			}
+
+			VerificationKeys::<T>::insert(
+				circuit_id,
+				version,
+				VerificationKeyInfo { key, curve, public_inputs, tree_depth, expires_at: None },
+			);
+			ActiveVersion::<T>::insert(circuit_id, version);
+
+			Self::deposit_event(Event::<T>::VerificationKeyRegistered {
+				circuit_id,
+				version,
+				curve,
+				public_inputs,
+				tree_depth,
+			});
+			Ok(())
+		}
+
+		/// Stop handing out `version` of `circuit_id` once the grace window has passed.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::WeightInfo::deprecate_verification_key())]
+		pub fn deprecate_verification_key(
+			origin: OriginFor<T>,
+			circuit_id: CircuitId,
+			version: KeyVersion,
+		) -> DispatchResult {
+			T::RegisterOrigin::ensure_origin(origin)?;
+
+			let now = frame_system::Pallet::<T>::block_number();
+			Self::deprecate(circuit_id, version, now)?;
+			UsableVersions::<T>::mutate(circuit_id, |versions| {
+				versions.retain(|v| Self::is_usable(circuit_id, *v, now))
+			});
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		fn deprecate(
+			circuit_id: CircuitId,
+			version: KeyVersion,
+			now: BlockNumberFor<T>,
+		) -> DispatchResult {
+			let expires_at = now.saturating_add(T::DeprecationGracePeriod::get());
+			VerificationKeys::<T>::try_mutate(circuit_id, version, |info| {
+				let info = info.as_mut().ok_or(Error::<T>::VerificationKeyNotFound)?;
+				ensure!(info.expires_at.is_none(), Error::<T>::AlreadyDeprecated);
+				info.expires_at = Some(expires_at);
+				Ok::<_, DispatchError>(())
+			})?;
+
+			Self::deposit_event(Event::<T>::VerificationKeyDeprecated {
+				circuit_id,
+				version,
+				expires_at,
+			});
+			Ok(())
+		}
+
+		fn is_usable(circuit_id: CircuitId, version: KeyVersion, now: BlockNumberFor<T>) -> bool {
+			VerificationKeys::<T>::get(circuit_id, version)
+				.map_or(false, |info| info.expires_at.map_or(true, |expires_at| now < expires_at))
+		}
+	}
+
+	impl<T: Config> VerificationKeyRegistry for Pallet<T> {
+		fn verification_keys(circuit_id: CircuitId) -> Vec<RegisteredVerificationKey> {
+			let now = frame_system::Pallet::<T>::block_number();
+			UsableVersions::<T>::get(circuit_id)
+				.into_iter()
+				.filter_map(|version| {
+					let info = VerificationKeys::<T>::get(circuit_id, version)?;
+					info.expires_at.map_or(true, |expires_at| now < expires_at).then(|| {
+						RegisteredVerificationKey {
+							version,
+							public_inputs: info.public_inputs,
+							tree_depth: info.tree_depth,
+							key: info.key.into_inner(),
+						}
+					})
+				})
+				.collect()
+		}
+
+		fn max_usable_keys() -> u32 {
+			T::MaxUsableVersions::get()
+		}
+
+		#[cfg(feature = "runtime-benchmarks")]
+		fn set_verification_keys(
+			circuit_id: CircuitId,
+			public_inputs: u32,
+			tree_depth: Option<u32>,
+			keys: Vec<Vec<u8>>,
+		) {
+			let next = ActiveVersion::<T>::get(circuit_id).map_or(0, |v| v + 1);
+			let latest = next + keys.len() as KeyVersion - 1;
+			let versions: Vec<_> = (next..=latest).rev().collect();
+			for (version, key) in versions.iter().zip(keys) {
+				let curve = zk_verifier::CurveVerificationKey::from_bytes(&key)
+					.expect("benchmark keys are valid")
+					.curve();
+				VerificationKeys::<T>::insert(
+					circuit_id,
+					version,
+					VerificationKeyInfo {
+						key: key.try_into().expect("benchmark keys fit MaxVerificationKeyLength"),
+						curve,
+						public_inputs,
+						tree_depth,
+						expires_at: None,
+					},
+				);
+			}
+			ActiveVersion::<T>::insert(circuit_id, latest);
+			UsableVersions::<T>::insert(circuit_id, BoundedVec::truncate_from(versions));
+		}
+	}
+}
@@ -0,0 +1,176 @@
+//! Storage migrations for the verification key registry pallet.
+
+use super::*;
+use frame_support::{
+	pallet_prelude::*,
+	traits::{GetStorageVersion, OnRuntimeUpgrade},
+};
+use sp_std::marker::PhantomData;
+
+#[cfg(feature = "try-runtime")]
+use sp_runtime::TryRuntimeError;
+
+pub mod v1 {
+	use super::*;
+	use frame_system::pallet_prelude::BlockNumberFor;
+
+	/// Registered keys before they recorded their tree depth.
+	#[derive(
+		Encode,
+		Decode,
+		CloneNoBound,
+		PartialEqNoBound,
+		EqNoBound,
+		RuntimeDebugNoBound,
+		TypeInfo,
+		MaxEncodedLen,
+	)]
+	#[scale_info(skip_type_params(T))]
+	#[codec(mel_bound())]
+	pub struct VerificationKeyInfo<T: Config> {
+		pub key: VerificationKeyDef<T>,
+		pub curve: Curve,
+		pub public_inputs: u32,
+		pub expires_at: Option<BlockNumberFor<T>>,
+	}
+
+	#[frame_support::storage_alias]
+	pub type VerificationKeys<T: Config> = StorageDoubleMap<
+		Pallet<T>,
+		Twox64Concat,
+		CircuitId,
+		Twox64Concat,
+		KeyVersion,
+		VerificationKeyInfo<T>,
+	>;
+
+	/// Rewrites every stored snarkjs `verification_key.json` into its compact form. Keys that no
+	/// longer prepare are dropped.
+	pub struct MigrateToV1<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+		fn on_runtime_upgrade() -> Weight {
+			let on_chain_version = Pallet::<T>::on_chain_storage_version();
+			if on_chain_version != 0 {
+				log::info!(
+					"pallet_vk_registry: skipping v1 migration, on-chain version {:?}",
+					on_chain_version
+				);
+				return T::DbWeight::get().reads(1)
+			}
+
+			let mut translated = 0u64;
+			VerificationKeys::<T>::translate::<VerificationKeyInfo<T>, _>(
+				|circuit_id, version, mut info| {
+					translated += 1;
+					let key = zk_verifier::parse_verification_key(&info.key)
+						.ok()
+						.and_then(|vk| zk_verifier::prepare_verification_key(vk).ok())
+						.and_then(|vk| vk.to_bytes().try_into().ok());
+					match key {
+						Some(key) => {
+							info.key = key;
+							Some(info)
+						},
+						None => {
+							log::error!(
+								"pallet_vk_registry: dropping key {} of circuit {} in v1 migration",
+								version,
+								circuit_id
+							);
+							None
+						},
+					}
+				},
+			);
+			StorageVersion::new(1).put::<Pallet<T>>();
+
+			log::info!("pallet_vk_registry: translated {} keys in v1 migration", translated);
+
+			T::DbWeight::get().reads_writes(translated + 1, translated + 1)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(_state: Vec<u8>) -> Result<(), TryRuntimeError> {
+			for info in VerificationKeys::<T>::iter_values() {
+				ensure!(
+					zk_verifier::CurveVerificationKey::from_bytes(&info.key).is_ok(),
+					"pallet_vk_registry: key not translated"
+				);
+			}
+			ensure!(
+				Pallet::<T>::on_chain_storage_version() == 1,
+				"pallet_vk_registry: storage version not bumped"
+			);
+			Ok(())
+		}
+	}
+}
+
+pub mod v2 {
+	use super::*;
+
+	/// Records the tree depth of every registered key, `TreeDepths` lists the depth each circuit
+	/// was compiled for. Keys of circuits missing from it are left without one.
+	pub struct MigrateToV2<T, TreeDepths>(PhantomData<(T, TreeDepths)>);
+
+	impl<T: Config, TreeDepths: Get<Vec<(CircuitId, u32)>>> OnRuntimeUpgrade
+		for MigrateToV2<T, TreeDepths>
+	{
+		fn on_runtime_upgrade() -> Weight {
+			let on_chain_version = Pallet::<T>::on_chain_storage_version();
+			if on_chain_version != 1 {
+				log::info!(
+					"pallet_vk_registry: skipping v2 migration, on-chain version {:?}",
+					on_chain_version
+				);
+				return T::DbWeight::get().reads(1)
+			}
+
+			let tree_depths = TreeDepths::get();
+			let mut translated = 0u64;
+			VerificationKeys::<T>::translate::<v1::VerificationKeyInfo<T>, _>(
+				|circuit_id, _version, old| {
+					translated += 1;
+					let tree_depth = tree_depths
+						.iter()
+						.find(|(id, _)| *id == circuit_id)
+						.map(|(_, depth)| *depth);
+					Some(VerificationKeyInfo {
+						key: old.key,
+						curve: old.curve,
+						public_inputs: old.public_inputs,
+						tree_depth,
+						expires_at: old.expires_at,
+					})
+				},
+			);
+			StorageVersion::new(2).put::<Pallet<T>>();
+
+			log::info!("pallet_vk_registry: translated {} keys in v2 migration", translated);
+
+			T::DbWeight::get().reads_writes(translated + 1, translated + 1)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+			Ok((v1::VerificationKeys::<T>::iter_keys().count() as u32).encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+			let keys: u32 = Decode::decode(&mut &state[..])
+				.map_err(|_| "pallet_vk_registry: invalid v2 state")?;
+
+			ensure!(
+				VerificationKeys::<T>::iter_keys().count() as u32 == keys,
+				"pallet_vk_registry: keys not translated"
+			);
+			ensure!(
+				Pallet::<T>::on_chain_storage_version() == 2,
+				"pallet_vk_registry: storage version not bumped"
+			);
+			Ok(())
+		}
+	}
+}
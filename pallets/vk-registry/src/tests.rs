@@ -0,0 +1,283 @@
+use crate::{mock::*, Error, Event, UsableVersions, VerificationKeyInfo, VerificationKeys};
+use frame_support::{assert_noop, assert_ok, BoundedVec};
+use primitives::VerificationKeyRegistry;
+use sp_runtime::DispatchError;
+use zk_verifier::{Curve, CurveVerificationKey};
+
+const CIRCUIT: u32 = 3;
+
+#[test]
+fn register_verification_key() {
+	new_test_ext().execute_with(|| {
+		let vk = vk_json(2);
+		assert_noop!(
+			VkRegistry::register_verification_key(
+				RuntimeOrigin::signed(1),
+				CIRCUIT,
+				vk.clone(),
+				None
+			),
+			DispatchError::BadOrigin
+		);
+
+		assert_ok!(VkRegistry::register_verification_key(
+			RuntimeOrigin::root(),
+			CIRCUIT,
+			vk.clone(),
+			Some(8)
+		));
+		System::assert_last_event(
+			Event::VerificationKeyRegistered {
+				circuit_id: CIRCUIT,
+				version: 0,
+				curve: Curve::Bls12381,
+				public_inputs: 2,
+				tree_depth: Some(8),
+			}
+			.into(),
+		);
+		assert_eq!(VkRegistry::active_version(CIRCUIT), Some(0));
+
+		let keys = VkRegistry::verification_keys(CIRCUIT);
+		assert_eq!(keys.len(), 1);
+		assert_eq!((keys[0].version, keys[0].public_inputs, keys[0].tree_depth), (0, 2, Some(8)));
+		// stored prepared, not as json
+		assert_eq!(keys[0].key, compact(&vk));
+		assert!(CurveVerificationKey::from_bytes(&keys[0].key).is_ok());
+		assert!(VkRegistry::verification_keys(CIRCUIT + 1).is_empty());
+	});
+}
+
+#[test]
+fn register_rejects_invalid_keys() {
+	new_test_ext().execute_with(|| {
+		let vk = String::from_utf8(vk_json(2)).unwrap();
+		let register = |vk: String| {
+			VkRegistry::register_verification_key(
+				RuntimeOrigin::root(),
+				CIRCUIT,
+				vk.into_bytes(),
+				None,
+			)
+		};
+
+		assert_noop!(register("{}".to_owned()), Error::<Test>::MalformedVerificationKey);
+		assert_noop!(register(vk.replace("bls12381", "bn254")), Error::<Test>::NotSupportedCurve);
+		assert_noop!(register(vk.replace("groth16", "plonk")), Error::<Test>::NotSupportedProtocol);
+		assert_noop!(
+			register(vk.replace(r#""nPublic": 2"#, r#""nPublic": 3"#)),
+			Error::<Test>::PublicInputsMismatch
+		);
+		// (1, 1) is not on the curve
+		let alpha = &vk[vk.find("vk_alpha_1").unwrap()..];
+		let alpha = &alpha[alpha.find('[').unwrap()..=alpha.find(']').unwrap()];
+		assert_noop!(
+			register(vk.replace(alpha, r#"["1", "1", "1"]"#)),
+			Error::<Test>::VerificationKeyCreationError
+		);
+		assert_noop!(
+			VkRegistry::register_verification_key(
+				RuntimeOrigin::root(),
+				CIRCUIT,
+				vec![0; 5001],
+				None
+			),
+			Error::<Test>::TooLongVerificationKey
+		);
+	});
+}
+
+#[test]
+fn replaced_version_is_usable_for_grace_period() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(VkRegistry::register_verification_key(
+			RuntimeOrigin::root(),
+			CIRCUIT,
+			vk_json(1),
+			None
+		));
+		assert_ok!(VkRegistry::register_verification_key(
+			RuntimeOrigin::root(),
+			CIRCUIT,
+			vk_json(2),
+			None
+		));
+		System::assert_has_event(
+			Event::VerificationKeyDeprecated { circuit_id: CIRCUIT, version: 0, expires_at: 11 }
+				.into(),
+		);
+		assert_eq!(VkRegistry::active_version(CIRCUIT), Some(1));
+		assert_eq!(versions(), vec![1, 0]);
+
+		// both versions are still usable, a third one does not fit
+		assert_noop!(
+			VkRegistry::register_verification_key(RuntimeOrigin::root(), CIRCUIT, vk_json(3), None),
+			Error::<Test>::TooManyUsableVersions
+		);
+
+		System::set_block_number(10);
+		assert_eq!(versions(), vec![1, 0]);
+		System::set_block_number(11);
+		assert_eq!(versions(), vec![1]);
+
+		assert_ok!(VkRegistry::register_verification_key(
+			RuntimeOrigin::root(),
+			CIRCUIT,
+			vk_json(3),
+			None
+		));
+		assert_eq!(versions(), vec![2, 1]);
+		assert_eq!(UsableVersions::<Test>::get(CIRCUIT).into_inner(), vec![2, 1]);
+		// expired versions are kept
+		assert!(VerificationKeys::<Test>::contains_key(CIRCUIT, 0));
+	});
+}
+
+#[test]
+fn deprecate_verification_key() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(VkRegistry::register_verification_key(
+			RuntimeOrigin::root(),
+			CIRCUIT,
+			vk_json(2),
+			None
+		));
+
+		assert_noop!(
+			VkRegistry::deprecate_verification_key(RuntimeOrigin::signed(1), CIRCUIT, 0),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			VkRegistry::deprecate_verification_key(RuntimeOrigin::root(), CIRCUIT, 1),
+			Error::<Test>::VerificationKeyNotFound
+		);
+
+		// deprecating the active version retires the circuit after the grace period
+		assert_ok!(VkRegistry::deprecate_verification_key(RuntimeOrigin::root(), CIRCUIT, 0));
+		System::assert_last_event(
+			Event::VerificationKeyDeprecated { circuit_id: CIRCUIT, version: 0, expires_at: 11 }
+				.into(),
+		);
+		assert_noop!(
+			VkRegistry::deprecate_verification_key(RuntimeOrigin::root(), CIRCUIT, 0),
+			Error::<Test>::AlreadyDeprecated
+		);
+
+		System::set_block_number(5);
+		assert_eq!(versions(), vec![0]);
+		System::set_block_number(11);
+		assert!(versions().is_empty());
+	});
+}
+
+#[test]
+fn migrate_to_v1_stores_compact_keys() {
+	use crate::migrations::v1::{self, MigrateToV1};
+	use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(0).put::<VkRegistry>();
+		let info = |key: Vec<u8>| v1::VerificationKeyInfo::<Test> {
+			key: BoundedVec::truncate_from(key),
+			curve: Curve::Bls12381,
+			public_inputs: 2,
+			expires_at: None,
+		};
+		v1::VerificationKeys::<Test>::insert(CIRCUIT, 0, info(vk_json(2)));
+		v1::VerificationKeys::<Test>::insert(CIRCUIT, 1, info(b"{}".to_vec()));
+
+		MigrateToV1::<Test>::on_runtime_upgrade();
+
+		assert_eq!(v1::VerificationKeys::<Test>::get(CIRCUIT, 0), Some(info(compact(&vk_json(2)))));
+		assert!(!v1::VerificationKeys::<Test>::contains_key(CIRCUIT, 1));
+		assert_eq!(VkRegistry::on_chain_storage_version(), 1);
+	});
+}
+
+#[test]
+fn migrate_to_v2_records_tree_depths() {
+	use crate::migrations::{v1, v2::MigrateToV2};
+	use frame_support::{
+		parameter_types,
+		traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion},
+	};
+
+	parameter_types! {
+		pub TreeDepths: Vec<(u32, u32)> = vec![(CIRCUIT, 8)];
+	}
+
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(1).put::<VkRegistry>();
+		let key = BoundedVec::truncate_from(compact(&vk_json(2)));
+		let info = v1::VerificationKeyInfo::<Test> {
+			key: key.clone(),
+			curve: Curve::Bls12381,
+			public_inputs: 2,
+			expires_at: Some(11),
+		};
+		v1::VerificationKeys::<Test>::insert(CIRCUIT, 0, info.clone());
+		v1::VerificationKeys::<Test>::insert(CIRCUIT + 1, 0, info);
+
+		MigrateToV2::<Test, TreeDepths>::on_runtime_upgrade();
+
+		let info = |tree_depth| VerificationKeyInfo::<Test> {
+			key: key.clone(),
+			curve: Curve::Bls12381,
+			public_inputs: 2,
+			tree_depth,
+			expires_at: Some(11),
+		};
+		assert_eq!(VerificationKeys::<Test>::get(CIRCUIT, 0), Some(info(Some(8))));
+		assert_eq!(VerificationKeys::<Test>::get(CIRCUIT + 1, 0), Some(info(None)));
+		assert_eq!(VkRegistry::on_chain_storage_version(), 2);
+	});
+}
+
+fn compact(vk_json: &[u8]) -> Vec<u8> {
+	let vk = zk_verifier::parse_verification_key(vk_json).unwrap();
+	zk_verifier::prepare_verification_key(vk).unwrap().to_bytes()
+}
+
+fn versions() -> Vec<u32> {
+	VkRegistry::verification_keys(CIRCUIT)
+		.into_iter()
+		.map(|key| key.version)
+		.collect()
+}
+
+// Groth16 key on bls12381 with `n_public` public inputs, its points are multiples of the
+// generators
+fn vk_json(n_public: u64) -> Vec<u8> {
+	use bls12_381::{G1Affine, G2Affine, Scalar};
+
+	fn g1(s: u64) -> String {
+		let bytes = G1Affine::from(G1Affine::generator() * Scalar::from(s)).to_uncompressed();
+		format!(r#"["{}", "{}", "1"]"#, dec(&bytes[..48]), dec(&bytes[48..]))
+	}
+	fn g2(s: u64) -> String {
+		// uncompressed G2 is x_c1 | x_c0 | y_c1 | y_c0, snarkjs lists c0 first
+		let bytes = G2Affine::from(G2Affine::generator() * Scalar::from(s)).to_uncompressed();
+		format!(
+			r#"[["{}", "{}"], ["{}", "{}"], ["1", "0"]]"#,
+			dec(&bytes[48..96]),
+			dec(&bytes[..48]),
+			dec(&bytes[144..]),
+			dec(&bytes[96..144])
+		)
+	}
+	fn dec(bytes: &[u8]) -> String {
+		zk_verifier::deserialization::U256::from_big_endian(bytes).to_string()
+	}
+
+	let ic: Vec<String> = (0..=n_public).map(|i| g1(11 + i)).collect();
+	format!(
+		r#"{{"protocol": "groth16", "curve": "bls12381", "nPublic": {}, "vk_alpha_1": {}, "vk_beta_2": {}, "vk_gamma_2": {}, "vk_delta_2": {}, "IC": [{}]}}"#,
+		n_public,
+		g1(2),
+		g2(3),
+		g2(5),
+		g2(7),
+		ic.join(", ")
+	)
+	.into_bytes()
+}
@@ -0,0 +1,61 @@
+//! Weights for pallet_vk_registry
+//!
+//! Estimated from the storage accessed by each call and the pairing computed for every registered
+//! key until they are generated on reference hardware with `scripts/benchmark.sh`, which
+//! overwrites this file.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for pallet_vk_registry.
+pub trait WeightInfo {
+	fn register_verification_key(l: u32, ) -> Weight;
+	fn deprecate_verification_key() -> Weight;
+}
+
+/// Weights for pallet_vk_registry using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: VkRegistry ActiveVersion (r:1 w:1)
+	/// Storage: VkRegistry UsableVersions (r:1 w:1)
+	/// Storage: VkRegistry VerificationKeys (r:4 w:2)
+	/// The range of component `l` is `[0, 5000]`.
+	fn register_verification_key(l: u32, ) -> Weight {
+		Weight::from_parts(1_200_000_000, 20_611)
+			.saturating_add(Weight::from_parts(500_000, 0).saturating_mul(l.into()))
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+	/// Storage: VkRegistry VerificationKeys (r:5 w:1)
+	/// Storage: VkRegistry UsableVersions (r:1 w:1)
+	fn deprecate_verification_key() -> Weight {
+		Weight::from_parts(40_000_000, 25_746)
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	/// Storage: VkRegistry ActiveVersion (r:1 w:1)
+	/// Storage: VkRegistry UsableVersions (r:1 w:1)
+	/// Storage: VkRegistry VerificationKeys (r:4 w:2)
+	/// The range of component `l` is `[0, 5000]`.
+	fn register_verification_key(l: u32, ) -> Weight {
+		Weight::from_parts(1_200_000_000, 20_611)
+			.saturating_add(Weight::from_parts(500_000, 0).saturating_mul(l.into()))
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+	/// Storage: VkRegistry VerificationKeys (r:5 w:1)
+	/// Storage: VkRegistry UsableVersions (r:1 w:1)
+	fn deprecate_verification_key() -> Weight {
+		Weight::from_parts(40_000_000, 25_746)
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+}
@@ -1,7 +1,9 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use sp_runtime::DispatchResult;
+use frame_support::weights::Weight;
+use sp_runtime::{DispatchResult, RuntimeDebug};
 use sp_std::vec::Vec;
+use zk_verifier::EncodedProof;
 pub mod currency;
 
 /// Signed version of Balance
@@ -9,17 +11,22 @@ pub type Amount = i128;
 
 pub type Balance = u128;
 
-pub trait Swap<Balance, AccountId> {
+pub trait Swap<CurrencyId, Balance, AccountId> {
+	fn get_target_currency_id(order_id: u32) -> Option<CurrencyId>;
+
 	fn get_target_amount(order_id: u32) -> Balance;
 
 	fn inter_take_order(taker: AccountId, order_id: u32, receiver: AccountId) -> DispatchResult;
+
+	/// Weight of `inter_take_order`
+	fn take_order_weight() -> Weight;
 }
 
 pub trait Otp<AccountId> {
 	//Only checks that time in the proof is larger than lastUsedTime, i.e. behaves like HOTP
 	fn naive_approval(
 		owner: AccountId,
-		proof: Vec<u8>,
+		proof: EncodedProof,
 		root: Vec<u8>,
 		timestamp: u128,
 	) -> DispatchResult;
@@ -27,8 +34,48 @@ pub trait Otp<AccountId> {
 	//Uses block timestamp to validate time, TOTP
 	fn block_time_approval(
 		owner: AccountId,
-		proof: Vec<u8>,
+		proof: EncodedProof,
 		root: Vec<u8>,
 		timestamp: u128,
 	) -> DispatchResult;
+
+	/// Weight of the costlier of `naive_approval` and `block_time_approval`
+	fn approval_weight() -> Weight;
+}
+
+/// Identifies a circuit in the verification key registry.
+pub type CircuitId = u32;
+
+/// Version of a circuit's verification key, counting up from 0.
+pub type KeyVersion = u32;
+
+/// Verification key handed out by a `VerificationKeyRegistry`.
+#[derive(Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct RegisteredVerificationKey {
+	pub version: KeyVersion,
+	/// Number of public inputs the circuit takes
+	pub public_inputs: u32,
+	/// Depth of the Merkle tree the circuit proves membership in, as declared at registration
+	pub tree_depth: Option<u32>,
+	/// Compact form of the prepared key, read with `CurveVerificationKey::from_bytes`
+	pub key: Vec<u8>,
+}
+
+pub trait VerificationKeyRegistry {
+	/// Keys proofs for `circuit_id` may be checked against, the latest registered version first,
+	/// followed by deprecated versions still inside their grace window.
+	fn verification_keys(circuit_id: CircuitId) -> Vec<RegisteredVerificationKey>;
+
+	/// The most keys `verification_keys` hands out for a circuit, a proof may be checked against
+	/// each of them.
+	fn max_usable_keys() -> u32;
+
+	/// Make `keys`, in compact form and the latest version first, the usable keys of `circuit_id`.
+	#[cfg(feature = "runtime-benchmarks")]
+	fn set_verification_keys(
+		circuit_id: CircuitId,
+		public_inputs: u32,
+		tree_depth: Option<u32>,
+		keys: Vec<Vec<u8>>,
+	);
 }
@@ -0,0 +1,68 @@
+//! The container of the iden3 binary files, `.r1cs` and `.zkey` alike: a magic, a version and
+//! sections of little-endian data, each with its id and size.
+
+use std::{
+	collections::HashMap,
+	io::{self, Read, Seek, SeekFrom},
+};
+
+pub(crate) struct BinFile<'a, R> {
+	reader: &'a mut R,
+	/// Where each section starts, and its size.
+	sections: HashMap<u32, (u64, u64)>,
+}
+
+impl<'a, R: Read + Seek> BinFile<'a, R> {
+	/// The sections of a file starting with `magic`, `wrong_file` is the error of any other.
+	pub(crate) fn open(
+		reader: &'a mut R,
+		magic: &[u8; 4],
+		wrong_file: &'static str,
+	) -> io::Result<Self> {
+		let mut file = BinFile { reader, sections: HashMap::new() };
+		if file.bytes(4)? != magic {
+			return Err(invalid(wrong_file))
+		}
+		let _version = file.u32()?;
+
+		for _ in 0..file.u32()? {
+			let id = file.u32()?;
+			let size = file.u64()?;
+			let position = file.reader.stream_position()?;
+			// a section given twice is read from its first copy
+			file.sections.entry(id).or_insert((position, size));
+			file.reader.seek(SeekFrom::Current(size as i64))?;
+		}
+		Ok(file)
+	}
+
+	/// Move to the start of `section`, returning its size.
+	pub(crate) fn seek(&mut self, section: u32) -> io::Result<u64> {
+		let (position, size) =
+			*self.sections.get(&section).ok_or_else(|| invalid("missing section"))?;
+		self.reader.seek(SeekFrom::Start(position))?;
+		Ok(size)
+	}
+
+	pub(crate) fn bytes(&mut self, len: usize) -> io::Result<Vec<u8>> {
+		let mut bytes = vec![0u8; len];
+		self.reader.read_exact(&mut bytes)?;
+		Ok(bytes)
+	}
+
+	pub(crate) fn u32(&mut self) -> io::Result<u32> {
+		let mut bytes = [0u8; 4];
+		self.reader.read_exact(&mut bytes)?;
+		Ok(u32::from_le_bytes(bytes))
+	}
+
+	pub(crate) fn u64(&mut self) -> io::Result<u64> {
+		let mut bytes = [0u8; 8];
+		self.reader.read_exact(&mut bytes)?;
+		Ok(u64::from_le_bytes(bytes))
+	}
+}
+
+pub(crate) fn invalid(reason: &'static str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, reason)
+}
@@ -0,0 +1,451 @@
+//! Groth16 proofs of the circom circuits, made in Rust for tests and tooling. Never a dependency of
+//! the runtime.
+//!
+//! A [`Prover`] loads the `.r1cs` and `.wasm` of a [`Circuit`] from `circom/<name>/build`, runs the
+//! wasm in wasmi for the witness of named input signals and proves it on BLS12-381, the curve every
+//! circuit is compiled for. The proof and the verification key come out as snarkjs json, the form
+//! the pallets and the vk registry take.
+//!
+//! The proving key is read from the circuit's `.zkey`, so proofs verify against its committed
+//! `verification_key.json`. A circuit without a zkey gets the key of a setup seeded by its r1cs.
+//! Anyone can recompute that trapdoor, which is fine for tests and nothing else. Either way the key
+//! is cached under [`cache_dir`], named after a hash of the artifacts it was made from. Later runs
+//! load it without parsing or setup, and nothing is ever fetched.
+//!
+//! Proofs are reduced with [`SnarkjsReduction`], the QAP of snarkjs on its own roots of unity.
+//! Randomness comes from the caller, tests seed it and prove the same on every run.
+//!
+//! This is not ark-circom, which does the same for BN254. Its release on arkworks 0.4, 0.1.0, reads
+//! zkeys into `ProvingKey<Bn254>` only and its witness memory hardcodes the BN254 modulus and
+//! Montgomery constant, while every circuit here is on BLS12-381. It also pins `ark-ff`, `ark-ec`
+//! and `ark-serialize` to `=0.4.1`, which can not share a lockfile with the 0.4.2 the verifier and
+//! the pallets build with, and runs witnesses in wasmer with a native compiler. The parts needed
+//! here, a witness calculator over the field of the wasm, the zkey sections and the snarkjs QAP,
+//! fit in `witness`, `zkey` and `qap`.
+
+mod binfile;
+mod qap;
+mod r1cs;
+mod witness;
+mod zkey;
+
+pub use qap::SnarkjsReduction;
+pub use r1cs::{R1cs, R1csCircuit};
+pub use zkey::read_zkey;
+
+use core::fmt;
+use std::{
+	fs,
+	io::{self, BufReader, BufWriter},
+	path::{Path, PathBuf},
+	sync::atomic::{AtomicUsize, Ordering},
+};
+
+use ark_bls12_381::{Bls12_381, Fr, G1Affine, G2Affine};
+use ark_ff::{BigInteger, PrimeField};
+use ark_groth16::{prepare_verifying_key, Groth16, ProvingKey, VerifyingKey};
+use ark_relations::r1cs::SynthesisError;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use ark_std::rand::{rngs::StdRng, CryptoRng, RngCore, SeedableRng};
+use sp_core::U256;
+use tiny_keccak::{Hasher, Sha3};
+use witness::WitnessCalculator;
+
+/// Environment variable to cache proving keys somewhere else than `target/zk-prover`.
+pub const CACHE_DIR_VAR: &str = "ZK_PROVER_CACHE";
+
+/// The circuits under `circom/`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Circuit {
+	/// `Withdraw(8)` over MiMC7.
+	Mixer,
+	/// `Withdraw(8)` over Poseidon255.
+	MixerPoseidon,
+	/// `OtpMerkleTreeInclusionProof(7)`.
+	Otp,
+}
+
+impl Circuit {
+	/// Directory of the circuit under `circom/`.
+	pub fn name(&self) -> &'static str {
+		match self {
+			Circuit::Mixer => "mixer",
+			Circuit::MixerPoseidon => "mixer-psd",
+			Circuit::Otp => "otp",
+		}
+	}
+
+	// file name of the compiled circuit
+	fn main(&self) -> &'static str {
+		match self {
+			Circuit::Mixer | Circuit::MixerPoseidon => "mixer",
+			Circuit::Otp => "otp",
+		}
+	}
+
+	pub fn dir(&self) -> PathBuf {
+		workspace_root().join("circom").join(self.name())
+	}
+
+	pub fn r1cs(&self) -> PathBuf {
+		self.dir().join("build").join(format!("{}.r1cs", self.main()))
+	}
+
+	pub fn wasm(&self) -> PathBuf {
+		self.dir()
+			.join("build")
+			.join(format!("{}_js/{}.wasm", self.main(), self.main()))
+	}
+
+	/// The last contribution to the circuit's setup, if it had one.
+	pub fn zkey(&self) -> Option<PathBuf> {
+		let zkey = self.dir().join(format!("{}_0001.zkey", self.main()));
+		zkey.exists().then_some(zkey)
+	}
+}
+
+/// Why a circuit could not be loaded or proved.
+#[derive(Debug)]
+pub enum Error {
+	/// An artifact or the cache could not be read or written.
+	Io(io::Error),
+	/// A proving key could not be written to the cache.
+	Serialization(SerializationError),
+	/// The wasm could not be loaded, or it rejected the inputs: a missing signal or a failed
+	/// assertion, like a root the path does not lead to.
+	Circom(String),
+	/// The witness does not satisfy the constraints or does not fit the key.
+	Synthesis(SynthesisError),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Error::Io(e) => write!(f, "io error: {}", e),
+			Error::Serialization(e) => write!(f, "serialization error: {}", e),
+			Error::Circom(e) => write!(f, "circom error: {}", e),
+			Error::Synthesis(e) => write!(f, "synthesis error: {}", e),
+		}
+	}
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+	fn from(e: io::Error) -> Self {
+		Error::Io(e)
+	}
+}
+
+impl From<SerializationError> for Error {
+	fn from(e: SerializationError) -> Self {
+		Error::Serialization(e)
+	}
+}
+
+impl From<SynthesisError> for Error {
+	fn from(e: SynthesisError) -> Self {
+		Error::Synthesis(e)
+	}
+}
+
+/// A proof as the pallets take it.
+#[derive(Clone, Debug)]
+pub struct Proof {
+	/// The snarkjs json of the proof, what `EncodedProof::Json` holds.
+	pub json: String,
+	/// The public signals of the witness: outputs first, then public inputs, in the order of the
+	/// circuit.
+	pub public_inputs: Vec<U256>,
+	proof: ark_groth16::Proof<Bls12_381>,
+}
+
+/// Proves one circuit. Loading compiles its wasm and reads or makes its proving key, keep the
+/// prover around for more than one proof.
+pub struct Prover {
+	circuit: Circuit,
+	r1cs: R1cs,
+	witness: WitnessCalculator,
+	key: ProvingKey<Bls12_381>,
+}
+
+impl Prover {
+	/// The prover of `circuit`, its key cached in [`cache_dir`].
+	pub fn load(circuit: Circuit) -> Result<Self, Error> {
+		Prover::load_cached(circuit, &cache_dir())
+	}
+
+	/// The prover of `circuit`, its key cached in `cache_dir`.
+	pub fn load_cached(circuit: Circuit, cache_dir: &Path) -> Result<Self, Error> {
+		let witness = WitnessCalculator::new(&fs::read(circuit.wasm())?)?;
+
+		let r1cs_file = fs::read(circuit.r1cs())?;
+		let r1cs = R1cs::read(&mut io::Cursor::new(&r1cs_file))?;
+		let zkey = circuit.zkey().map(fs::read).transpose()?;
+		let mut hash = [0u8; 32];
+		let mut sha3 = Sha3::v256();
+		sha3.update(&r1cs_file);
+		sha3.update(zkey.as_deref().unwrap_or_default());
+		sha3.finalize(&mut hash);
+
+		let cached = cache_dir.join(format!("{}-{}.pk", circuit.name(), hex(&hash[..8])));
+		// anything unreadable is made again
+		let key = match read_key(&cached) {
+			Ok(key) => key,
+			Err(_) => {
+				let key = match zkey {
+					Some(zkey) => read_zkey(&mut io::Cursor::new(zkey))?,
+					None => setup(&r1cs, hash)?,
+				};
+				write_key(&cached, &key)?;
+				key
+			},
+		};
+
+		Ok(Prover { circuit, r1cs, witness, key })
+	}
+
+	pub fn circuit(&self) -> Circuit {
+		self.circuit
+	}
+
+	/// Number of public signals, what the verification key is registered for.
+	pub fn public_inputs(&self) -> usize {
+		self.key.vk.gamma_abc_g1.len() - 1
+	}
+
+	/// The snarkjs `verification_key.json` to register for the circuit.
+	pub fn verification_key_json(&self) -> String {
+		vk_json(&self.key.vk)
+	}
+
+	/// Prove the input signals by name, blinded with `rng`. Every signal is a list of values, like
+	/// in snarkjs' `input.json`, a single one for a scalar signal.
+	pub fn prove<R: RngCore + CryptoRng>(
+		&self,
+		signals: &[(&str, Vec<U256>)],
+		rng: &mut R,
+	) -> Result<Proof, Error> {
+		let witness = self.witness.calculate(signals)?;
+		let public_inputs = witness[1..self.r1cs.instance].iter().map(from_field).collect();
+
+		let circuit = R1csCircuit { r1cs: &self.r1cs, witness: Some(witness) };
+		let proof = Groth16::<Bls12_381, SnarkjsReduction>::create_random_proof_with_reduction(
+			circuit, &self.key, rng,
+		)?;
+
+		Ok(Proof { json: proof_json(&proof), public_inputs, proof })
+	}
+
+	/// Check `proof` against the key it was made with, before handing it to a pallet.
+	pub fn verify(&self, proof: &Proof) -> Result<bool, Error> {
+		let inputs = proof.public_inputs.iter().map(to_field).collect::<Vec<_>>();
+		let vk = prepare_verifying_key(&self.key.vk);
+		Ok(Groth16::<Bls12_381>::verify_proof(&vk, &proof.proof, &inputs)?)
+	}
+}
+
+/// `$ZK_PROVER_CACHE`, or `target/zk-prover` of the workspace.
+pub fn cache_dir() -> PathBuf {
+	std::env::var_os(CACHE_DIR_VAR)
+		.map(PathBuf::from)
+		.unwrap_or_else(|| workspace_root().join("target").join("zk-prover"))
+}
+
+fn workspace_root() -> PathBuf {
+	Path::new(env!("CARGO_MANIFEST_DIR")).join("../..")
+}
+
+// a key of a setup seeded by the artifacts, the same on every machine and so is its trapdoor
+fn setup(r1cs: &R1cs, seed: [u8; 32]) -> Result<ProvingKey<Bls12_381>, Error> {
+	let circuit = R1csCircuit { r1cs, witness: None };
+	Ok(Groth16::<Bls12_381, SnarkjsReduction>::generate_random_parameters_with_reduction(
+		circuit,
+		&mut StdRng::from_seed(seed),
+	)?)
+}
+
+fn read_key(path: &Path) -> Result<ProvingKey<Bls12_381>, Error> {
+	let file = BufReader::new(fs::File::open(path)?);
+	// checked when it was made, the cache holds nothing else
+	Ok(ProvingKey::deserialize_uncompressed_unchecked(file)?)
+}
+
+fn write_key(path: &Path, key: &ProvingKey<Bls12_381>) -> Result<(), Error> {
+	static WRITES: AtomicUsize = AtomicUsize::new(0);
+
+	if let Some(dir) = path.parent() {
+		fs::create_dir_all(dir)?;
+	}
+	// written aside and moved in place, so a test loading the same circuit never reads half a key
+	let partial = path.with_extension(format!(
+		"{}-{}",
+		std::process::id(),
+		WRITES.fetch_add(1, Ordering::Relaxed)
+	));
+	key.serialize_uncompressed(BufWriter::new(fs::File::create(&partial)?))?;
+	fs::rename(&partial, path)?;
+	Ok(())
+}
+
+pub(crate) fn circom_error(e: impl fmt::Display) -> Error {
+	Error::Circom(e.to_string())
+}
+
+fn hex(bytes: &[u8]) -> String {
+	bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub(crate) fn to_field(value: &U256) -> Fr {
+	let mut bytes = [0u8; 32];
+	value.to_little_endian(&mut bytes);
+	Fr::from_le_bytes_mod_order(&bytes)
+}
+
+fn from_field(value: &Fr) -> U256 {
+	U256::from_little_endian(&value.into_bigint().to_bytes_le())
+}
+
+pub(crate) fn vk_json(vk: &VerifyingKey<Bls12_381>) -> String {
+	format!(
+		r#"{{"protocol": "groth16", "curve": "bls12381", "nPublic": {}, "vk_alpha_1": {}, "vk_beta_2": {}, "vk_gamma_2": {}, "vk_delta_2": {}, "IC": [{}]}}"#,
+		vk.gamma_abc_g1.len() - 1,
+		g1_json(&vk.alpha_g1),
+		g2_json(&vk.beta_g2),
+		g2_json(&vk.gamma_g2),
+		g2_json(&vk.delta_g2),
+		vk.gamma_abc_g1.iter().map(g1_json).collect::<Vec<_>>().join(", ")
+	)
+}
+
+fn proof_json(proof: &ark_groth16::Proof<Bls12_381>) -> String {
+	format!(
+		r#"{{"pi_a": {}, "pi_b": {}, "pi_c": {}, "protocol": "groth16", "curve": "bls12381"}}"#,
+		g1_json(&proof.a),
+		g2_json(&proof.b),
+		g1_json(&proof.c)
+	)
+}
+
+fn g1_json(p: &G1Affine) -> String {
+	format!(r#"["{}", "{}", "1"]"#, p.x.into_bigint(), p.y.into_bigint())
+}
+
+fn g2_json(p: &G2Affine) -> String {
+	format!(
+		r#"[["{}", "{}"], ["{}", "{}"], ["1", "0"]]"#,
+		p.x.c0.into_bigint(),
+		p.x.c1.into_bigint(),
+		p.y.c0.into_bigint(),
+		p.y.c1.into_bigint()
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn rng() -> StdRng {
+		StdRng::seed_from_u64(0)
+	}
+
+	// the signals of a snarkjs `input.json`, numbers or decimal strings
+	fn input_json(circuit: Circuit) -> Vec<(String, Vec<U256>)> {
+		let input: serde_json::Map<String, serde_json::Value> =
+			serde_json::from_reader(fs::File::open(circuit.dir().join("input.json")).unwrap())
+				.unwrap();
+		let value = |value: &serde_json::Value| match value {
+			serde_json::Value::String(value) => U256::from_dec_str(value).unwrap(),
+			value => U256::from(value.as_u64().unwrap()),
+		};
+		input
+			.iter()
+			.map(|(name, values)| match values {
+				serde_json::Value::Array(values) =>
+					(name.clone(), values.iter().map(value).collect()),
+				single => (name.clone(), vec![value(single)]),
+			})
+			.collect()
+	}
+
+	// prove the circuit's `input.json`, the public inputs are its `public.json`
+	fn assert_proof_matches_public_json(circuit: Circuit, public_inputs: usize) {
+		let prover = Prover::load(circuit).unwrap();
+		assert_eq!(
+			prover.public_inputs(),
+			public_inputs,
+			"circom/{} is stale, rebuild it with `just`",
+			circuit.name()
+		);
+
+		let input = input_json(circuit);
+		let signals = input
+			.iter()
+			.map(|(name, values)| (name.as_str(), values.clone()))
+			.collect::<Vec<_>>();
+		let proof = prover.prove(&signals, &mut rng()).unwrap();
+
+		let public: Vec<String> =
+			serde_json::from_reader(fs::File::open(circuit.dir().join("public.json")).unwrap())
+				.unwrap();
+		assert_eq!(
+			proof.public_inputs,
+			public.iter().map(|p| U256::from_dec_str(p).unwrap()).collect::<Vec<_>>()
+		);
+		assert!(prover.verify(&proof).unwrap());
+
+		let mut forged = proof.clone();
+		forged.public_inputs[1] += U256::one();
+		assert!(!prover.verify(&forged).unwrap());
+	}
+
+	#[test]
+	fn test_otp_proof_matches_public_json() {
+		assert_proof_matches_public_json(Circuit::Otp, 2);
+	}
+
+	#[test]
+	#[ignore = "circom/mixer/build is the two input circuit, rebuild it with `just`"]
+	fn test_mixer_proof_matches_public_json() {
+		assert_proof_matches_public_json(Circuit::Mixer, 7);
+	}
+
+	#[test]
+	#[ignore = "circom/mixer-psd/build is the two input circuit, rebuild it with `just`"]
+	fn test_mixer_poseidon_proof_matches_public_json() {
+		assert_proof_matches_public_json(Circuit::MixerPoseidon, 7);
+	}
+
+	#[test]
+	fn test_witness_rejects_failed_assertions() {
+		let prover = Prover::load(Circuit::Otp).unwrap();
+
+		// path indices are bits
+		let mut input = input_json(Circuit::Otp);
+		let (_, path_index) = input.iter_mut().find(|(name, _)| name == "path_index").unwrap();
+		path_index[0] = U256::from(2);
+		let signals = input
+			.iter()
+			.map(|(name, values)| (name.as_str(), values.clone()))
+			.collect::<Vec<_>>();
+
+		assert!(matches!(prover.prove(&signals, &mut rng()), Err(Error::Circom(_))));
+	}
+
+	#[test]
+	fn test_key_is_cached() {
+		let cache = std::env::temp_dir().join(format!("zk-prover-{}", std::process::id()));
+		let prover = Prover::load_cached(Circuit::Otp, &cache).unwrap();
+
+		let cached = fs::read_dir(&cache)
+			.unwrap()
+			.map(|entry| entry.unwrap().path())
+			.collect::<Vec<_>>();
+		assert_eq!(cached.len(), 1);
+		assert!(cached[0].file_name().unwrap().to_str().unwrap().starts_with("otp-"));
+
+		let reloaded = Prover::load_cached(Circuit::Otp, &cache).unwrap();
+		assert_eq!(reloaded.verification_key_json(), prover.verification_key_json());
+		fs::remove_dir_all(cache).unwrap();
+	}
+}
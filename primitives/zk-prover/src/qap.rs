@@ -0,0 +1,120 @@
+//! The R1CS to QAP reduction of snarkjs.
+//!
+//! ark-circom's `CircomReduction` computes H like snarkjs, as the odd coefficients of `AB - C` in a
+//! domain twice as large. It takes the roots of unity of arkworks though, powers of the field's
+//! multiplicative generator. snarkjs takes powers of the smallest quadratic non-residue. Both are 5
+//! on BN254, but BLS12-381 has 7 as generator, and a zkey made by snarkjs only accepts proofs over
+//! its own domain. The reduction here is `CircomReduction` on the domain of snarkjs.
+
+use ark_ff::PrimeField;
+use ark_groth16::r1cs_to_qap::{evaluate_constraint, R1CSToQAP};
+use ark_poly::{EvaluationDomain, Radix2EvaluationDomain};
+use ark_relations::r1cs::{ConstraintMatrices, ConstraintSystemRef, SynthesisError};
+
+/// The smallest quadratic non-residue of the BLS12-381 scalar field, the root snarkjs builds its
+/// domains from.
+const NON_RESIDUE: u64 = 5;
+
+/// ark-circom's `CircomReduction` over the roots of unity of snarkjs.
+pub struct SnarkjsReduction;
+
+impl R1CSToQAP for SnarkjsReduction {
+	#[allow(clippy::type_complexity)]
+	fn instance_map_with_evaluation<F: PrimeField, D: EvaluationDomain<F>>(
+		cs: ConstraintSystemRef<F>,
+		t: &F,
+	) -> Result<(Vec<F>, Vec<F>, Vec<F>, F, usize, usize), SynthesisError> {
+		let matrices = cs.to_matrices().ok_or(SynthesisError::MissingCS)?;
+		let num_constraints = cs.num_constraints();
+		let num_inputs = cs.num_instance_variables();
+		let domain = snarkjs_domain::<F>(num_constraints + num_inputs)?;
+
+		let zt = domain.evaluate_vanishing_polynomial(*t);
+		let u = domain.evaluate_all_lagrange_coefficients(*t);
+
+		let qap_num_variables = (num_inputs - 1) + cs.num_witness_variables();
+		let mut a = vec![F::zero(); qap_num_variables + 1];
+		let mut b = vec![F::zero(); qap_num_variables + 1];
+		let mut c = vec![F::zero(); qap_num_variables + 1];
+
+		a[..num_inputs].copy_from_slice(&u[num_constraints..num_constraints + num_inputs]);
+		for (i, u_i) in u.iter().enumerate().take(num_constraints) {
+			for (coeff, index) in &matrices.a[i] {
+				a[*index] += *u_i * coeff;
+			}
+			for (coeff, index) in &matrices.b[i] {
+				b[*index] += *u_i * coeff;
+			}
+			for (coeff, index) in &matrices.c[i] {
+				c[*index] += *u_i * coeff;
+			}
+		}
+
+		Ok((a, b, c, zt, qap_num_variables, domain.size()))
+	}
+
+	fn witness_map_from_matrices<F: PrimeField, D: EvaluationDomain<F>>(
+		matrices: &ConstraintMatrices<F>,
+		num_inputs: usize,
+		num_constraints: usize,
+		full_assignment: &[F],
+	) -> Result<Vec<F>, SynthesisError> {
+		let domain = snarkjs_domain::<F>(num_constraints + num_inputs)?;
+		let domain_size = domain.size();
+
+		let mut a = vec![F::zero(); domain_size];
+		let mut b = vec![F::zero(); domain_size];
+		let mut c = vec![F::zero(); domain_size];
+		for i in 0..num_constraints {
+			a[i] = evaluate_constraint(&matrices.a[i], full_assignment);
+			b[i] = evaluate_constraint(&matrices.b[i], full_assignment);
+			c[i] = a[i] * b[i];
+		}
+		a[num_constraints..num_constraints + num_inputs]
+			.copy_from_slice(&full_assignment[..num_inputs]);
+
+		// evaluate on the odd powers of the domain twice as large
+		let root = snarkjs_domain::<F>(2 * domain_size)?.element(1);
+		for evaluations in [&mut a, &mut b, &mut c] {
+			domain.ifft_in_place(evaluations);
+			Radix2EvaluationDomain::distribute_powers_and_mul_by_const(evaluations, root, F::one());
+			domain.fft_in_place(evaluations);
+		}
+
+		let mut ab = domain.mul_polynomials_in_evaluation_domain(&a, &b);
+		for (ab, c) in ab.iter_mut().zip(c) {
+			*ab -= c;
+		}
+		Ok(ab)
+	}
+
+	fn h_query_scalars<F: PrimeField, D: EvaluationDomain<F>>(
+		max_power: usize,
+		t: F,
+		_: F,
+		delta_inverse: F,
+	) -> Result<Vec<F>, SynthesisError> {
+		// H has `domain - 1` powers and Z has `domain`, so HZ has `2 * domain - 1`
+		let mut scalars = (0..2 * max_power + 1)
+			.map(|i| delta_inverse * t.pow([i as u64]))
+			.collect::<Vec<_>>();
+		snarkjs_domain::<F>(scalars.len())?.ifft_in_place(&mut scalars);
+		Ok(scalars.into_iter().skip(1).step_by(2).collect())
+	}
+}
+
+/// The radix 2 domain of at least `size` elements, generated like in snarkjs.
+fn snarkjs_domain<F: PrimeField>(size: usize) -> Result<Radix2EvaluationDomain<F>, SynthesisError> {
+	let mut domain =
+		Radix2EvaluationDomain::<F>::new(size).ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+
+	// the non-residue to the odd part of `r - 1` generates the 2-adic subgroup
+	let mut root = F::from(NON_RESIDUE).pow(F::TRACE);
+	for _ in domain.log_size_of_group..F::TWO_ADICITY {
+		root.square_in_place();
+	}
+
+	domain.group_gen = root;
+	domain.group_gen_inv = root.inverse().expect("a root of unity is not zero");
+	Ok(domain)
+}
@@ -0,0 +1,108 @@
+//! Constraint systems of circom `.r1cs` files.
+
+use std::io::{self, Read, Seek};
+
+use ark_bls12_381::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use ark_relations::r1cs::{
+	ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable,
+};
+
+use crate::binfile::{invalid, BinFile};
+
+const MAGIC: &[u8; 4] = b"r1cs";
+
+// section ids of the r1cs
+const HEADER: u32 = 1;
+const CONSTRAINTS: u32 = 2;
+
+/// `(wire, coefficient)` terms of a linear combination.
+pub type Terms = Vec<(usize, Fr)>;
+
+/// The constraints `A * B = C` of a circuit over its wires: the constant `1`, the public outputs,
+/// the public inputs and everything private, in the order of the witness circom computes.
+#[derive(Clone, Debug)]
+pub struct R1cs {
+	pub wires: usize,
+	/// Wires of the instance, `1` included.
+	pub instance: usize,
+	pub constraints: Vec<(Terms, Terms, Terms)>,
+}
+
+impl R1cs {
+	/// Read the constraints of a BLS12-381 `.r1cs`.
+	pub fn read<R: Read + Seek>(reader: &mut R) -> io::Result<Self> {
+		let mut r1cs = BinFile::open(reader, MAGIC, "not an r1cs file")?;
+
+		r1cs.seek(HEADER)?;
+		let (n8, prime) = (r1cs.u32()?, r1cs.bytes(32)?);
+		if n8 != 32 || prime != Fr::MODULUS.to_bytes_le() {
+			return Err(invalid("not a BLS12-381 circuit"))
+		}
+		let wires = r1cs.u32()? as usize;
+		let public_outputs = r1cs.u32()? as usize;
+		let public_inputs = r1cs.u32()? as usize;
+		let _private_inputs = r1cs.u32()?;
+		let _labels = r1cs.u64()?;
+		let constraints = r1cs.u32()?;
+
+		r1cs.seek(CONSTRAINTS)?;
+		let mut terms = || -> io::Result<Terms> {
+			(0..r1cs.u32()?)
+				.map(|_| {
+					let wire = r1cs.u32()? as usize;
+					let coefficient = Fr::from_le_bytes_mod_order(&r1cs.bytes(32)?);
+					if wire >= wires {
+						return Err(invalid("constraint on a missing wire"))
+					}
+					Ok((wire, coefficient))
+				})
+				.collect()
+		};
+		let constraints = (0..constraints)
+			.map(|_| Ok((terms()?, terms()?, terms()?)))
+			.collect::<io::Result<_>>()?;
+
+		Ok(R1cs { wires, instance: 1 + public_outputs + public_inputs, constraints })
+	}
+}
+
+/// The constraints of an [`R1cs`] for arkworks, with the values of its wires when proving and
+/// without them for a setup.
+pub struct R1csCircuit<'a> {
+	pub r1cs: &'a R1cs,
+	pub witness: Option<Vec<Fr>>,
+}
+
+impl ConstraintSynthesizer<Fr> for R1csCircuit<'_> {
+	fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+		let value = |wire: usize| match &self.witness {
+			Some(witness) => witness.get(wire).copied().ok_or(SynthesisError::AssignmentMissing),
+			None => Ok(Fr::from(1u64)),
+		};
+
+		// arkworks allocates the constant `1` itself
+		for wire in 1..self.r1cs.instance {
+			cs.new_input_variable(|| value(wire))?;
+		}
+		for wire in self.r1cs.instance..self.r1cs.wires {
+			cs.new_witness_variable(|| value(wire))?;
+		}
+
+		let instance = self.r1cs.instance;
+		let lc = |terms: &Terms| {
+			terms.iter().fold(LinearCombination::zero(), |lc, (wire, coefficient)| {
+				let variable = if *wire < instance {
+					Variable::Instance(*wire)
+				} else {
+					Variable::Witness(wire - instance)
+				};
+				lc + (*coefficient, variable)
+			})
+		};
+		for (a, b, c) in &self.r1cs.constraints {
+			cs.enforce_constraint(lc(a), lc(b), lc(c))?;
+		}
+		Ok(())
+	}
+}
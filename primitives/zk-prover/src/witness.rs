@@ -0,0 +1,189 @@
+//! Witnesses of the `.wasm` circom 2 compiles next to a circuit, run with wasmi.
+//!
+//! The calculator takes field elements through a shared buffer of 32-bit words, least significant
+//! first, and input signals by the FNV-1a hash of their name.
+
+use ark_bls12_381::Fr;
+use ark_ff::{BigInt, BigInteger, PrimeField};
+use sp_core::U256;
+use wasmi::{Caller, Engine, Extern, Instance, Linker, Module, Store};
+
+use crate::{circom_error, to_field, Error};
+
+/// Words of a BLS12-381 scalar.
+const N32: usize = 8;
+
+/// The witness calculator of one circuit, instantiated anew for every witness.
+pub(crate) struct WitnessCalculator {
+	engine: Engine,
+	module: Module,
+}
+
+/// Calls into an instance of the calculator. The store holds the messages of its last error.
+struct Calculator {
+	store: Store<String>,
+	instance: Instance,
+}
+
+impl WitnessCalculator {
+	pub(crate) fn new(wasm: &[u8]) -> Result<Self, Error> {
+		let engine = Engine::default();
+		let module = Module::new(&engine, wasm).map_err(circom_error)?;
+		let calculator = WitnessCalculator { engine, module };
+
+		// checked once, every instance is of the same circuit
+		let mut instance = calculator.instantiate()?;
+		if instance.call::<(), i32>("getFieldNumLen32", ())? as usize != N32 {
+			return Err(Error::Circom("not a BLS12-381 circuit".into()))
+		}
+		instance.call::<(), ()>("getRawPrime", ())?;
+		if instance.read()? != Fr::MODULUS {
+			return Err(Error::Circom("not a BLS12-381 circuit".into()))
+		}
+		Ok(calculator)
+	}
+
+	/// The values of every wire, from the input signals by name.
+	pub(crate) fn calculate(&self, signals: &[(&str, Vec<U256>)]) -> Result<Vec<Fr>, Error> {
+		let mut instance = self.instantiate()?;
+		instance.call::<i32, ()>("init", 0)?;
+
+		let mut inputs = 0;
+		for (name, values) in signals {
+			let (msb, lsb) = fnv1a(name);
+			let size = instance.call::<(i32, i32), i32>("getInputSignalSize", (msb, lsb))?;
+			// unknown names have no values
+			if size <= 0 {
+				return Err(Error::Circom(format!("signal {} not found", name)))
+			}
+			if values.len() != size as usize {
+				return Err(Error::Circom(format!(
+					"signal {} takes {} values, not {}",
+					name,
+					size,
+					values.len()
+				)))
+			}
+			for (index, value) in values.iter().enumerate() {
+				instance.write(to_field(value).into_bigint())?;
+				instance.call::<(i32, i32, i32), ()>("setInputSignal", (msb, lsb, index as i32))?;
+			}
+			inputs += values.len();
+		}
+		let expected = instance.call::<(), i32>("getInputSize", ())? as usize;
+		if inputs < expected {
+			return Err(Error::Circom(format!("{} of {} inputs set", inputs, expected)))
+		}
+
+		let size = instance.call::<(), i32>("getWitnessSize", ())?;
+		(0..size)
+			.map(|wire| {
+				instance.call::<i32, ()>("getWitness", wire)?;
+				let value = instance.read()?;
+				Fr::from_bigint(value)
+					.ok_or_else(|| Error::Circom("witness out of the field".into()))
+			})
+			.collect()
+	}
+
+	fn instantiate(&self) -> Result<Calculator, Error> {
+		let mut store = Store::new(&self.engine, String::new());
+		let mut linker = Linker::<String>::new(&self.engine);
+		linker
+			.func_wrap("runtime", "exceptionHandler", |caller: Caller<'_, String>, code: i32| {
+				let reason = match code {
+					1 => "signal not found",
+					2 => "too many signals set",
+					3 => "signal already set",
+					4 => "assert failed",
+					5 => "not enough memory",
+					6 => "input signal array access exceeds the size",
+					_ => "unknown error",
+				};
+				Err::<(), _>(wasmi::Error::new(format!("{}{}", reason, caller.data())))
+			})
+			.and_then(|linker| {
+				linker.func_wrap(
+					"runtime",
+					"printErrorMessage",
+					|mut caller: Caller<'_, String>| {
+						let message = message(&mut caller)?;
+						caller.data_mut().push_str(&format!(": {}", message.trim_end()));
+						Ok::<_, wasmi::Error>(())
+					},
+				)
+			})
+			// logs of the circuit, nobody reads them
+			.and_then(|linker| {
+				linker.func_wrap(
+					"runtime",
+					"writeBufferMessage",
+					|mut caller: Caller<'_, String>| message(&mut caller).map(drop),
+				)
+			})
+			.and_then(|linker| linker.func_wrap("runtime", "showSharedRWMemory", || {}))
+			.map_err(circom_error)?;
+
+		let instance = linker
+			.instantiate(&mut store, &self.module)
+			.and_then(|instance| instance.start(&mut store))
+			.map_err(circom_error)?;
+		Ok(Calculator { store, instance })
+	}
+}
+
+impl Calculator {
+	fn call<Params: wasmi::WasmParams, Results: wasmi::WasmResults>(
+		&mut self,
+		name: &str,
+		params: Params,
+	) -> Result<Results, Error> {
+		self.instance
+			.get_typed_func::<Params, Results>(&self.store, name)
+			.and_then(|func| func.call(&mut self.store, params))
+			.map_err(circom_error)
+	}
+
+	/// The field element in the shared buffer.
+	fn read(&mut self) -> Result<BigInt<4>, Error> {
+		let mut limbs = [0u64; 4];
+		for word in 0..N32 {
+			let value = self.call::<i32, i32>("readSharedRWMemory", word as i32)? as u32;
+			limbs[word / 2] |= (value as u64) << (32 * (word % 2));
+		}
+		Ok(BigInt::new(limbs))
+	}
+
+	fn write(&mut self, value: BigInt<4>) -> Result<(), Error> {
+		let bytes = value.to_bytes_le();
+		for (word, value) in bytes.chunks(4).enumerate() {
+			let value = u32::from_le_bytes(value.try_into().expect("chunks of 4 bytes"));
+			self.call::<(i32, i32), ()>("writeSharedRWMemory", (word as i32, value as i32))?;
+		}
+		Ok(())
+	}
+}
+
+/// The message the calculator spells out by `getMessageChar`.
+fn message(caller: &mut Caller<'_, String>) -> Result<String, wasmi::Error> {
+	let next = caller
+		.get_export("getMessageChar")
+		.and_then(Extern::into_func)
+		.ok_or_else(|| wasmi::Error::new("missing getMessageChar"))?
+		.typed::<(), i32>(&*caller)?;
+	let mut message = String::new();
+	loop {
+		match next.call(&mut *caller, ())? {
+			0 => return Ok(message),
+			c => message.push(c as u8 as char),
+		}
+	}
+}
+
+/// High and low 32 bits of the 64-bit FNV-1a hash of a signal name.
+fn fnv1a(name: &str) -> (i32, i32) {
+	let hash = name
+		.bytes()
+		.fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+	((hash >> 32) as i32, hash as i32)
+}
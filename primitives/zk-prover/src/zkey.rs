@@ -0,0 +1,146 @@
+//! Proving keys of snarkjs `.zkey` files on BLS12-381.
+//!
+//! The file is the same on every curve, sections of little-endian integers and points in Montgomery
+//! form, only the size of the field elements differs.
+
+use std::io::{self, Read, Seek};
+
+use ark_bls12_381::{Bls12_381, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ff::{BigInt, BigInteger, PrimeField, Zero};
+use ark_groth16::{ProvingKey, VerifyingKey};
+
+use crate::binfile::{invalid, BinFile};
+
+const MAGIC: &[u8; 4] = b"zkey";
+const GROTH16: u32 = 1;
+
+// section ids of the groth16 zkey
+const HEADER: u32 = 1;
+const GROTH16_HEADER: u32 = 2;
+const IC: u32 = 3;
+const A: u32 = 5;
+const B_G1: u32 = 6;
+const B_G2: u32 = 7;
+const C: u32 = 8;
+const H: u32 = 9;
+
+/// Read the Groth16 proving key of a BLS12-381 `.zkey`. Its H query is for the domain of snarkjs,
+/// proofs against it have to be made with [`crate::SnarkjsReduction`].
+pub fn read_zkey<R: Read + Seek>(reader: &mut R) -> io::Result<ProvingKey<Bls12_381>> {
+	let mut zkey = BinFile::open(reader, MAGIC, "not a zkey file")?;
+
+	zkey.seek(HEADER)?;
+	if zkey.u32()? != GROTH16 {
+		return Err(invalid("not a groth16 key"))
+	}
+
+	zkey.seek(GROTH16_HEADER)?;
+	let (n8q, q) = (zkey.u32()?, zkey.bytes(48)?);
+	let (n8r, r) = (zkey.u32()?, zkey.bytes(32)?);
+	if n8q != 48 || q != Fq::MODULUS.to_bytes_le() || n8r != 32 || r != Fr::MODULUS.to_bytes_le() {
+		return Err(invalid("not a BLS12-381 key"))
+	}
+	let n_vars = zkey.u32()? as usize;
+	let n_public = zkey.u32()? as usize;
+	let domain_size = zkey.u32()? as usize;
+	let alpha_g1 = zkey.g1()?;
+	let beta_g1 = zkey.g1()?;
+	let beta_g2 = zkey.g2()?;
+	let gamma_g2 = zkey.g2()?;
+	let delta_g1 = zkey.g1()?;
+	let delta_g2 = zkey.g2()?;
+
+	Ok(ProvingKey {
+		vk: VerifyingKey {
+			alpha_g1,
+			beta_g2,
+			gamma_g2,
+			delta_g2,
+			gamma_abc_g1: zkey.g1_section(IC, n_public + 1)?,
+		},
+		beta_g1,
+		delta_g1,
+		a_query: zkey.g1_section(A, n_vars)?,
+		b_g1_query: zkey.g1_section(B_G1, n_vars)?,
+		b_g2_query: zkey.g2_section(B_G2, n_vars)?,
+		h_query: zkey.g1_section(H, domain_size)?,
+		l_query: zkey.g1_section(C, n_vars - n_public - 1)?,
+	})
+}
+
+impl<'a, R: Read + Seek> BinFile<'a, R> {
+	/// A base field element, kept in the Montgomery form snarkjs writes it in.
+	fn fq(&mut self) -> io::Result<Fq> {
+		let mut limbs = [0u64; 6];
+		for limb in limbs.iter_mut() {
+			*limb = self.u64()?;
+		}
+		Ok(Fq::new_unchecked(BigInt::new(limbs)))
+	}
+
+	fn fq2(&mut self) -> io::Result<Fq2> {
+		Ok(Fq2::new(self.fq()?, self.fq()?))
+	}
+
+	// points are taken as given, the key is trusted like the circuit it was made for
+	fn g1(&mut self) -> io::Result<G1Affine> {
+		let (x, y) = (self.fq()?, self.fq()?);
+		Ok(if x.is_zero() && y.is_zero() {
+			G1Affine::identity()
+		} else {
+			G1Affine::new_unchecked(x, y)
+		})
+	}
+
+	fn g2(&mut self) -> io::Result<G2Affine> {
+		let (x, y) = (self.fq2()?, self.fq2()?);
+		Ok(if x.is_zero() && y.is_zero() {
+			G2Affine::identity()
+		} else {
+			G2Affine::new_unchecked(x, y)
+		})
+	}
+
+	fn g1_section(&mut self, section: u32, len: usize) -> io::Result<Vec<G1Affine>> {
+		self.seek(section)?;
+		(0..len).map(|_| self.g1()).collect()
+	}
+
+	fn g2_section(&mut self, section: u32, len: usize) -> io::Result<Vec<G2Affine>> {
+		self.seek(section)?;
+		(0..len).map(|_| self.g2()).collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{vk_json, Circuit};
+	use std::fs::File;
+
+	#[test]
+	fn test_read_zkey_matches_verification_key_json() {
+		for circuit in [Circuit::Mixer, Circuit::Otp] {
+			let zkey = circuit.zkey().expect("both circuits have a zkey");
+			let key = read_zkey(&mut File::open(zkey).unwrap()).unwrap();
+
+			let expected: serde_json::Value = serde_json::from_reader(
+				File::open(circuit.dir().join("verification_key.json")).unwrap(),
+			)
+			.unwrap();
+			let json: serde_json::Value = serde_json::from_str(&vk_json(&key.vk)).unwrap();
+			for field in ["nPublic", "vk_alpha_1", "vk_beta_2", "vk_gamma_2", "vk_delta_2", "IC"] {
+				assert_eq!(json[field], expected[field], "{} of {}", field, circuit.name());
+			}
+		}
+	}
+
+	#[test]
+	fn test_read_zkey_rejects_other_files() {
+		let r1cs = Circuit::Otp.r1cs();
+		assert_eq!(
+			read_zkey(&mut File::open(r1cs).unwrap()).unwrap_err().kind(),
+			io::ErrorKind::InvalidData
+		);
+	}
+}
@@ -0,0 +1,150 @@
+//! BLS12-381 keys and proofs made from a known trapdoor, so benchmarks can verify real proofs
+//! without running a prover. Anyone knowing the trapdoor can prove anything against these keys.
+
+use crate::{groth16, verify, CurveVerificationKey, EncodedProof};
+use ark_bls12_381::{Fr, G1Affine, G2Affine};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{Field, PrimeField};
+use ark_groth16::VerifyingKey;
+use scale_info::prelude::{format, string::String};
+use sp_std::prelude::*;
+
+/// Key of a circuit with `public_inputs` inputs, every call returns the same key
+pub fn simulated_verification_key(public_inputs: usize) -> CurveVerificationKey {
+	verification_key(ic(public_inputs))
+}
+
+/// Key with the trapdoor of [`simulated_verification_key`] but another `ic`, so simulated proofs
+/// take as long to check against it and fail. Every `nth` is another key.
+pub fn rejecting_verification_key(public_inputs: usize, nth: u64) -> CurveVerificationKey {
+	let mut ic = ic(public_inputs);
+	ic[0] += Fr::from(nth + 1);
+	verification_key(ic)
+}
+
+/// [`simulated_verification_key`] as a snarkjs `verification_key.json`
+pub fn simulated_verification_key_json(public_inputs: usize) -> Vec<u8> {
+	let [alpha, beta, gamma, delta] = trapdoor();
+	let ic: Vec<_> = ic(public_inputs).into_iter().map(|s| g1_json(g1(s))).collect();
+	format!(
+		r#"{{"protocol": "groth16", "curve": "bls12381", "nPublic": {}, "vk_alpha_1": {}, "vk_beta_2": {}, "vk_gamma_2": {}, "vk_delta_2": {}, "IC": [{}]}}"#,
+		public_inputs,
+		g1_json(g1(alpha)),
+		g2_json(g2(beta)),
+		g2_json(g2(gamma)),
+		g2_json(g2(delta)),
+		ic.join(", ")
+	)
+	.into_bytes()
+}
+
+/// snarkjs json proof of `public_inputs` against [`simulated_verification_key`]
+pub fn simulated_proof(public_inputs: Vec<sp_core::U256>) -> EncodedProof {
+	let [alpha, beta, gamma, delta] = trapdoor();
+	let ic = ic(public_inputs.len());
+	let inputs = verify::prepare_public_inputs(public_inputs).expect("inputs are in the field");
+	let acc = inputs.iter().zip(ic.iter().skip(1)).fold(ic[0], |acc, (i, c)| acc + *i * c);
+
+	// any `a` and `b` verify with `c = (a * b - alpha * beta - acc * gamma) / delta`
+	let (a, b) = (Fr::from(13u64), Fr::from(17u64));
+	let c = (a * b - alpha * beta - acc * gamma) * delta.inverse().expect("delta is not zero");
+
+	EncodedProof::Json(
+		format!(
+			r#"{{"pi_a": {}, "pi_b": {}, "pi_c": {}, "protocol": "groth16", "curve": "bls12381"}}"#,
+			g1_json(g1(a)),
+			g2_json(g2(b)),
+			g1_json(g1(c))
+		)
+		.into_bytes(),
+	)
+}
+
+fn verification_key(ic: Vec<Fr>) -> CurveVerificationKey {
+	let [alpha, beta, gamma, delta] = trapdoor();
+	CurveVerificationKey::Bls12381(groth16::VerificationKey::new(VerifyingKey {
+		alpha_g1: g1(alpha),
+		beta_g2: g2(beta),
+		gamma_g2: g2(gamma),
+		delta_g2: g2(delta),
+		gamma_abc_g1: ic.into_iter().map(g1).collect(),
+	}))
+}
+
+/// `alpha`, `beta`, `gamma` and `delta`
+fn trapdoor() -> [Fr; 4] {
+	[2u64, 3, 5, 7].map(Fr::from)
+}
+
+fn ic(public_inputs: usize) -> Vec<Fr> {
+	(0..=public_inputs as u64).map(|i| Fr::from(11 + i)).collect()
+}
+
+fn g1(s: Fr) -> G1Affine {
+	(G1Affine::generator() * s).into_affine()
+}
+
+fn g2(s: Fr) -> G2Affine {
+	(G2Affine::generator() * s).into_affine()
+}
+
+fn g1_json(p: G1Affine) -> String {
+	format!(r#"["{}", "{}", "1"]"#, p.x.into_bigint(), p.y.into_bigint())
+}
+
+fn g2_json(p: G2Affine) -> String {
+	format!(
+		r#"[["{}", "{}"], ["{}", "{}"], ["1", "0"]]"#,
+		p.x.c0.into_bigint(),
+		p.x.c1.into_bigint(),
+		p.y.c0.into_bigint(),
+		p.y.c1.into_bigint()
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		benchmarking::{
+			rejecting_verification_key, simulated_proof, simulated_verification_key,
+			simulated_verification_key_json,
+		},
+		decode_proof, parse_verification_key, prepare_verification_key, verify,
+		CurveVerificationKey,
+	};
+	use sp_core::U256;
+
+	#[test]
+	fn simulated_proof_verifies() {
+		let inputs = vec![U256::from(5), U256::from(9)];
+		let vk = simulated_verification_key(2);
+		let vk = CurveVerificationKey::from_bytes(&vk.to_bytes()).unwrap();
+		let proof = decode_proof(&simulated_proof(inputs.clone())).unwrap();
+
+		assert!(verify(&vk, &proof, inputs).unwrap());
+		assert!(!verify(&vk, &proof, vec![U256::from(5), U256::from(8)]).unwrap());
+	}
+
+	#[test]
+	fn rejecting_key_fails_simulated_proof() {
+		let inputs = vec![U256::from(5), U256::from(9)];
+		let proof = decode_proof(&simulated_proof(inputs.clone())).unwrap();
+		for nth in 0..2 {
+			let vk = rejecting_verification_key(2, nth);
+			assert!(!verify(&vk, &proof, inputs.clone()).unwrap());
+		}
+		assert_ne!(
+			rejecting_verification_key(2, 0).to_bytes(),
+			rejecting_verification_key(2, 1).to_bytes()
+		);
+	}
+
+	#[test]
+	fn simulated_verification_key_json_prepares_to_the_key() {
+		let vk = parse_verification_key(&simulated_verification_key_json(3)).unwrap();
+		assert_eq!(
+			prepare_verification_key(vk).unwrap().to_bytes(),
+			simulated_verification_key(3).to_bytes()
+		);
+	}
+}
@@ -0,0 +1,165 @@
+//! Curve independent half of the Groth16 verifier, shared by [`crate::verify`] and
+//! [`crate::verify_bn254`].
+
+use crate::verify::{GProofCreationError, VerificationError, VerificationKeyCreationError};
+use ark_ec::{
+	pairing::{Pairing, PairingOutput},
+	short_weierstrass::{Affine, SWCurveConfig},
+	AffineRepr, CurveGroup,
+};
+use ark_ff::{PrimeField, Zero};
+use ark_groth16::{prepare_verifying_key, Groth16, PreparedVerifyingKey, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+use sp_core::hashing::blake2_256;
+use sp_std::{ops::Neg, prelude::*, vec};
+
+/// Prepared Groth16 verification key, `e(alpha, beta)` is computed once when it is built
+pub struct VerificationKey<E: Pairing> {
+	pub inner: PreparedVerifyingKey<E>,
+}
+
+impl<E: Pairing> VerificationKey<E> {
+	pub fn new(vk: VerifyingKey<E>) -> Self {
+		VerificationKey { inner: prepare_verifying_key(&vk) }
+	}
+
+	/// Compressed points of the key followed by `e(alpha, beta)`. The prepared `gamma` and `delta`
+	/// are left out, they take several kilobytes and are cheap to rebuild.
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let mut bytes = Vec::new();
+		// writing into a `Vec` can not fail
+		let _ = self.inner.vk.serialize_compressed(&mut bytes);
+		let _ = self.inner.alpha_g1_beta_g2.serialize_compressed(&mut bytes);
+		bytes
+	}
+
+	/// Reads a key written by [`Self::to_bytes`]. Its points were validated when the key was first
+	/// built, so they are not checked again.
+	pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, VerificationKeyCreationError> {
+		let point = |_| VerificationKeyCreationError::PointCreationError;
+		let vk = VerifyingKey::<E>::deserialize_with_mode(&mut bytes, Compress::Yes, Validate::No)
+			.map_err(point)?;
+		let alpha_g1_beta_g2 =
+			E::TargetField::deserialize_with_mode(&mut bytes, Compress::Yes, Validate::No)
+				.map_err(point)?;
+		if !bytes.is_empty() {
+			return Err(VerificationKeyCreationError::PointCreationError)
+		}
+
+		Ok(VerificationKey {
+			inner: PreparedVerifyingKey {
+				gamma_g2_neg_pc: vk.gamma_g2.into_group().neg().into_affine().into(),
+				delta_g2_neg_pc: vk.delta_g2.into_group().neg().into_affine().into(),
+				vk,
+				alpha_g1_beta_g2,
+			},
+		})
+	}
+}
+
+/// Groth16 proof
+pub struct GProof<E: Pairing> {
+	pub inner: ark_groth16::Proof<E>,
+}
+
+impl<E: Pairing> GProof<E> {
+	/// Reads ark-serialize compressed points, checking they are in the prime order subgroups
+	pub fn from_compressed(a: &[u8], b: &[u8], c: &[u8]) -> Result<Self, GProofCreationError> {
+		let point = |_| GProofCreationError::PointCreationError;
+		Ok(GProof {
+			inner: ark_groth16::Proof {
+				a: E::G1Affine::deserialize_compressed(a).map_err(point)?,
+				b: E::G2Affine::deserialize_compressed(b).map_err(point)?,
+				c: E::G1Affine::deserialize_compressed(c).map_err(point)?,
+			},
+		})
+	}
+
+	/// ark-serialize compressed `a`, `b` and `c`
+	pub fn to_compressed<const G1: usize, const G2: usize>(
+		&self,
+	) -> ([u8; G1], [u8; G2], [u8; G1]) {
+		let (mut a, mut b, mut c) = ([0u8; G1], [0u8; G2], [0u8; G1]);
+		// the sizes are those of the compressed points of the curve
+		let _ = self.inner.a.serialize_compressed(&mut a[..]);
+		let _ = self.inner.b.serialize_compressed(&mut b[..]);
+		let _ = self.inner.c.serialize_compressed(&mut c[..]);
+		(a, b, c)
+	}
+}
+
+/// Verifies given proof with given verification key and public inputs, in one multi Miller loop
+pub fn verify<E: Pairing>(
+	vk: &VerificationKey<E>,
+	proof: &GProof<E>,
+	inputs: &[E::ScalarField],
+) -> Result<bool, VerificationError> {
+	Groth16::<E>::verify_proof(&vk.inner, &proof.inner, inputs)
+		.map_err(|_| VerificationError::InvalidVerificationKey)
+}
+
+/// Verifies every proof of `batch` against `vk` in a single multi Miller loop and final
+/// exponentiation. The equation of each proof is scaled by a weight derived from the whole batch,
+/// so invalid proofs can not be crafted to cancel each other out. `false` if any of them is
+/// invalid.
+pub fn batch_verify<E: Pairing>(
+	vk: &VerificationKey<E>,
+	batch: &[(&GProof<E>, Vec<E::ScalarField>)],
+) -> Result<bool, VerificationError> {
+	let ic = &vk.inner.vk.gamma_abc_g1;
+	if batch.iter().any(|(_, inputs)| inputs.len() + 1 != ic.len()) {
+		return Err(VerificationError::InvalidVerificationKey)
+	}
+
+	// sum of r * (e(a, b) - e(alpha, beta) - e(sum of ic * inputs, gamma) - e(c, delta)) = 0
+	let mut g1 = Vec::<E::G1Prepared>::with_capacity(batch.len() + 2);
+	let mut g2 = Vec::<E::G2Prepared>::with_capacity(batch.len() + 2);
+	let mut weight_sum = E::ScalarField::zero();
+	let mut input_sums = vec![E::ScalarField::zero(); ic.len() - 1];
+	let mut c_sum = E::G1::zero();
+	for ((proof, inputs), r) in batch.iter().zip(batch_weights(batch)) {
+		g1.push(E::G1Prepared::from(proof.inner.a * r));
+		g2.push(proof.inner.b.into());
+		c_sum += proof.inner.c * r;
+		weight_sum += r;
+		for (sum, input) in input_sums.iter_mut().zip(inputs) {
+			*sum += r * input;
+		}
+	}
+	let inputs_sum = ic
+		.iter()
+		.skip(1)
+		.zip(&input_sums)
+		.fold(ic[0] * weight_sum, |acc, (p, s)| acc + *p * s);
+	g1.push(E::G1Prepared::from(inputs_sum));
+	g2.push(vk.inner.gamma_g2_neg_pc.clone());
+	g1.push(E::G1Prepared::from(c_sum));
+	g2.push(vk.inner.delta_g2_neg_pc.clone());
+
+	let expected = PairingOutput(vk.inner.alpha_g1_beta_g2) * weight_sum;
+	Ok(E::final_exponentiation(E::multi_miller_loop(g1, g2)) == Some(expected))
+}
+
+/// One weight per proof, hashed from every proof and input of the batch
+fn batch_weights<E: Pairing>(
+	batch: &[(&GProof<E>, Vec<E::ScalarField>)],
+) -> impl Iterator<Item = E::ScalarField> {
+	let mut transcript = Vec::new();
+	for (proof, inputs) in batch {
+		// writing into a `Vec` can not fail
+		let _ = proof.inner.serialize_compressed(&mut transcript);
+		let _ = inputs.serialize_compressed(&mut transcript);
+	}
+	let seed = blake2_256(&transcript);
+	(0..batch.len() as u32).map(move |i| {
+		let mut preimage = seed.to_vec();
+		preimage.extend(i.to_le_bytes());
+		E::ScalarField::from_le_bytes_mod_order(&blake2_256(&preimage))
+	})
+}
+
+/// `(x, y)` if it is a point of the prime order subgroup
+pub fn point<P: SWCurveConfig>(x: P::BaseField, y: P::BaseField) -> Option<Affine<P>> {
+	let p = Affine::<P>::new_unchecked(x, y);
+	(p.is_on_curve() && p.is_in_correct_subgroup_assuming_on_curve()).then_some(p)
+}
@@ -0,0 +1,408 @@
+//! Groth16 verification of snarkjs generated keys and proofs, on BLS12-381 and BN254.
+//!
+//! Keys and proofs are parsed from snarkjs json with [`parse_verification_key`] and
+//! [`parse_proof`], the key is prepared with [`prepare_verification_key`] and the proof checked
+//! against `U256` public inputs with [`verify`]. Many proofs against one key are cheaper checked
+//! together with [`batch_verify`].
+//!
+//! A prepared key can be kept in its compact form from [`CurveVerificationKey::to_bytes`], which is
+//! read back by [`CurveVerificationKey::from_bytes`] without any json parsing, point validation or
+//! pairing.
+//!
+//! Proofs are submitted as an [`EncodedProof`], either the snarkjs json or the compressed points,
+//! and read with [`decode_proof`].
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(any(test, feature = "runtime-benchmarks"))]
+pub mod benchmarking;
+pub mod deserialization;
+pub mod groth16;
+pub mod verify;
+pub mod verify_bn254;
+
+use crate::{
+	deserialization::{Proof, VKey},
+	verify::{
+		GProof, GProofCreationError, PublicInputsCreationError, VerificationError, VerificationKey,
+		VerificationKeyCreationError, SUPPORTED_PROTOCOL,
+	},
+};
+use codec::{Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use sp_std::vec::Vec;
+
+/// Curves a Groth16 key or proof can be given on, named as in snarkjs json
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode, TypeInfo, MaxEncodedLen)]
+pub enum Curve {
+	Bls12381,
+	Bn254,
+}
+
+impl Curve {
+	pub fn from_name(name: &[u8]) -> Option<Self> {
+		if name == verify::SUPPORTED_CURVE.as_bytes() {
+			Some(Curve::Bls12381)
+		} else if name == verify_bn254::SUPPORTED_CURVE.as_bytes() {
+			Some(Curve::Bn254)
+		} else {
+			None
+		}
+	}
+}
+
+/// Verification key on whichever curve its `curve` field names
+pub enum CurveVerificationKey {
+	Bls12381(VerificationKey),
+	Bn254(verify_bn254::VerificationKey),
+}
+
+impl CurveVerificationKey {
+	pub fn curve(&self) -> Curve {
+		match self {
+			CurveVerificationKey::Bls12381(_) => Curve::Bls12381,
+			CurveVerificationKey::Bn254(_) => Curve::Bn254,
+		}
+	}
+
+	/// Compact form of the key: its SCALE encoded [`Curve`], followed by the compressed points and
+	/// the precomputed `e(alpha, beta)`
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let mut bytes = self.curve().encode();
+		bytes.extend(match self {
+			CurveVerificationKey::Bls12381(vk) => vk.to_bytes(),
+			CurveVerificationKey::Bn254(vk) => vk.to_bytes(),
+		});
+		bytes
+	}
+
+	/// Reads a key written by [`Self::to_bytes`], trusting its points to be valid
+	pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, VerificationKeyCreationError> {
+		match Curve::decode(&mut bytes) {
+			Ok(Curve::Bls12381) => VerificationKey::from_bytes(bytes).map(Self::Bls12381),
+			Ok(Curve::Bn254) => verify_bn254::VerificationKey::from_bytes(bytes).map(Self::Bn254),
+			Err(_) => Err(VerificationKeyCreationError::NotSupportedCurve),
+		}
+	}
+}
+
+/// Proof on whichever curve its `curve` field names
+pub enum CurveProof {
+	Bls12381(GProof),
+	Bn254(verify_bn254::GProof),
+}
+
+impl CurveProof {
+	/// The proof as [`EncodedProof::Bls12381`] or [`EncodedProof::Bn254`]
+	pub fn to_compressed(&self) -> EncodedProof {
+		match self {
+			CurveProof::Bls12381(proof) => {
+				let (a, b, c) = proof.to_compressed();
+				EncodedProof::Bls12381(a, b, c)
+			},
+			CurveProof::Bn254(proof) => {
+				let (a, b, c) = proof.to_compressed();
+				EncodedProof::Bn254(a, b, c)
+			},
+		}
+	}
+}
+
+/// Groth16 proof as it is submitted in a call
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub enum EncodedProof {
+	/// snarkjs `proof.json`
+	Json(Vec<u8>),
+	/// ark-serialize compressed `a`, `b` and `c` of a proof on BLS12-381
+	Bls12381([u8; 48], [u8; 96], [u8; 48]),
+	/// ark-serialize compressed `a`, `b` and `c` of a proof on BN254
+	Bn254([u8; 32], [u8; 64], [u8; 32]),
+}
+
+/// Why snarkjs json could not be turned into a verification key or proof
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+	/// Not a snarkjs key or proof
+	Malformed,
+	NotSupportedCurve,
+	NotSupportedProtocol,
+	/// Coordinates are not a point of the curve
+	PointCreationError,
+}
+
+/// Reads a snarkjs `verification_key.json`, checking it is a Groth16 key on a supported curve
+pub fn parse_verification_key(json: &[u8]) -> Result<VKey, ParseError> {
+	let vk = VKey::from_json_u8_slice(json).map_err(|_| ParseError::Malformed)?;
+	if Curve::from_name(&vk.curve).is_none() {
+		return Err(ParseError::NotSupportedCurve)
+	}
+	if vk.protocol != SUPPORTED_PROTOCOL.as_bytes() {
+		return Err(ParseError::NotSupportedProtocol)
+	}
+	Ok(vk)
+}
+
+/// Reads a snarkjs `proof.json` into a proof on the curve it names
+pub fn parse_proof(json: &[u8]) -> Result<CurveProof, ParseError> {
+	let proof = Proof::from_json_u8_slice(json).map_err(|_| ParseError::Malformed)?;
+	if Curve::from_name(&proof.curve).is_none() {
+		return Err(ParseError::NotSupportedCurve)
+	}
+	if proof.protocol != SUPPORTED_PROTOCOL.as_bytes() {
+		return Err(ParseError::NotSupportedProtocol)
+	}
+	prepare_proof(proof).map_err(|_| ParseError::PointCreationError)
+}
+
+/// Reads a proof in any of its encodings
+pub fn decode_proof(proof: &EncodedProof) -> Result<CurveProof, ParseError> {
+	let point = |_| ParseError::PointCreationError;
+	match proof {
+		EncodedProof::Json(json) => parse_proof(json),
+		EncodedProof::Bls12381(a, b, c) =>
+			GProof::from_compressed(a, b, c).map(CurveProof::Bls12381).map_err(point),
+		EncodedProof::Bn254(a, b, c) => verify_bn254::GProof::from_compressed(a, b, c)
+			.map(CurveProof::Bn254)
+			.map_err(point),
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CurveVerificationError {
+	CurveMismatch,
+	PublicInputsOutOfRange,
+	InvalidVerificationKey,
+}
+
+impl From<PublicInputsCreationError> for CurveVerificationError {
+	fn from(_: PublicInputsCreationError) -> Self {
+		CurveVerificationError::PublicInputsOutOfRange
+	}
+}
+
+impl From<VerificationError> for CurveVerificationError {
+	fn from(_: VerificationError) -> Self {
+		CurveVerificationError::InvalidVerificationKey
+	}
+}
+
+pub fn prepare_verification_key(
+	deserialized_vk: VKey,
+) -> Result<CurveVerificationKey, VerificationKeyCreationError> {
+	match Curve::from_name(&deserialized_vk.curve) {
+		Some(Curve::Bls12381) =>
+			VerificationKey::from_vkey(&deserialized_vk).map(CurveVerificationKey::Bls12381),
+		Some(Curve::Bn254) => verify_bn254::VerificationKey::from_vkey(&deserialized_vk)
+			.map(CurveVerificationKey::Bn254),
+		None => Err(VerificationKeyCreationError::NotSupportedCurve),
+	}
+}
+
+pub fn prepare_proof(proof: Proof) -> Result<CurveProof, GProofCreationError> {
+	match Curve::from_name(&proof.curve) {
+		Some(Curve::Bls12381) => GProof::from_proof(&proof).map(CurveProof::Bls12381),
+		Some(Curve::Bn254) => verify_bn254::GProof::from_proof(&proof).map(CurveProof::Bn254),
+		None => Err(GProofCreationError::NotSupportedCurve),
+	}
+}
+
+/// Verifies `proof` against `vk`, both of which have to be on the same curve
+pub fn verify(
+	vk: &CurveVerificationKey,
+	proof: &CurveProof,
+	inputs: Vec<sp_core::U256>,
+) -> Result<bool, CurveVerificationError> {
+	match (vk, proof) {
+		(CurveVerificationKey::Bls12381(vk), CurveProof::Bls12381(proof)) =>
+			Ok(verify::verify(vk, proof, verify::prepare_public_inputs(inputs)?)?),
+		(CurveVerificationKey::Bn254(vk), CurveProof::Bn254(proof)) =>
+			Ok(verify_bn254::verify(vk, proof, verify_bn254::prepare_public_inputs(inputs)?)?),
+		_ => Err(CurveVerificationError::CurveMismatch),
+	}
+}
+
+/// Verifies `proof` against each of `vks` on its curve until one accepts it. `CurveMismatch` if
+/// none of them is on the curve of the proof.
+pub fn verify_any<'a>(
+	vks: impl IntoIterator<Item = &'a CurveVerificationKey>,
+	proof: &CurveProof,
+	inputs: Vec<sp_core::U256>,
+) -> Result<bool, CurveVerificationError> {
+	let mut result = Err(CurveVerificationError::CurveMismatch);
+	for vk in vks {
+		match verify(vk, proof, inputs.clone()) {
+			Ok(true) => return Ok(true),
+			Err(CurveVerificationError::CurveMismatch) => continue,
+			other => result = other,
+		}
+	}
+	result
+}
+
+/// Verifies every proof of `batch` against `vk` at once, all of them have to be on the curve of
+/// `vk`
+pub fn batch_verify(
+	vk: &CurveVerificationKey,
+	batch: &[(CurveProof, Vec<sp_core::U256>)],
+) -> Result<bool, CurveVerificationError> {
+	match vk {
+		CurveVerificationKey::Bls12381(vk) => {
+			let batch = batch
+				.iter()
+				.map(|(proof, inputs)| match proof {
+					CurveProof::Bls12381(proof) =>
+						Ok((proof, verify::prepare_public_inputs(inputs.clone())?)),
+					_ => Err(CurveVerificationError::CurveMismatch),
+				})
+				.collect::<Result<Vec<_>, _>>()?;
+			Ok(verify::batch_verify(vk, &batch)?)
+		},
+		CurveVerificationKey::Bn254(vk) => {
+			let batch = batch
+				.iter()
+				.map(|(proof, inputs)| match proof {
+					CurveProof::Bn254(proof) =>
+						Ok((proof, verify_bn254::prepare_public_inputs(inputs.clone())?)),
+					_ => Err(CurveVerificationError::CurveMismatch),
+				})
+				.collect::<Result<Vec<_>, _>>()?;
+			Ok(verify_bn254::batch_verify(vk, &batch)?)
+		},
+	}
+}
+
+/// [`batch_verify`] against each of `vks` until one accepts the whole batch. `CurveMismatch` if
+/// none of them is on the curve of every proof.
+pub fn batch_verify_any<'a>(
+	vks: impl IntoIterator<Item = &'a CurveVerificationKey>,
+	batch: &[(CurveProof, Vec<sp_core::U256>)],
+) -> Result<bool, CurveVerificationError> {
+	let mut result = Err(CurveVerificationError::CurveMismatch);
+	for vk in vks {
+		match batch_verify(vk, batch) {
+			Ok(true) => return Ok(true),
+			Err(CurveVerificationError::CurveMismatch) => continue,
+			other => result = other,
+		}
+	}
+	result
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		decode_proof, groth16::GProof, parse_proof, parse_verification_key,
+		verify::VerificationKeyCreationError, Curve, CurveProof, CurveVerificationKey,
+		EncodedProof, ParseError,
+	};
+	use ark_ec::AffineRepr;
+
+	const PROOF: &str = r#"{
+ "pi_a": ["1", "2", "1"],
+ "pi_b": [["1", "2"], ["3", "4"], ["1", "0"]],
+ "pi_c": ["1", "2", "1"],
+ "protocol": "<protocol>",
+ "curve": "<curve>"
+}"#;
+
+	fn proof(protocol: &str, curve: &str) -> String {
+		PROOF.replace("<protocol>", protocol).replace("<curve>", curve)
+	}
+
+	#[test]
+	fn proof_must_be_groth16_on_supported_curve() {
+		assert_eq!(
+			parse_proof(proof("groth16", "secp256k1").as_bytes()).err(),
+			Some(ParseError::NotSupportedCurve)
+		);
+		assert_eq!(
+			parse_proof(proof("plonk", "bn128").as_bytes()).err(),
+			Some(ParseError::NotSupportedProtocol)
+		);
+		// (1, 2) is the BN254 G1 generator, but b is not on the twist
+		assert_eq!(
+			parse_proof(proof("groth16", "bn128").as_bytes()).err(),
+			Some(ParseError::PointCreationError)
+		);
+	}
+
+	#[test]
+	fn malformed_json_is_rejected() {
+		assert_eq!(parse_verification_key(b"{}").err(), Some(ParseError::Malformed));
+		assert_eq!(parse_proof(b"[]").err(), Some(ParseError::Malformed));
+	}
+
+	#[test]
+	fn compact_verification_key_starts_with_its_curve() {
+		assert!(matches!(
+			CurveVerificationKey::from_bytes(&[2]),
+			Err(VerificationKeyCreationError::NotSupportedCurve)
+		));
+		assert!(matches!(
+			CurveVerificationKey::from_bytes(&[]),
+			Err(VerificationKeyCreationError::NotSupportedCurve)
+		));
+		assert!(matches!(
+			CurveVerificationKey::from_bytes(&[1, 0]),
+			Err(VerificationKeyCreationError::PointCreationError)
+		));
+	}
+
+	/// Proof made of the curve generators
+	fn generator_proof(curve: Curve) -> CurveProof {
+		match curve {
+			Curve::Bls12381 => CurveProof::Bls12381(GProof {
+				inner: ark_groth16::Proof {
+					a: ark_bls12_381::G1Affine::generator(),
+					b: ark_bls12_381::G2Affine::generator(),
+					c: ark_bls12_381::G1Affine::generator(),
+				},
+			}),
+			Curve::Bn254 => CurveProof::Bn254(GProof {
+				inner: ark_groth16::Proof {
+					a: ark_bn254::G1Affine::generator(),
+					b: ark_bn254::G2Affine::generator(),
+					c: ark_bn254::G1Affine::generator(),
+				},
+			}),
+		}
+	}
+
+	#[test]
+	fn compressed_proof_round_trips() {
+		let proof = generator_proof(Curve::Bn254);
+		let encoded = proof.to_compressed();
+		assert!(matches!(encoded, EncodedProof::Bn254(..)));
+		assert_eq!(decode_proof(&encoded).map(|p| p.to_compressed()), Ok(encoded));
+
+		let proof = generator_proof(Curve::Bls12381);
+		let encoded = proof.to_compressed();
+		assert!(matches!(encoded, EncodedProof::Bls12381(..)));
+		assert_eq!(decode_proof(&encoded).map(|p| p.to_compressed()), Ok(encoded));
+	}
+
+	#[test]
+	fn compressed_proof_points_are_validated() {
+		// x coordinates above the field modulus, flags left clear
+		let mut bn254 = [0xff; 32];
+		bn254[31] = 0x3f;
+		let valid = generator_proof(Curve::Bn254).to_compressed();
+		let EncodedProof::Bn254(_, b, c) = valid else { unreachable!() };
+		assert_eq!(
+			decode_proof(&EncodedProof::Bn254(bn254, b, c)).err(),
+			Some(ParseError::PointCreationError)
+		);
+		let mut bls12381 = [0xff; 48];
+		bls12381[0] = 0x9f;
+		let valid = generator_proof(Curve::Bls12381).to_compressed();
+		let EncodedProof::Bls12381(_, b, c) = valid else { unreachable!() };
+		assert_eq!(
+			decode_proof(&EncodedProof::Bls12381(bls12381, b, c)).err(),
+			Some(ParseError::PointCreationError)
+		);
+		assert_eq!(
+			decode_proof(&EncodedProof::Json(proof("groth16", "secp256k1").into_bytes())).err(),
+			Some(ParseError::NotSupportedCurve)
+		);
+	}
+}
@@ -0,0 +1,338 @@
+// MIT License
+
+// Copyright (c) 2022 Bright Inventions
+
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::{
+	deserialization::{Proof, VKey},
+	groth16::{self, point},
+};
+use ark_bls12_381::{Bls12_381, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ff::{BigInt, PrimeField};
+use ark_groth16::VerifyingKey;
+use sp_std::prelude::*;
+
+pub const SUPPORTED_CURVE: &str = "bls12381";
+pub const SUPPORTED_PROTOCOL: &str = "groth16";
+
+type Number = [u8; 48];
+
+/// Represents prepared Groth16 verification key over BLS12-381
+pub type VerificationKey = groth16::VerificationKey<Bls12_381>;
+
+#[derive(Debug)]
+pub enum VerificationKeyCreationError {
+	PointCreationError,
+	NotSupportedCurve,
+}
+
+impl VerificationKey {
+	pub fn from_vkey(vk: &VKey) -> Result<Self, VerificationKeyCreationError> {
+		let point = || VerificationKeyCreationError::PointCreationError;
+		let mut gamma_abc_g1 = Vec::with_capacity(vk.ic.len());
+		for ic in vk.ic.iter() {
+			gamma_abc_g1.push(g1(&ic[0], &ic[1]).ok_or_else(point)?);
+		}
+		let vk = VerifyingKey::<Bls12_381> {
+			alpha_g1: g1(&vk.alpha[0], &vk.alpha[1]).ok_or_else(point)?,
+			beta_g2: g2(&vk.beta).ok_or_else(point)?,
+			gamma_g2: g2(&vk.gamma).ok_or_else(point)?,
+			delta_g2: g2(&vk.delta).ok_or_else(point)?,
+			gamma_abc_g1,
+		};
+
+		Ok(VerificationKey::new(vk))
+	}
+}
+
+/// Represents Groth16 proof over BLS12-381
+pub type GProof = groth16::GProof<Bls12_381>;
+
+#[derive(Debug)]
+pub enum GProofCreationError {
+	PointCreationError,
+	NotSupportedCurve,
+}
+
+impl GProof {
+	pub fn from_proof(proof: &Proof) -> Result<Self, GProofCreationError> {
+		let point = || GProofCreationError::PointCreationError;
+		let a = g1(&proof.a[0], &proof.a[1]).ok_or_else(point)?;
+		let b = g2(&proof.b).ok_or_else(point)?;
+		let c = g1(&proof.c[0], &proof.c[1]).ok_or_else(point)?;
+
+		Ok(GProof { inner: ark_groth16::Proof { a, b, c } })
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub enum VerificationError {
+	InvalidVerificationKey,
+}
+
+pub type VerificationResult = Result<bool, VerificationError>;
+
+pub type PublicInputs = Vec<Fr>;
+
+#[derive(Debug)]
+pub enum PublicInputsCreationError {
+	OutOfRange,
+}
+
+/// Turns `U256` values into `Fr` representation, rejecting values outside of the scalar field
+pub fn prepare_public_inputs(
+	inputs: Vec<sp_core::U256>,
+) -> Result<PublicInputs, PublicInputsCreationError> {
+	inputs
+		.into_iter()
+		.map(|x| Fr::from_bigint(BigInt::new(x.0)).ok_or(PublicInputsCreationError::OutOfRange))
+		.collect()
+}
+
+/// Verifies given proof with given verification key and public inputs
+pub fn verify(vk: &VerificationKey, proof: &GProof, inputs: PublicInputs) -> VerificationResult {
+	// e(a, b) = e(alpha, beta) * e(sum of ic * inputs, gamma) * e(c, delta), with e(alpha, beta)
+	// taken from the prepared key
+	groth16::verify(vk, proof, &inputs)
+}
+
+/// Verifies all proofs of `batch` with given verification key at once
+pub fn batch_verify(vk: &VerificationKey, batch: &[(&GProof, PublicInputs)]) -> VerificationResult {
+	groth16::batch_verify(vk, batch)
+}
+
+/// Reads big endian base field element, `None` if it is not reduced
+fn fq(number: &Number) -> Option<Fq> {
+	let mut limbs = [0u64; 6];
+	for (limb, bytes) in limbs.iter_mut().zip(number.rchunks_exact(8)) {
+		*limb = u64::from_be_bytes(bytes.try_into().ok()?);
+	}
+	Fq::from_bigint(BigInt::new(limbs))
+}
+
+fn g1(x: &Number, y: &Number) -> Option<G1Affine> {
+	point(fq(x)?, fq(y)?)
+}
+
+/// snarkjs lists G2 coordinates as `[[x_c0, x_c1], [y_c0, y_c1], [1, 0]]`
+fn g2(coordinates: &[[Number; 2]; 3]) -> Option<G2Affine> {
+	let x = Fq2::new(fq(&coordinates[0][0])?, fq(&coordinates[0][1])?);
+	let y = Fq2::new(fq(&coordinates[1][0])?, fq(&coordinates[1][1])?);
+	point(x, y)
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		deserialization::U256,
+		verify::{batch_verify, g1, g2, verify, GProof, VerificationError, VerificationKey},
+	};
+	use ark_bls12_381::{G1Affine, G2Affine};
+	use ark_ec::{AffineRepr, CurveGroup};
+	use ark_groth16::{Proof, VerifyingKey};
+
+	const ALPHA_X: &str = "2417420058161902631695569321985275527817337553240735969068630412919230058600548397578577183742111992841943587142680";
+	const ALPHA_Y: &str = "2683193963041639430431668252069589353703764749562535314981925385889474793061455502785968498855669710056680025802535";
+
+	const BETA_X_C0: &str = "2953983861911780746898420772852203750596202163211813473761616529894571940032171065334774419373056700627707738200018";
+	const BETA_X_C1: &str = "3062465588861097636655055190501059315624734570742089309263797407021640154269222765149244340402777629537231482465213";
+
+	const BETA_Y_C0: &str = "2880510548434910442614869111285946610418075557776097505115113030863387119802265689270335925248001883102867749676243";
+	const BETA_Y_C1: &str = "2872114062532568575643729173452461066994643453813848213872870173636132169046691827766994227240293333106164659529444";
+
+	const GAMMA_X_C0: &str = "352701069587466618187139116011060144890029952792775240219908644239793785735715026873347600343865175952761926303160";
+	const GAMMA_X_C1: &str = "3059144344244213709971259814753781636986470325476647558659373206291635324768958432433509563104347017837885763365758";
+
+	const GAMMA_Y_C0: &str = "1985150602287291935568054521177171638300868978215655730859378665066344726373823718423869104263333984641494340347905";
+	const GAMMA_Y_C1: &str = "927553665492332455747201965776037880757740193453592970025027978793976877002675564980949289727957565575433344219582";
+
+	const DELTA_X_C0: &str = "1397400294785329269149248027941029918234275798984995986592789994215372037046682288247459925132482655775231958770596";
+	const DELTA_X_C1: &str = "3613651892030917982825314322568444757238870140073427833524931882395488683192849483836696311878674061447378155414322";
+
+	const DELTA_Y_C0: &str = "1454420022135097547429203607513890428221900276713697693498600894391966225725356692084173923746366083520797626734711";
+	const DELTA_Y_C1: &str = "2405306655262521121779739123612338596090750073099847349336699337941746231436397110773618181083856700942862129820841";
+
+	const IC_1_X: &str = "1036455169342233390855996586834520647962171510914420928779905953251272176363349160512017514969413843826714495861777";
+	const IC_1_Y: &str = "3225757548975669202743314017707154170140342810479555354528303455797434256089415962868447574306245203533729979725838";
+
+	const IC_2_X: &str = "2306767568146465899824632338747274961711075325739057886746993285987967410538122442295923393427774655394152050218360";
+	const IC_2_Y: &str = "1110686736735022843500989850943596336256510944040379817126812118843722981304262779720098389756327870602977197635083";
+
+	const PI_A_X: &str = "1547868284561670884744470829066291861753711715427536197016979117727657722537367306855408779073400007356480755992286";
+	const PI_A_Y: &str = "133377702143528739575377729631360601614088262416333931136172973337607317017542609318946667454426700160620492918070";
+
+	const PI_B_X_C0: &str = "3464179927623990666132434581669710292812271436336621246126774308069940684644800766694467705159555008883836001203558";
+	const PI_B_X_C1: &str = "2546213637341159614042232103352468058136925633034122854640067781563520449770334670597953179425897845578304324932654";
+	const PI_B_Y_C0: &str = "1727172519477219519750367293438016239792036515829871417520013243406611034907195588907593103368826194109213319586533";
+	const PI_B_Y_C1: &str = "1608709552654556864133663038831358765687167633553533833302139692670076873672935498325809703404354703063813928303923";
+
+	const PI_C_X: &str = "1754096103716358561952826128249523421393931227029702817784288419733418512708632119712049074095306383315056978720954";
+	const PI_C_Y: &str = "2834250288052560472935431224341595955480629006732618887386362957441961005785403404522081920080207211610068590548972";
+
+	#[test]
+	fn verification_key_from_correct_coordinates_is_ok() {
+		assert!(g1(&from_dec_string(ALPHA_X), &from_dec_string(ALPHA_Y)).is_some());
+		assert!(g2(&g2_coordinates(BETA_X_C0, BETA_X_C1, BETA_Y_C0, BETA_Y_C1)).is_some());
+		assert!(g2(&g2_coordinates(GAMMA_X_C0, GAMMA_X_C1, GAMMA_Y_C0, GAMMA_Y_C1)).is_some());
+		assert!(g2(&g2_coordinates(DELTA_X_C0, DELTA_X_C1, DELTA_Y_C0, DELTA_Y_C1)).is_some());
+		assert!(g1(&from_dec_string(IC_1_X), &from_dec_string(IC_1_Y)).is_some());
+		assert!(g1(&from_dec_string(IC_2_X), &from_dec_string(IC_2_Y)).is_some());
+		// swapped coordinates are not on the curve
+		assert!(g1(&from_dec_string(ALPHA_Y), &from_dec_string(ALPHA_X)).is_none());
+	}
+
+	#[test]
+	fn proof_from_correct_coordinates_is_ok() {
+		assert!(g1(&from_dec_string(PI_A_X), &from_dec_string(PI_A_Y)).is_some());
+		assert!(g2(&g2_coordinates(PI_B_X_C0, PI_B_X_C1, PI_B_Y_C0, PI_B_Y_C1)).is_some());
+		assert!(g1(&from_dec_string(PI_C_X), &from_dec_string(PI_C_Y)).is_some());
+	}
+
+	#[test]
+	fn verify_correct_proof() {
+		// circuit description https://github.com/iden3/circom/blob/7e59274c3e78674c2178766f9b8a4371c760ac3a/mkdocs/docs/getting-started/writing-circuits.md
+		// blog/data/verification_key.json, blog/data/proof.json and blog/data/public.json
+		assert!(verify(
+			&verification_key(2),
+			&proof(PI_A_X, PI_A_Y, PI_C_X, PI_C_Y),
+			[12.into()].into()
+		)
+		.unwrap())
+	}
+
+	#[test]
+	fn verify_incorrect_proof() {
+		// sample/verification_key.json and sample/proof.json with a and c swapped
+		assert!(!verify(
+			&verification_key(2),
+			&proof(PI_C_X, PI_C_Y, PI_A_X, PI_A_Y),
+			[33.into()].into()
+		)
+		.unwrap())
+	}
+
+	#[test]
+	fn verify_with_incorrect_ic_len() {
+		assert_eq!(
+			verify(
+				&verification_key(1),
+				&proof(PI_A_X, PI_A_Y, PI_C_X, PI_C_Y),
+				[33.into()].into()
+			)
+			.err()
+			.unwrap(),
+			VerificationError::InvalidVerificationKey
+		)
+	}
+
+	#[test]
+	fn compact_verification_key_verifies_like_the_prepared_one() {
+		let bytes = verification_key(2).to_bytes();
+		let vk = VerificationKey::from_bytes(&bytes).unwrap();
+
+		assert_eq!(vk.inner.alpha_g1_beta_g2, verification_key(2).inner.alpha_g1_beta_g2);
+		assert!(verify(&vk, &proof(PI_A_X, PI_A_Y, PI_C_X, PI_C_Y), [12.into()].into()).unwrap());
+		assert!(VerificationKey::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+		assert!(VerificationKey::from_bytes(&[bytes.as_slice(), &[0]].concat()).is_err());
+	}
+
+	#[test]
+	fn batch_verify_correct_proofs() {
+		let vk = verification_key(2);
+		let proof = proof(PI_A_X, PI_A_Y, PI_C_X, PI_C_Y);
+		let batch = vec![(&proof, vec![12.into()]); 3];
+		assert!(batch_verify(&vk, &batch).unwrap());
+		assert!(batch_verify(&vk, &batch[..1]).unwrap());
+	}
+
+	#[test]
+	fn batch_verify_rejects_an_incorrect_proof() {
+		let vk = verification_key(2);
+		let proof = proof(PI_A_X, PI_A_Y, PI_C_X, PI_C_Y);
+		let batch = vec![(&proof, vec![12.into()]), (&proof, vec![33.into()])];
+		assert!(!batch_verify(&vk, &batch).unwrap());
+		assert_eq!(
+			batch_verify(&vk, &[(&proof, vec![12.into(), 12.into()])]).err(),
+			Some(VerificationError::InvalidVerificationKey)
+		);
+	}
+
+	#[test]
+	fn batch_verify_incorrect_proofs_do_not_cancel_out() {
+		// c shifted by `d` in one proof and by `-d` in the other, the unweighted sum verifies
+		let vk = verification_key(2);
+		let proof = proof(PI_A_X, PI_A_Y, PI_C_X, PI_C_Y);
+		let d = G1Affine::generator();
+		let shifted = |c: G1Affine| GProof { inner: Proof { c, ..proof.inner.clone() } };
+		let up = shifted((proof.inner.c + d).into_affine());
+		let down = shifted((proof.inner.c - d).into_affine());
+		assert!(!verify(&vk, &up, [12.into()].into()).unwrap());
+		assert!(!batch_verify(&vk, &[(&up, vec![12.into()]), (&down, vec![12.into()])]).unwrap());
+	}
+
+	/// blog/data/verification_key.json with its first `ic_len` IC points
+	fn verification_key(ic_len: usize) -> VerificationKey {
+		let g2_point = |x_c0, x_c1, y_c0, y_c1| -> G2Affine {
+			g2(&g2_coordinates(x_c0, x_c1, y_c0, y_c1)).unwrap()
+		};
+		let ic: [G1Affine; 2] = [
+			g1(&from_dec_string(IC_1_X), &from_dec_string(IC_1_Y)).unwrap(),
+			g1(&from_dec_string(IC_2_X), &from_dec_string(IC_2_Y)).unwrap(),
+		];
+
+		VerificationKey::new(VerifyingKey {
+			alpha_g1: g1(&from_dec_string(ALPHA_X), &from_dec_string(ALPHA_Y)).unwrap(),
+			beta_g2: g2_point(BETA_X_C0, BETA_X_C1, BETA_Y_C0, BETA_Y_C1),
+			gamma_g2: g2_point(GAMMA_X_C0, GAMMA_X_C1, GAMMA_Y_C0, GAMMA_Y_C1),
+			delta_g2: g2_point(DELTA_X_C0, DELTA_X_C1, DELTA_Y_C0, DELTA_Y_C1),
+			gamma_abc_g1: ic[..ic_len].to_vec(),
+		})
+	}
+
+	fn proof(a_x: &str, a_y: &str, c_x: &str, c_y: &str) -> GProof {
+		GProof {
+			inner: Proof {
+				a: g1(&from_dec_string(a_x), &from_dec_string(a_y)).unwrap(),
+				b: g2(&g2_coordinates(PI_B_X_C0, PI_B_X_C1, PI_B_Y_C0, PI_B_Y_C1)).unwrap(),
+				c: g1(&from_dec_string(c_x), &from_dec_string(c_y)).unwrap(),
+			},
+		}
+	}
+
+	fn g2_coordinates(x_c0: &str, x_c1: &str, y_c0: &str, y_c1: &str) -> [[[u8; 48]; 2]; 3] {
+		[
+			[from_dec_string(x_c0), from_dec_string(x_c1)],
+			[from_dec_string(y_c0), from_dec_string(y_c1)],
+			[from_dec_string("1"), from_dec_string("0")],
+		]
+	}
+
+	fn from_dec_string(number: &str) -> [u8; 48] {
+		let mut bytes: [u8; 48] = [0; 48];
+		U256::from_dec_str(number).unwrap().to_big_endian(bytes.as_mut_slice());
+		bytes
+	}
+}
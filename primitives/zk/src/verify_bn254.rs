@@ -0,0 +1,152 @@
+use crate::{
+	deserialization::{Proof, VKey},
+	groth16::{self, point},
+	verify::{
+		GProofCreationError, PublicInputsCreationError, VerificationError,
+		VerificationKeyCreationError,
+	},
+};
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ff::{BigInt, PrimeField};
+use ark_groth16::VerifyingKey;
+use sp_std::prelude::*;
+
+/// Name snarkjs gives BN254 in `verification_key.json` and `proof.json`
+pub const SUPPORTED_CURVE: &str = "bn128";
+
+type Number = [u8; 48];
+
+/// Represents prepared Groth16 verification key over BN254
+pub type VerificationKey = groth16::VerificationKey<Bn254>;
+
+impl VerificationKey {
+	pub fn from_vkey(vk: &VKey) -> Result<Self, VerificationKeyCreationError> {
+		let point = || VerificationKeyCreationError::PointCreationError;
+		let mut gamma_abc_g1 = Vec::with_capacity(vk.ic.len());
+		for ic in vk.ic.iter() {
+			gamma_abc_g1.push(g1(&ic[0], &ic[1]).ok_or_else(point)?);
+		}
+		let vk = VerifyingKey::<Bn254> {
+			alpha_g1: g1(&vk.alpha[0], &vk.alpha[1]).ok_or_else(point)?,
+			beta_g2: g2(&vk.beta).ok_or_else(point)?,
+			gamma_g2: g2(&vk.gamma).ok_or_else(point)?,
+			delta_g2: g2(&vk.delta).ok_or_else(point)?,
+			gamma_abc_g1,
+		};
+
+		Ok(VerificationKey::new(vk))
+	}
+}
+
+/// Represents Groth16 proof over BN254
+pub type GProof = groth16::GProof<Bn254>;
+
+impl GProof {
+	pub fn from_proof(proof: &Proof) -> Result<Self, GProofCreationError> {
+		let point = || GProofCreationError::PointCreationError;
+		let a = g1(&proof.a[0], &proof.a[1]).ok_or_else(point)?;
+		let b = g2(&proof.b).ok_or_else(point)?;
+		let c = g1(&proof.c[0], &proof.c[1]).ok_or_else(point)?;
+
+		Ok(GProof { inner: ark_groth16::Proof { a, b, c } })
+	}
+}
+
+pub type PublicInputs = Vec<Fr>;
+
+/// Turns `U256` values into `Fr` representation, rejecting values outside of the scalar field
+pub fn prepare_public_inputs(
+	inputs: Vec<sp_core::U256>,
+) -> Result<PublicInputs, PublicInputsCreationError> {
+	inputs
+		.into_iter()
+		.map(|x| Fr::from_bigint(BigInt::new(x.0)).ok_or(PublicInputsCreationError::OutOfRange))
+		.collect()
+}
+
+/// Verifies given proof with given verification key and public inputs
+pub fn verify(
+	vk: &VerificationKey,
+	proof: &GProof,
+	inputs: PublicInputs,
+) -> Result<bool, VerificationError> {
+	groth16::verify(vk, proof, &inputs)
+}
+
+/// Verifies all proofs of `batch` with given verification key at once
+pub fn batch_verify(
+	vk: &VerificationKey,
+	batch: &[(&GProof, PublicInputs)],
+) -> Result<bool, VerificationError> {
+	groth16::batch_verify(vk, batch)
+}
+
+/// Reads big endian base field element, `None` if it is not reduced
+fn fq(number: &Number) -> Option<Fq> {
+	if number[..16].iter().any(|b| *b != 0) {
+		return None
+	}
+	let mut limbs = [0u64; 4];
+	for (limb, bytes) in limbs.iter_mut().zip(number[16..].rchunks_exact(8)) {
+		*limb = u64::from_be_bytes(bytes.try_into().ok()?);
+	}
+	Fq::from_bigint(BigInt::new(limbs))
+}
+
+fn g1(x: &Number, y: &Number) -> Option<G1Affine> {
+	point(fq(x)?, fq(y)?)
+}
+
+/// snarkjs lists G2 coordinates as `[[x_c0, x_c1], [y_c0, y_c1], [1, 0]]`
+fn g2(coordinates: &[[Number; 2]; 3]) -> Option<G2Affine> {
+	let x = Fq2::new(fq(&coordinates[0][0])?, fq(&coordinates[0][1])?);
+	let y = Fq2::new(fq(&coordinates[1][0])?, fq(&coordinates[1][1])?);
+	point(x, y)
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		deserialization::U256,
+		verify_bn254::{fq, g1, prepare_public_inputs},
+	};
+	use ark_bn254::{Fq, G1Affine};
+	use ark_ec::AffineRepr;
+	use ark_ff::PrimeField;
+
+	// BN254 base field modulus
+	const Q: &str = "21888242871839275222246405745257275088696311157297823662689037894645226208583";
+	// BN254 scalar field modulus
+	const R: &str = "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+
+	#[test]
+	fn field_element_must_be_reduced() {
+		assert!(fq(&from_dec_string(Q)).is_none());
+		assert_eq!(fq(&from_dec_string("7")), Some(Fq::from(7u64)));
+	}
+
+	#[test]
+	fn generator_is_valid_g1_point() {
+		let generator = G1Affine::generator();
+		let x = from_dec_string(&generator.x.into_bigint().to_string());
+		let y = from_dec_string(&generator.y.into_bigint().to_string());
+
+		assert_eq!(g1(&x, &y), Some(generator));
+		assert!(g1(&y, &x).is_none());
+	}
+
+	#[test]
+	fn public_inputs_outside_of_scalar_field_are_rejected() {
+		let r = sp_core::U256::from_dec_str(R).unwrap();
+
+		assert!(prepare_public_inputs(vec![r - 1]).is_ok());
+		assert!(prepare_public_inputs(vec![r]).is_err());
+		assert!(prepare_public_inputs(vec![sp_core::U256::MAX]).is_err());
+	}
+
+	fn from_dec_string(number: &str) -> [u8; 48] {
+		let mut bytes: [u8; 48] = [0; 48];
+		U256::from_dec_str(number).unwrap().to_big_endian(bytes.as_mut_slice());
+		bytes
+	}
+}
@@ -9,11 +9,13 @@ include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 use pallet_grandpa::AuthorityId as GrandpaId;
 use sp_api::impl_runtime_apis;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
-use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
+use sp_core::{crypto::KeyTypeId, OpaqueMetadata, U256};
 use sp_runtime::{
 	create_runtime_str, generic, impl_opaque_keys,
 	traits::{BlakeTwo256, Block as BlockT, IdentifyAccount, NumberFor, One, Verify, Zero},
-	transaction_validity::{TransactionSource, TransactionValidity},
+	transaction_validity::{
+		TransactionLongevity, TransactionPriority, TransactionSource, TransactionValidity,
+	},
 	ApplyExtrinsicResult, ExtrinsicInclusionMode, MultiSignature,
 };
 use sp_std::prelude::*;
@@ -47,7 +49,7 @@ pub use pallet_timestamp::Call as TimestampCall;
 use pallet_transaction_payment::{ConstFeeMultiplier, CurrencyAdapter, Multiplier};
 use primitives::{
 	currency::{CurrencyId, TokenSymbol},
-	Amount,
+	Amount, CircuitId,
 };
 
 #[cfg(any(feature = "std", test))]
@@ -110,12 +112,12 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// The version of the runtime specification. A full node will not attempt to use its native
 	//   runtime in substitute for the on-chain Wasm runtime unless all of `spec_name`,
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
-	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
+	// This value started at 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
 	//   the compatible custom types.
-	spec_version: 100,
+	spec_version: 101,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 2,
 	state_version: 1,
 };
 
@@ -260,37 +262,86 @@ impl pallet_sudo::Config for Runtime {
 
 impl pallet_swap::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
-	type WeightInfo = ();
+	type WeightInfo = pallet_swap::weights::SubstrateWeight<Runtime>;
 	type Currency = Currencies;
 }
 
 impl pallet_otp::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
-	type WeightInfo = ();
+	type WeightInfo = pallet_otp::weights::SubstrateWeight<Runtime>;
 	type MaxPublicInputsLength = ConstU32<3000>;
 	type MaxProofLength = ConstU32<5000>;
-	type MaxVerificationKeyLength = ConstU32<5000>;
+	type VerificationKeys = VkRegistry;
+	type OtpCircuitId = OtpCircuitId;
 	type TimeProvider = pallet_timestamp::Pallet<Runtime>;
 }
 
+parameter_types! {
+	/// Registry id of the mixer `Withdraw(MixerTreeDepth)` circuit, the pool of the deposits made
+	/// before pools verifies with it.
+	pub const WithdrawCircuitId: CircuitId = 0;
+	/// Registry id of the otp circuit.
+	pub const OtpCircuitId: CircuitId = 1;
+	/// Registry id of the Poseidon mixer `Withdraw(MixerTreeDepth)` circuit, `circom/mixer-psd`.
+	pub const PoseidonWithdrawCircuitId: CircuitId = 2;
+	/// Proofs against a replaced key keep being accepted for a day.
+	pub const VerificationKeyGracePeriod: BlockNumber = DAYS;
+}
+
+impl pallet_vk_registry::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type WeightInfo = pallet_vk_registry::weights::SubstrateWeight<Runtime>;
+	type RegisterOrigin = EnsureRoot<AccountId>;
+	type MaxVerificationKeyLength = ConstU32<5000>;
+	type MaxUsableVersions = ConstU32<4>;
+	type DeprecationGracePeriod = VerificationKeyGracePeriod;
+}
+
 parameter_types! {
 	pub const MixerPalletId: PalletId = PalletId(*b"py/mixer");
 	pub const UNITS: Balance = 1_000_000_000_000;
+	/// Denomination of the pool existing deposits are moved into by the v1 mixer migration.
 	pub const MixerBalance: Balance = 1_000_000_000_000_000;
+	/// Nullifier hashes and blacklisted accounts the v1 mixer migration moves at most, each.
+	pub const MixerMigrationMaxEntries: u32 = 10_000;
+	/// Depth the `circom` mixer circuits are compiled for, pools proven with them are created
+	/// with it.
+	pub const MixerTreeDepth: u32 = 8;
+	/// Pools may be created with trees of up to 2^20 notes.
+	pub const MixerMaxTreeDepth: u32 = 20;
+	pub const MixerRootHistorySize: u32 = 30;
+	pub const MixerUnsignedPriority: TransactionPriority = TransactionPriority::MAX / 2;
+	pub const MixerUnsignedLongevity: TransactionLongevity = 10;
+	/// Tree depths of the circuits with keys registered before the registry recorded them.
+	pub VerificationKeyTreeDepths: Vec<(CircuitId, u32)> = sp_std::vec![
+		(WithdrawCircuitId::get(), MixerTreeDepth::get()),
+		(PoseidonWithdrawCircuitId::get(), MixerTreeDepth::get()),
+		(OtpCircuitId::get(), 7),
+	];
 }
 
 /// Configure the pallet-mixer in pallets/mixer.
 impl pallet_mixer::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
-	type WeightInfo = ();
+	type WeightInfo = pallet_mixer::weights::SubstrateWeight<Runtime>;
 	type MaxPublicInputsLength = ConstU32<3000>;
 	type MaxProofLength = ConstU32<5000>;
-	type MaxVerificationKeyLength = ConstU32<5000>;
 	type PalletId = MixerPalletId;
-	type Currency = Balances;
-	type MixerBalance = MixerBalance;
+	type Currency = Currencies;
+	type GetNativeCurrencyId = NativeCurrencyId;
+	type CreatePoolOrigin = EnsureRoot<AccountId>;
+	type UnsignedPriority = MixerUnsignedPriority;
+	type UnsignedLongevity = MixerUnsignedLongevity;
+	type MaxTreeDepth = MixerMaxTreeDepth;
+	type RootHistorySize = MixerRootHistorySize;
+	type MaxBatchWithdraws = ConstU32<64>;
+	type BlackListOrigin = EnsureRoot<AccountId>;
+	type MaxBlackListReasonLength = ConstU32<256>;
+	type MaxBlackListBatch = ConstU32<100>;
+	type VerificationKeys = VkRegistry;
 	type SwapApi = Swap;
 	type OtpApi = Otp;
+	type TimeProvider = pallet_timestamp::Pallet<Runtime>;
 }
 
 parameter_types! {
@@ -378,6 +429,7 @@ construct_runtime!(
 		Currencies: pallet_currencies,
 		Otp: pallet_otp,
 		Swap: pallet_swap,
+		VkRegistry: pallet_vk_registry,
 		// Include the custom logic from the pallet-template in the runtime.
 		Mixer: pallet_mixer,
 	}
@@ -399,13 +451,24 @@ pub type SignedExtra = (
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
 	pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+	pallet_mixer::CheckMixerCall<Runtime>,
 );
 
 /// All migrations of the runtime, aside from the ones declared in the pallets.
 ///
 /// This can be a tuple of types, each implementing `OnRuntimeUpgrade`.
 #[allow(unused_parens)]
-type Migrations = ();
+type Migrations = (
+	pallet_mixer::migrations::v1::MigrateToV1<
+		Runtime,
+		MixerBalance,
+		WithdrawCircuitId,
+		MixerMigrationMaxEntries,
+	>,
+	pallet_otp::migrations::v1::MigrateToV1<Runtime>,
+	pallet_vk_registry::migrations::v1::MigrateToV1<Runtime>,
+	pallet_vk_registry::migrations::v2::MigrateToV2<Runtime, VerificationKeyTreeDepths>,
+);
 
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic =
@@ -430,7 +493,10 @@ mod benches {
 		[pallet_balances, Balances]
 		[pallet_timestamp, Timestamp]
 		[pallet_sudo, Sudo]
-		[pallet_template, TemplateModule]
+		[pallet_mixer, Mixer]
+		[pallet_otp, Otp]
+		[pallet_swap, Swap]
+		[pallet_vk_registry, VkRegistry]
 	);
 }
 
@@ -579,6 +645,30 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_mixer_rpc_runtime_api::MixerApi<Block> for Runtime {
+		fn get_merkle_path(
+			pool_id: pallet_mixer::PoolId,
+			leaf_index: u32,
+		) -> Option<pallet_mixer::merkle_tree::MerklePath> {
+			pallet_mixer::get_merkle_path::<Runtime>(pool_id, leaf_index)
+		}
+		fn get_leaf_index(pool_id: pallet_mixer::PoolId, commitment: U256) -> Option<u32> {
+			pallet_mixer::leaf_index::<Runtime>(pool_id, commitment)
+		}
+		fn is_known_root(pool_id: pallet_mixer::PoolId, root: U256) -> bool {
+			pallet_mixer::is_known_root::<Runtime>(pool_id, root)
+		}
+		fn is_spent(pool_id: pallet_mixer::PoolId, nullifier_hash: U256) -> bool {
+			pallet_mixer::is_spent::<Runtime>(pool_id, nullifier_hash)
+		}
+		fn current_root(pool_id: pallet_mixer::PoolId) -> Option<U256> {
+			pallet_mixer::merkle_root::<Runtime>(pool_id)
+		}
+		fn leaf_count(pool_id: pallet_mixer::PoolId) -> u32 {
+			pallet_mixer::NextIndex::<Runtime>::get(pool_id)
+		}
+	}
+
 	impl pallet_transaction_payment_rpc_runtime_api::TransactionPaymentCallApi<Block, Balance, RuntimeCall>
 		for Runtime
 	{
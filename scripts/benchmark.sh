@@ -0,0 +1,20 @@
+#!/usr/bin/env bash
+# This script is meant to be run on Unix/Linux based systems
+set -e
+
+echo "*** Benchmarking mixer, otp, swap and vk-registry pallets"
+
+cd $(dirname ${BASH_SOURCE[0]})/..
+
+cargo build --release -p node-template --features runtime-benchmarks
+
+for pallet in mixer otp swap vk-registry; do
+  ./target/release/node-template benchmark pallet \
+    --chain dev \
+    --pallet pallet_${pallet//-/_} \
+    --extrinsic '*' \
+    --steps 50 \
+    --repeat 20 \
+    --wasm-execution compiled \
+    --output pallets/$pallet/src/weights.rs
+done